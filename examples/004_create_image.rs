@@ -1,12 +1,14 @@
 use image::{ImageBuffer, Rgba};
-use vulkan_engine::{AbstractEngine, ComputeEngine};
+use vulkan_engine::{
+    convert_rgba8_pixels, AbstractEngine, ComputeEngine, FormatUsage, COLOR_FORMAT_PREFERENCES,
+};
 use vulkano::{
     buffer::{Buffer, BufferCreateInfo, BufferUsage},
     command_buffer::{
         allocator::{StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo},
         AutoCommandBufferBuilder, ClearColorImageInfo, CopyImageToBufferInfo,
     },
-    format::{ClearColorValue, Format},
+    format::ClearColorValue,
     image::{ImageDimensions, StorageImage},
     memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
 };
@@ -36,6 +38,15 @@ pub fn main() {
     );
 
     // Prepare Image
+    // StorageImage::new also makes the image usable as color attachment and sampled image
+    let format = compute_engine.get_logical_device().select_format(
+        &COLOR_FORMAT_PREFERENCES,
+        &[
+            FormatUsage::Storage,
+            FormatUsage::ColorAttachment,
+            FormatUsage::Sampled,
+        ],
+    );
     let image = StorageImage::new(
         &memory_allocator,
         ImageDimensions::Dim2d {
@@ -43,7 +54,7 @@ pub fn main() {
             height: 1024,
             array_layers: 1,
         },
-        Format::R8G8B8A8_UNORM,
+        format,
         Some(compute_engine.get_logical_device().get_queue_family_index()),
     )
     .expect("failed to create image");
@@ -88,10 +99,11 @@ pub fn main() {
     });
 
     // Assert results
-    let buffer_content = output_buffer.read().unwrap();
+    let mut buffer_content = output_buffer.read().unwrap().to_vec();
+    convert_rgba8_pixels(format, &mut buffer_content);
 
     log::debug!("Convert Texel to Image");
-    let image = ImageBuffer::<Rgba<u8>, _>::from_raw(1024, 1024, &buffer_content[..]).unwrap();
+    let image = ImageBuffer::<Rgba<u8>, _>::from_raw(1024, 1024, buffer_content).unwrap();
 
     log::debug!("Save Image as PNG");
     image.save("004_create_image.png").unwrap();
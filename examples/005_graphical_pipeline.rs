@@ -1,7 +1,10 @@
 use std::time::Instant;
 
 use image::{ImageBuffer, Rgba};
-use vulkan_engine::{AbstractEngine, ComputeEngine, SVertex};
+use vulkan_engine::{
+    convert_rgba8_pixels, AbstractEngine, ComputeEngine, FormatUsage, SVertex,
+    COLOR_FORMAT_PREFERENCES,
+};
 use vulkano::{
     buffer::{Buffer, BufferCreateInfo, BufferUsage},
     command_buffer::{
@@ -9,7 +12,6 @@ use vulkano::{
         AutoCommandBufferBuilder, CommandBufferUsage, CopyImageToBufferInfo, RenderPassBeginInfo,
         SubpassContents,
     },
-    format::Format,
     image::{view::ImageView, ImageDimensions, StorageImage},
     memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
     pipeline::{
@@ -110,6 +112,16 @@ pub fn main() {
         depth_range: 0.0..1.0,
     };
 
+    // Select the image format, StorageImage::new also makes the image usable as storage and sampled image
+    let format = compute_engine.get_logical_device().select_format(
+        &COLOR_FORMAT_PREFERENCES,
+        &[
+            FormatUsage::Storage,
+            FormatUsage::ColorAttachment,
+            FormatUsage::Sampled,
+        ],
+    );
+
     // Create RenderPass (prepare "rendering mode")
     // Defines the format and way of the image to be rendered
     let render_pass = single_pass_renderpass!(
@@ -118,7 +130,7 @@ pub fn main() {
             color: {
                 load: Clear,    // Tells the GPU to clear the image when entering RenderPass
                 store: Store,   // Tells the GPU to store any outputs to our image
-                format: Format::R8G8B8A8_UNORM,
+                format: format,
                 samples: 1,
             }
         },
@@ -137,7 +149,7 @@ pub fn main() {
             height: 1024,
             array_layers: 1,
         },
-        Format::R8G8B8A8_UNORM,
+        format,
         Some(compute_engine.get_logical_device().get_queue_family_index()),
     )
     .unwrap();
@@ -219,8 +231,9 @@ pub fn main() {
     #[cfg(debug_assertions)]
     let start = Instant::now();
 
-    let buffer_content = output_buffer.read().unwrap();
-    let image = ImageBuffer::<Rgba<u8>, _>::from_raw(1024, 1024, &buffer_content[..]).unwrap();
+    let mut buffer_content = output_buffer.read().unwrap().to_vec();
+    convert_rgba8_pixels(format, &mut buffer_content);
+    let image = ImageBuffer::<Rgba<u8>, _>::from_raw(1024, 1024, buffer_content).unwrap();
     image.save("005_graphical_pipeline.png").unwrap();
 
     #[cfg(debug_assertions)]
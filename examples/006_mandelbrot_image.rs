@@ -1,7 +1,7 @@
 use std::time::Instant;
 
 use image::{ImageBuffer, Rgba};
-use vulkan_engine::{AbstractEngine, ComputeEngine};
+use vulkan_engine::{AbstractEngine, ComputeEngine, FormatUsage};
 use vulkano::{
     buffer::{Buffer, BufferCreateInfo, BufferUsage},
     command_buffer::{AutoCommandBufferBuilder, CopyImageToBufferInfo},
@@ -11,7 +11,7 @@ use vulkano::{
     format::Format,
     image::{view::ImageView, ImageDimensions, StorageImage},
     memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
-    pipeline::{ComputePipeline, Pipeline, PipelineBindPoint},
+    pipeline::{Pipeline, PipelineBindPoint},
 };
 
 mod shader {
@@ -39,6 +39,15 @@ pub fn main() {
         StandardDescriptorSetAllocator::new(compute_engine.get_logical_device().get_device());
 
     // Prepare Image
+    // The shader declares the image as `rgba8`, so R8G8B8A8_UNORM is the only usable storage format
+    let format = compute_engine.get_logical_device().select_format(
+        &[Format::R8G8B8A8_UNORM],
+        &[
+            FormatUsage::Storage,
+            FormatUsage::ColorAttachment,
+            FormatUsage::Sampled,
+        ],
+    );
     let image = StorageImage::new(
         &memory_allocator,
         ImageDimensions::Dim2d {
@@ -46,7 +55,7 @@ pub fn main() {
             height: 1024,
             array_layers: 1,
         },
-        Format::R8G8B8A8_UNORM,
+        format,
         Some(compute_engine.get_logical_device().get_queue_family_index()),
     )
     .unwrap();
@@ -72,14 +81,18 @@ pub fn main() {
         .expect("failed to create shader module");
 
     // Prepare Compute Pipeline
-    let compute_pipeline = ComputePipeline::new(
-        compute_engine.get_logical_device().get_device(),
-        shader.entry_point("main").unwrap(),
-        &(),
-        None,
-        |_| {},
-    )
-    .expect("failed to create compute pipeline");
+    let compute_pipeline = compute_engine.create_compute_pipeline::<shader::PushConstants, _>(
+        shader,
+        &shader::SpecializationConstants {
+            max_iterations: 200,
+        },
+    );
+
+    // Prepare Push Constants
+    let push_constants = shader::PushConstants {
+        center: [-1.0, 0.0],
+        zoom: 1.0,
+    };
 
     // Prepare Descriptor Set
     let layout = compute_pipeline.layout().set_layouts().get(0).unwrap();
@@ -107,6 +120,7 @@ pub fn main() {
                 0,
                 set.clone(),
             )
+            .push_constants(compute_pipeline.layout().clone(), 0, push_constants)
             .dispatch([1024 / 8, 1024 / 8, 1])
             .unwrap()
             .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
@@ -1,6 +1,12 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use vulkan_engine::{AbstractEngine, GraphicalEngine, LogicalDevice, SVertex};
+use vulkan_engine::{
+    AbstractEngine, ActionMap, GameLoop, GameLoopOptions, GraphicalEngine, Input, LogicalDevice,
+    SVertex, WindowConfig,
+};
 use vulkano::{
     buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer},
     command_buffer::{
@@ -21,12 +27,10 @@ use vulkano::{
     swapchain::{self, AcquireError, SwapchainPresentInfo},
     sync::{self, FlushError, GpuFuture},
 };
-use vulkano_win::VkSurfaceBuild;
 use winit::{
     dpi::{PhysicalSize, Pixel},
     event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
 };
 
 mod shader_vertex {
@@ -115,14 +119,16 @@ pub fn main() {
     // Vulkan instance
     let instance = GraphicalEngine::make_instance();
 
-    // Window
+    // Window and Engine
     let event_loop = EventLoop::new();
-    let surface = WindowBuilder::new()
-        .build_vk_surface(&event_loop, instance.clone()) // Not all Winit versions are compatible with vulkano-win apparently. Make sure they work together or imports won't work!
-        .expect("failed to create window surface");
-
-    // Engine
-    let graphical_engine = Arc::new(Mutex::new(GraphicalEngine::new(instance, surface.clone())));
+    let graphical_engine = Arc::new(Mutex::new(GraphicalEngine::with_window_config(
+        instance,
+        &event_loop,
+        &WindowConfig {
+            title: "007 - Basic Triangle".to_string(),
+            ..Default::default()
+        },
+    )));
 
     // Memory Allocator
     let memory_allocator = StandardMemoryAllocator::new_default(
@@ -184,10 +190,11 @@ pub fn main() {
     let pipeline = Mutex::new(create_pipeline(
         vertex_shader.clone(),
         fragment_shader.clone(),
-        PhysicalSize {
-            width: 1024.0,
-            height: 1024.0,
-        },
+        graphical_engine
+            .lock()
+            .unwrap()
+            .get_winit_window()
+            .inner_size(),
         render_pass.clone(),
         graphical_engine.lock().unwrap().get_logical_device(),
     ));
@@ -208,13 +215,37 @@ pub fn main() {
         vertex_buffer.clone(),
     );
 
+    // Input
+    let mut input = Input::new(
+        ActionMap::load(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/examples/007_basic_triangle.actions"
+        ))
+        .expect("failed to load action map"),
+    );
+
+    // Frame timing, capped at 60 FPS with the statistics logged every few seconds
+    let mut game_loop = GameLoop::new(GameLoopOptions {
+        frame_cap: Some(60.0),
+        log_interval: Some(Duration::from_secs(5)),
+        ..Default::default()
+    });
+
     // Window variables
     let mut window_resize_request: Option<PhysicalSize<u32>> = None;
     let mut recreate_swapchain = false;
 
     // Hijack thread and open window
     event_loop.run(move |event, _, control_flow| {
-        *control_flow = winit::event_loop::ControlFlow::Wait;
+        *control_flow = game_loop.get_control_flow();
+
+        input.on_event(&event);
+        if let Event::WindowEvent { window_id, event } = &event {
+            graphical_engine
+                .lock()
+                .unwrap()
+                .on_window_event(*window_id, event);
+        }
 
         match event {
             Event::WindowEvent {
@@ -234,11 +265,27 @@ pub fn main() {
                 window_resize_request = Some(new_size);
             }
             Event::RedrawEventsCleared => {
+                if game_loop.begin_frame().is_none() {
+                    // Frame cap not reached yet
+                    return;
+                }
+
+                if input.is_action_pressed("exit") {
+                    *control_flow = ControlFlow::Exit;
+                    graphical_engine.lock().unwrap().kill();
+                    return;
+                }
+                if input.is_action_pressed("toggle_fullscreen") {
+                    graphical_engine.lock().unwrap().toggle_fullscreen();
+                }
+                input.end_frame();
+
                 log::debug!("RedrawEventsCleared");
                 log::debug!("Resized: {:?}", window_resize_request);
                 log::debug!("Recreate: {}", recreate_swapchain);
 
-                if window_resize_request.is_some() || recreate_swapchain {
+                let swap_chain_outdated = graphical_engine.lock().unwrap().is_swap_chain_outdated();
+                if window_resize_request.is_some() || recreate_swapchain || swap_chain_outdated {
                     match graphical_engine
                         .lock()
                         .unwrap()
@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use vulkan_engine::{
+    egui::{self, Slider},
+    AbstractEngine, EguiRenderer, FormatUsage, GraphicalEngine,
+};
+use vulkano::{
+    command_buffer::{
+        AutoCommandBufferBuilder, CommandBufferUsage, RenderPassBeginInfo, SubpassContents,
+    },
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    format::Format,
+    image::{view::ImageView, ImageCreateFlags, ImageDimensions, ImageUsage, StorageImage},
+    memory::allocator::StandardMemoryAllocator,
+    pipeline::{Pipeline, PipelineBindPoint},
+    sampler::Filter,
+    swapchain::{self, AcquireError, SwapchainPresentInfo},
+    sync::{self, FlushError, GpuFuture},
+};
+use vulkano_win::VkSurfaceBuild;
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+mod shader {
+    vulkano_shaders::shader! {ty: "compute", path: "shaders/006_mandelbrot_image.comp"}
+}
+
+const IMAGE_SIZE: u32 = 1024;
+
+pub fn main() {
+    env_logger::init();
+    log::info!(
+        "Logger initialized at max level set to {}",
+        log::max_level()
+    );
+    log::info!("008 - egui Mandelbrot");
+
+    // Vulkan instance
+    let instance = GraphicalEngine::make_instance();
+
+    // Window
+    let event_loop = EventLoop::new();
+    let surface = WindowBuilder::new()
+        .with_title("008 - egui Mandelbrot")
+        .build_vk_surface(&event_loop, instance.clone())
+        .expect("failed to create window surface");
+
+    // Engine
+    let mut graphical_engine = GraphicalEngine::new(instance, surface);
+    let device = graphical_engine.get_logical_device().get_device();
+
+    // Allocators
+    let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
+    let descriptor_set_allocator = StandardDescriptorSetAllocator::new(device.clone());
+
+    // Mandelbrot image, written by the compute shader and sampled by egui
+    // The shader declares the image as `rgba8`, so R8G8B8A8_UNORM is the only usable storage format
+    let format = graphical_engine.get_logical_device().select_format(
+        &[Format::R8G8B8A8_UNORM],
+        &[FormatUsage::Storage, FormatUsage::Sampled],
+    );
+    let image = StorageImage::with_usage(
+        &memory_allocator,
+        ImageDimensions::Dim2d {
+            width: IMAGE_SIZE,
+            height: IMAGE_SIZE,
+            array_layers: 1,
+        },
+        format,
+        ImageUsage::STORAGE | ImageUsage::SAMPLED,
+        ImageCreateFlags::empty(),
+        None,
+    )
+    .expect("failed to create image");
+    let image_view = ImageView::new_default(image).unwrap();
+
+    // Shader
+    let shader = shader::load(device.clone()).expect("failed to create shader module");
+
+    // Registered before the image view is moved into the pipeline closure below
+    let texture_image_view = image_view.clone();
+
+    // The maximal iterations are a specialization constant, hence the pipeline is recreated whenever they change
+    let create_pipeline_and_set = move |graphical_engine: &GraphicalEngine, max_iterations: u32| {
+        let pipeline = graphical_engine.create_compute_pipeline::<shader::PushConstants, _>(
+            shader.clone(),
+            &shader::SpecializationConstants { max_iterations },
+        );
+
+        let set = PersistentDescriptorSet::new(
+            &descriptor_set_allocator,
+            pipeline.layout().set_layouts()[0].clone(),
+            [WriteDescriptorSet::image_view(0, image_view.clone())],
+        )
+        .expect("failed to create descriptor set");
+
+        (pipeline, set)
+    };
+
+    // Parameters tweaked by the UI
+    let mut center = [-1.0f32, 0.0];
+    let mut zoom = 1.0f32;
+    let mut max_iterations = 200u32;
+    let mut current_max_iterations = max_iterations;
+    let (mut compute_pipeline, mut set) =
+        create_pipeline_and_set(&graphical_engine, max_iterations);
+
+    // RenderPass and Framebuffers, only used to clear the window before the UI is drawn on top
+    let render_pass = graphical_engine.create_render_pass();
+    let mut frame_buffers = graphical_engine.create_frame_buffers(render_pass.clone());
+
+    // UI
+    let mut egui_renderer = EguiRenderer::new(&graphical_engine, memory_allocator);
+    let texture_id = egui_renderer.register_user_texture(texture_image_view, Filter::Linear);
+
+    let mut recreate_swapchain = false;
+
+    // Hijack thread and open window
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+
+        match event {
+            Event::WindowEvent { event, .. } => {
+                if egui_renderer.on_window_event(&event).consumed {
+                    return;
+                }
+
+                match event {
+                    WindowEvent::CloseRequested => {
+                        *control_flow = ControlFlow::Exit;
+
+                        // Kills the engine (and this main thread) and frees resources.
+                        // Otherwise, SEGFAULT's will occur on exit.
+                        graphical_engine.kill();
+                    }
+                    WindowEvent::Resized(_) => {
+                        recreate_swapchain = true;
+                    }
+                    _ => (),
+                }
+            }
+            Event::RedrawEventsCleared => {
+                if recreate_swapchain {
+                    match graphical_engine.recreate_swap_chain_and_images(render_pass.clone()) {
+                        Some(new_frame_buffers) => {
+                            frame_buffers = new_frame_buffers;
+                            egui_renderer.resize(&graphical_engine);
+                            recreate_swapchain = false;
+                        }
+                        None => {
+                            log::error!("Failed recreating SwapChain! Retrying ...");
+                            return;
+                        }
+                    }
+                }
+
+                // Build the UI
+                egui_renderer.run(&graphical_engine, |context| {
+                    egui::SidePanel::left("parameters").show(context, |ui| {
+                        ui.heading("Mandelbrot");
+                        ui.add(Slider::new(&mut center[0], -2.0..=1.0).text("Center X"));
+                        ui.add(Slider::new(&mut center[1], -1.5..=1.5).text("Center Y"));
+                        ui.add(
+                            Slider::new(&mut zoom, 0.5..=10000.0)
+                                .logarithmic(true)
+                                .text("Zoom"),
+                        );
+                        ui.add(Slider::new(&mut max_iterations, 10..=2000).text("Iterations"));
+                    });
+
+                    egui::CentralPanel::default().show(context, |ui| {
+                        let size = ui.available_size().min_elem();
+                        ui.image((texture_id, egui::vec2(size, size)));
+                    });
+                });
+
+                if max_iterations != current_max_iterations {
+                    (compute_pipeline, set) =
+                        create_pipeline_and_set(&graphical_engine, max_iterations);
+                    current_max_iterations = max_iterations;
+                }
+
+                let (image_i, suboptimal, acquire_future) =
+                    match swapchain::acquire_next_image(graphical_engine.get_swap_chain(), None) {
+                        Ok(r) => r,
+                        Err(AcquireError::OutOfDate) => {
+                            recreate_swapchain = true;
+                            return;
+                        }
+                        Err(e) => panic!("Failed to acquire next image: {:?}", e),
+                    };
+
+                if suboptimal {
+                    recreate_swapchain = true;
+                }
+
+                let mut builder = AutoCommandBufferBuilder::primary(
+                    &graphical_engine.get_command_buffer_allocator(),
+                    graphical_engine
+                        .get_logical_device()
+                        .get_queue_family_index(),
+                    CommandBufferUsage::OneTimeSubmit,
+                )
+                .unwrap();
+
+                // Render the Mandelbrot set with the current parameters
+                builder
+                    .bind_pipeline_compute(compute_pipeline.clone())
+                    .bind_descriptor_sets(
+                        PipelineBindPoint::Compute,
+                        compute_pipeline.layout().clone(),
+                        0,
+                        set.clone(),
+                    )
+                    .push_constants(
+                        compute_pipeline.layout().clone(),
+                        0,
+                        shader::PushConstants { center, zoom },
+                    )
+                    .dispatch([IMAGE_SIZE / 8, IMAGE_SIZE / 8, 1])
+                    .unwrap();
+
+                // Clear the window, the UI is drawn on top
+                builder
+                    .begin_render_pass(
+                        RenderPassBeginInfo {
+                            clear_values: vec![Some([0.1, 0.1, 0.1, 1.0].into())],
+                            ..RenderPassBeginInfo::framebuffer(
+                                frame_buffers[image_i as usize].clone(),
+                            )
+                        },
+                        SubpassContents::Inline,
+                    )
+                    .unwrap()
+                    .end_render_pass()
+                    .unwrap();
+
+                egui_renderer.record(&mut builder, image_i);
+
+                let execution = sync::now(device.clone())
+                    // Wait for the image to actually become available
+                    .join(acquire_future)
+                    .then_execute(
+                        graphical_engine.get_logical_device().get_first_queue(),
+                        builder.build().unwrap(),
+                    )
+                    .unwrap()
+                    // Finish drawing and present the image on the swapchain
+                    .then_swapchain_present(
+                        graphical_engine.get_logical_device().get_first_queue(),
+                        SwapchainPresentInfo::swapchain_image_index(
+                            graphical_engine.get_swap_chain(),
+                            image_i,
+                        ),
+                    )
+                    .then_signal_fence_and_flush();
+
+                match execution {
+                    Ok(future) => future.wait(None).unwrap(), // Wait for the GPU to finish
+                    Err(FlushError::OutOfDate) => {
+                        // Something did go wrong, recreate swapchain
+                        recreate_swapchain = true;
+                    }
+                    Err(e) => {
+                        // Unknown error
+                        log::error!("Failed to flush future: {:?}", e);
+                    }
+                }
+            }
+            _ => (),
+        }
+    });
+}
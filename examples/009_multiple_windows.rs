@@ -0,0 +1,154 @@
+use std::{collections::HashMap, sync::Arc};
+
+use vulkan_engine::{AbstractEngine, GraphicalEngine, WindowConfig};
+use vulkano::{
+    command_buffer::{
+        AutoCommandBufferBuilder, CommandBufferUsage, RenderPassBeginInfo, SubpassContents,
+    },
+    render_pass::{Framebuffer, RenderPass},
+};
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowId,
+};
+
+/// Per window rendering state, each window may have a different `Swapchain` format.
+struct WindowTarget {
+    render_pass: Arc<RenderPass>,
+    frame_buffers: Vec<Arc<Framebuffer>>,
+    clear_color: [f32; 4],
+}
+
+pub fn main() {
+    env_logger::init();
+    log::info!(
+        "Logger initialized at max level set to {}",
+        log::max_level()
+    );
+    log::info!("009 - Multiple Windows");
+
+    // Vulkan instance
+    let instance = GraphicalEngine::make_instance();
+
+    // Primary window and Engine
+    let event_loop = EventLoop::new();
+    let mut graphical_engine = GraphicalEngine::with_window_config(
+        instance,
+        &event_loop,
+        &WindowConfig {
+            title: "009 - Primary Window".to_string(),
+            ..Default::default()
+        },
+    );
+
+    // Secondary windows, sharing the device of the primary one
+    for index in 1..3 {
+        graphical_engine.add_window(
+            &event_loop,
+            &WindowConfig {
+                title: format!("009 - Secondary Window {}", index),
+                size: [512, 384],
+                ..Default::default()
+            },
+        );
+    }
+
+    // RenderPasses and Framebuffers per window
+    let clear_colors = [
+        [0.1, 0.1, 0.1, 1.0],
+        [0.6, 0.2, 0.2, 1.0],
+        [0.2, 0.2, 0.6, 1.0],
+    ];
+    let mut targets: HashMap<WindowId, WindowTarget> = graphical_engine
+        .get_engine_windows()
+        .iter()
+        .zip(clear_colors)
+        .map(|(window, clear_color)| {
+            let render_pass = graphical_engine
+                .create_render_pass_with_format(window.get_swap_chain().image_format());
+
+            (
+                window.get_id().unwrap(),
+                WindowTarget {
+                    frame_buffers: window.create_frame_buffers(render_pass.clone()),
+                    render_pass,
+                    clear_color,
+                },
+            )
+        })
+        .collect();
+
+    let primary_window_id = graphical_engine.get_primary_window().get_id().unwrap();
+
+    // Hijack thread and open windows
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+
+        match event {
+            Event::WindowEvent { window_id, event } => {
+                graphical_engine.on_window_event(window_id, &event);
+
+                if event == WindowEvent::CloseRequested {
+                    if window_id == primary_window_id {
+                        *control_flow = ControlFlow::Exit;
+
+                        // Kills the engine (and this main thread) and frees resources.
+                        // Otherwise, SEGFAULT's will occur on exit.
+                        graphical_engine.kill();
+                    } else {
+                        graphical_engine.remove_window(window_id);
+                        targets.remove(&window_id);
+                    }
+                }
+            }
+            Event::RedrawEventsCleared => {
+                let command_buffer_allocator = graphical_engine.get_command_buffer_allocator();
+                let queue_family_index = graphical_engine
+                    .get_logical_device()
+                    .get_queue_family_index();
+
+                // Each window is recreated, rendered and presented on its own
+                for window in graphical_engine.get_engine_windows_mut() {
+                    let target = targets.get_mut(&window.get_id().unwrap()).unwrap();
+
+                    if window.is_swap_chain_outdated() {
+                        match window.recreate_swap_chain_and_images(target.render_pass.clone()) {
+                            Some(frame_buffers) => target.frame_buffers = frame_buffers,
+                            // Minimized, skip the window this frame
+                            None => continue,
+                        }
+                    }
+
+                    let Some((image_i, acquire_future)) = window.acquire_next_image() else {
+                        continue;
+                    };
+
+                    let mut builder = AutoCommandBufferBuilder::primary(
+                        &command_buffer_allocator,
+                        queue_family_index,
+                        CommandBufferUsage::OneTimeSubmit,
+                    )
+                    .unwrap();
+
+                    builder
+                        .begin_render_pass(
+                            RenderPassBeginInfo {
+                                clear_values: vec![Some(target.clear_color.into())],
+                                ..RenderPassBeginInfo::framebuffer(
+                                    target.frame_buffers[image_i as usize].clone(),
+                                )
+                            },
+                            SubpassContents::Inline,
+                        )
+                        .unwrap()
+                        .end_render_pass()
+                        .unwrap();
+
+                    window.present(acquire_future, builder.build().unwrap(), image_i);
+                }
+            }
+            _ => (),
+        }
+    });
+}
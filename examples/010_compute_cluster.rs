@@ -0,0 +1,105 @@
+use vulkan_engine::{AbstractEngine, ComputeCluster, ComputeEngine, GlslShaderKind};
+use vulkano::{
+    buffer::{Buffer, BufferCreateInfo, BufferUsage},
+    command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage},
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
+    pipeline::{Pipeline, PipelineBindPoint},
+};
+
+// Every work group multiplies 64 values
+const SHADER: &str = r#"
+#version 450
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+layout(set = 0, binding = 0) buffer Data { uint data[]; } buf;
+
+void main() {
+    buf.data[gl_GlobalInvocationID.x] *= 12;
+}
+"#;
+
+const WORK_GROUP_COUNT: u32 = 1024;
+
+pub fn main() {
+    env_logger::init();
+    log::info!(
+        "Logger initialized at max level set to {}",
+        log::max_level()
+    );
+    log::info!("010 - Compute Cluster");
+
+    // One engine per compute capable device, restrict it with e.g. VULKAN_ENGINE_DEVICE=llvmpipe
+    let compute_cluster = ComputeCluster::new();
+    compute_cluster.print_devices(log::Level::Info);
+
+    // Every device processes its share of the work groups on its own data
+    let report =
+        compute_cluster.dispatch(WORK_GROUP_COUNT, |engine: &ComputeEngine, work_groups| {
+            let device = engine.get_logical_device();
+            let memory_allocator = StandardMemoryAllocator::new_default(device.get_device());
+            let descriptor_set_allocator = StandardDescriptorSetAllocator::new(device.get_device());
+
+            let data_buffer = Buffer::from_iter(
+                &memory_allocator,
+                BufferCreateInfo {
+                    usage: BufferUsage::STORAGE_BUFFER,
+                    ..Default::default()
+                },
+                AllocationCreateInfo {
+                    usage: MemoryUsage::Upload,
+                    ..Default::default()
+                },
+                work_groups.start * 64..work_groups.end * 64,
+            )
+            .expect("failed to create data buffer");
+
+            let shader = device.create_glsl_shader_module(SHADER, GlslShaderKind::Compute);
+            let compute_pipeline = engine.create_compute_pipeline::<(), _>(shader, &());
+
+            let layout = compute_pipeline.layout().set_layouts().get(0).unwrap();
+            let set = PersistentDescriptorSet::new(
+                &descriptor_set_allocator,
+                layout.clone(),
+                [WriteDescriptorSet::buffer(0, data_buffer.clone())],
+            )
+            .expect("failed to create descriptor set");
+
+            engine.compute(&|engine: &ComputeEngine| {
+                let mut builder = AutoCommandBufferBuilder::primary(
+                    &engine.get_command_buffer_allocator(),
+                    engine.get_logical_device().get_queue_family_index(),
+                    CommandBufferUsage::OneTimeSubmit,
+                )
+                .unwrap();
+
+                builder
+                    .bind_pipeline_compute(compute_pipeline.clone())
+                    .bind_descriptor_sets(
+                        PipelineBindPoint::Compute,
+                        compute_pipeline.layout().clone(),
+                        0,
+                        set.clone(),
+                    )
+                    .dispatch([work_groups.len() as u32, 1, 1])
+                    .unwrap();
+
+                builder.build().unwrap()
+            });
+
+            let content = data_buffer.read().unwrap();
+            content.to_vec()
+        });
+    report.print_timings(log::Level::Info);
+
+    // Assert the gathered results
+    let results = report.results.concat();
+    assert_eq!(results.len(), WORK_GROUP_COUNT as usize * 64);
+    for (n, val) in results.iter().enumerate() {
+        assert_eq!(*val, n as u32 * 12);
+    }
+    log::info!("Assertion passed");
+}
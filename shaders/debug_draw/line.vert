@@ -0,0 +1,15 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec4 color;
+
+layout(location = 0) out vec4 v_color;
+
+layout(push_constant) uniform PushConstants { mat4 view_projection; }
+parameters;
+
+// Debug primitives are given in world space.
+void main() {
+  v_color = color;
+  gl_Position = parameters.view_projection * vec4(position, 1.0);
+}
@@ -0,0 +1,32 @@
+#version 450
+
+layout(location = 0) in vec2 v_uv;
+layout(location = 1) in vec4 v_color;
+
+layout(location = 0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D egui_texture;
+layout(set = 0, binding = 1) uniform sampler egui_sampler;
+
+layout(push_constant) uniform PushConstants {
+  vec2 screen_size;
+  uint srgb_target;
+}
+parameters;
+
+vec3 linear_to_srgb(vec3 linear) {
+  vec3 cutoff = step(vec3(0.0031308), linear);
+  vec3 lower = linear * 12.92;
+  vec3 higher = 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055;
+  return mix(lower, higher, cutoff);
+}
+
+// Blending is done with premultiplied alpha.
+// Targets without sRGB format expect already encoded colors.
+void main() {
+  f_color = texture(sampler2D(egui_texture, egui_sampler), v_uv) * v_color;
+
+  if (parameters.srgb_target == 0u) {
+    f_color.rgb = linear_to_srgb(f_color.rgb);
+  }
+}
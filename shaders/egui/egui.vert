@@ -0,0 +1,30 @@
+#version 450
+
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+
+layout(location = 0) out vec2 v_uv;
+layout(location = 1) out vec4 v_color;
+
+layout(push_constant) uniform PushConstants {
+  vec2 screen_size;
+  uint srgb_target;
+}
+parameters;
+
+vec3 srgb_to_linear(vec3 srgb) {
+  vec3 cutoff = step(vec3(0.04045), srgb);
+  vec3 lower = srgb / 12.92;
+  vec3 higher = pow((srgb + 0.055) / 1.055, vec3(2.4));
+  return mix(lower, higher, cutoff);
+}
+
+// egui positions are in points, with the origin in the top left corner.
+// Vertex colors are sRGB encoded with premultiplied alpha.
+void main() {
+  v_uv = uv;
+  v_color = vec4(srgb_to_linear(color.rgb), color.a);
+  gl_Position =
+      vec4(position / parameters.screen_size * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 450
+
+layout(set = 0, binding = 0, HDR_FORMAT) uniform readonly image2D input_image;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+  ivec2 coordinates = clamp(ivec2(gl_FragCoord.xy), ivec2(0),
+                            imageSize(input_image) - 1);
+  f_color = imageLoad(input_image, coordinates);
+}
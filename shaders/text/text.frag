@@ -0,0 +1,29 @@
+#version 450
+
+layout(location = 0) in vec2 v_uv;
+layout(location = 1) in vec4 v_color;
+
+layout(location = 0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D glyph_atlas;
+layout(set = 0, binding = 1) uniform sampler glyph_sampler;
+
+layout(push_constant) uniform PushConstants {
+  mat4 transform;
+  uint signed_distance_field;
+}
+parameters;
+
+// The alpha channel of the atlas is either the coverage of a glyph or its
+// signed distance field, where 0.5 is the outline.
+void main() {
+  float alpha =
+      texture(sampler2D(glyph_atlas, glyph_sampler), v_uv).a;
+
+  if (parameters.signed_distance_field != 0u) {
+    float width = fwidth(alpha);
+    alpha = smoothstep(0.5 - width, 0.5 + width, alpha);
+  }
+
+  f_color = vec4(v_color.rgb, v_color.a * alpha);
+}
@@ -0,0 +1,22 @@
+#version 450
+
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+
+layout(location = 0) out vec2 v_uv;
+layout(location = 1) out vec4 v_color;
+
+layout(push_constant) uniform PushConstants {
+  mat4 transform;
+  uint signed_distance_field;
+}
+parameters;
+
+// Glyph positions are in pixels, the transform maps them into screen or world
+// space.
+void main() {
+  v_uv = uv;
+  v_color = color;
+  gl_Position = parameters.transform * vec4(position, 0.0, 1.0);
+}
@@ -1,15 +1,38 @@
 use std::sync::Arc;
+#[cfg(debug_assertions)]
+use std::time::Instant;
 
 use vulkano::{
-    command_buffer::{allocator::CommandBufferAllocator, PrimaryAutoCommandBuffer},
+    buffer::BufferContents,
+    command_buffer::{
+        allocator::{CommandBufferAllocator, StandardCommandBufferAllocator},
+        AutoCommandBufferBuilder, PrimaryAutoCommandBuffer, PrimaryCommandBufferAbstract,
+    },
     instance::Instance,
+    pipeline::{layout::PipelineLayout, ComputePipeline, Pipeline},
+    query::QueryPipelineStatisticFlags,
+    shader::{ShaderModule, SpecializationConstants},
+    sync::GpuFuture,
 };
 
-use crate::LogicalDevice;
+use crate::{
+    print_live_resources, track_resource, EngineError, LogicalDevice, OcclusionQueries,
+    PipelineStatisticsQuery, ResourceKind, SubmissionBatch,
+};
+
+/// The `AutoCommandBufferBuilder` used to record primary command buffers with the engines' `StandardCommandBufferAllocator`.
+pub type EngineCommandBufferBuilder =
+    AutoCommandBufferBuilder<PrimaryAutoCommandBuffer, Arc<StandardCommandBufferAllocator>>;
 
 pub trait AbstractEngine<T: CommandBufferAllocator> {
     /// Kills the engine as a safety measure in case other things like winit aren't killing the Engine part.
+    /// Nothing is dropped, so in debug builds all tracked resources which are still alive are reported before,
+    /// including the ones the engine owns itself.
     fn kill(&self) {
+        if cfg!(debug_assertions) {
+            print_live_resources(log::Level::Warn);
+        }
+
         log::debug!(":::  KILLING MAIN PROCESS  :::");
         log::debug!("::: CARGO MAY REPORT ERROR :::");
         std::process::exit(std::process::id().try_into().unwrap());
@@ -21,8 +44,125 @@ pub trait AbstractEngine<T: CommandBufferAllocator> {
         log::log!(log_level, "Max API Version: {}", instance.max_api_version());
     }
 
-    /// Computes a given operation on the GPU
-    fn compute(&self, operation: &dyn (Fn(&Self) -> PrimaryAutoCommandBuffer));
+    /// Computes a given operation on the GPU.
+    /// Panics on any error, use `try_compute` to handle a lost device or running out of memory.
+    fn compute(&self, operation: &dyn Fn(&Self) -> PrimaryAutoCommandBuffer) {
+        if let Err(e) = self.try_compute(operation) {
+            panic!("failed to compute operation: {}", e);
+        }
+    }
+
+    /// Computes a given operation on the GPU and waits for it to finish.
+    /// On `EngineError::requires_recovery` errors the engine has to be recovered before it can be used again.
+    fn try_compute(
+        &self,
+        operation: &dyn Fn(&Self) -> PrimaryAutoCommandBuffer,
+    ) -> Result<(), EngineError> {
+        let command_buffer = operation(self);
+
+        #[cfg(debug_assertions)]
+        let start_fence = Instant::now();
+
+        command_buffer
+            .execute(self.get_logical_device().get_first_queue())?
+            .then_signal_fence_and_flush()?
+            .wait(None)?;
+
+        #[cfg(debug_assertions)]
+        {
+            let end_fence = Instant::now();
+            log::debug!(
+                "Compute operation took: {}ms",
+                end_fence.duration_since(start_fence).as_millis()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Creates an empty `SubmissionBatch` submitting to the first queue of the `LogicalDevice`.
+    fn create_submission_batch(&self) -> SubmissionBatch {
+        SubmissionBatch::new(self.get_logical_device().get_first_queue())
+    }
+
+    /// Creates an empty `SubmissionBatch` submitting to the additional compute queue of the `LogicalDevice`,
+    /// e.g. to run compute work concurrently to the first queue. `None` if the device has no such queue.
+    fn create_compute_submission_batch(&self) -> Option<SubmissionBatch> {
+        self.get_logical_device()
+            .get_compute_queue()
+            .map(SubmissionBatch::new)
+    }
+
+    /// Creates a `ComputePipeline` from the `main` entry point of a given compute `ShaderModule`.
+    /// The push constants `Pc` and the specialization constants are validated against the ones declared in the shader.
+    /// Use `()` for either if the shader doesn't declare any.
+    #[track_caller]
+    fn create_compute_pipeline<Pc, Css>(
+        &self,
+        shader: Arc<ShaderModule>,
+        specialization_constants: &Css,
+    ) -> Arc<ComputePipeline>
+    where
+        Pc: BufferContents,
+        Css: SpecializationConstants,
+    {
+        log::debug!("AbstractEngine::create_compute_pipeline");
+
+        let pipeline = ComputePipeline::new(
+            self.get_logical_device().get_device(),
+            shader
+                .entry_point("main")
+                .expect("shader has no 'main' entry point"),
+            specialization_constants,
+            None,
+            |_| {},
+        )
+        .expect("failed to create compute pipeline");
+        Self::validate_push_constants::<Pc>(pipeline.layout());
+        track_resource(ResourceKind::Pipeline, &pipeline);
+
+        pipeline
+    }
+
+    /// Validates that the push constants `Pc` match the push constant ranges the shaders of a `PipelineLayout` declare.
+    /// Panics with a description of the mismatch otherwise.
+    /// Called by `create_compute_pipeline`, graphics pipelines built with `GraphicsPipeline::start` have to call it themselves.
+    fn validate_push_constants<Pc>(layout: &PipelineLayout)
+    where
+        Pc: BufferContents,
+    {
+        let push_constants_size = std::mem::size_of::<Pc>() as u32;
+        let layout_size = layout
+            .push_constant_ranges()
+            .iter()
+            .map(|range| range.offset + range.size)
+            .max()
+            .unwrap_or(0);
+
+        assert_eq!(
+            push_constants_size,
+            layout_size,
+            "push constants of type `{}` are {} bytes, but the shader declares {} bytes",
+            std::any::type_name::<Pc>(),
+            push_constants_size,
+            layout_size
+        );
+    }
+
+    /// Creates a query counting the given pipeline statistics.
+    /// Panics if the device doesn't support pipeline statistics queries.
+    fn create_pipeline_statistics_query(
+        &self,
+        flags: QueryPipelineStatisticFlags,
+    ) -> PipelineStatisticsQuery {
+        PipelineStatisticsQuery::new(&self.get_logical_device(), flags)
+    }
+
+    /// Creates `count` occlusion queries, `precise` ones count the exact number of samples.
+    /// Panics if precise queries are requested but not supported by the device.
+    fn create_occlusion_queries(&self, count: u32, precise: bool) -> OcclusionQueries {
+        OcclusionQueries::new(&self.get_logical_device(), count, precise)
+    }
 
     /// Returns the instance of the Vulkan API.
     fn get_instance(&self) -> Arc<Instance>;
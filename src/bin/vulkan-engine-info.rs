@@ -0,0 +1,35 @@
+//! Prints the capabilities of all Vulkan devices, to be attached to bug reports.
+//!
+//! Usage: `vulkan-engine-info [--json]`
+
+use vulkan_engine::{DeviceReport, GraphicalEngine};
+
+fn main() {
+    let mut json = false;
+    for argument in std::env::args().skip(1) {
+        match argument.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("Usage: vulkan-engine-info [--json]");
+                println!();
+                println!("Prints the capabilities of all Vulkan devices as tables, or as JSON with --json.");
+                return;
+            }
+            argument => {
+                eprintln!("Unknown argument '{}', see --help", argument);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let report = DeviceReport::new(&GraphicalEngine::make_instance());
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("failed to serialize device report")
+        );
+    } else {
+        print!("{}", report);
+    }
+}
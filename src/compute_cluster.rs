@@ -0,0 +1,375 @@
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use vulkano::device::{
+    physical::{PhysicalDevice, PhysicalDeviceType},
+    QueueFlags,
+};
+
+use crate::{AbstractEngine, ComputeEngine, DeviceSelector};
+
+/// Time a single device of a `ComputeCluster` took for its share of the work.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceTiming {
+    /// Index of the engine in `ComputeCluster::get_engines`.
+    pub engine_index: usize,
+    pub device_name: String,
+    /// The work groups or jobs the device processed.
+    pub work: Range<usize>,
+    pub duration: Duration,
+}
+
+/// Results and per-device timings of a `ComputeCluster::dispatch` or `ComputeCluster::run_jobs`.
+#[derive(Debug, Clone)]
+pub struct ClusterReport<R> {
+    pub results: Vec<R>,
+    /// Only devices which got a share of the work, in engine order.
+    pub timings: Vec<DeviceTiming>,
+}
+
+impl<R> ClusterReport<R> {
+    /// Returns the time of the slowest device, i.e. of the whole workload.
+    pub fn get_total_duration(&self) -> Duration {
+        self.timings
+            .iter()
+            .map(|timing| timing.duration)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Prints out how much work every device processed and how long it took.
+    pub fn print_timings(&self, log_level: log::Level) {
+        for timing in &self.timings {
+            log::log!(
+                log_level,
+                "Device {} ({}): {} work items ({}..{}) in {}ms",
+                timing.engine_index,
+                timing.device_name,
+                timing.work.len(),
+                timing.work.start,
+                timing.work.end,
+                timing.duration.as_millis()
+            );
+        }
+        log::log!(
+            log_level,
+            "Total: {}ms",
+            self.get_total_duration().as_millis()
+        );
+    }
+}
+
+/// A `ComputeCluster` spreads a workload over several `ComputeEngine`s, one per `PhysicalDevice`.
+/// The work is split proportionally to a capability score per device and every device runs its share on its own thread.
+pub struct ComputeCluster {
+    engines: Vec<ComputeEngine>,
+    scores: Vec<f64>,
+}
+
+impl ComputeCluster {
+    /// Creates a `ComputeEngine` for every compute capable `PhysicalDevice`, CPU devices included.
+    /// If `DEVICE_SELECTOR_ENV` is set, only the devices it matches are used.
+    pub fn new() -> Self {
+        log::debug!("ComputeCluster::new");
+
+        let instance = ComputeEngine::make_instance();
+        let selector = DeviceSelector::from_env();
+
+        let engines = instance
+            .enumerate_physical_devices()
+            .expect("failed enumerating physical devices")
+            .enumerate()
+            .filter(|(index, physical_device)| {
+                selector.matches(*index, physical_device)
+                    && physical_device
+                        .queue_family_properties()
+                        .iter()
+                        .any(|queue_family| queue_family.queue_flags.contains(QueueFlags::COMPUTE))
+            })
+            .map(|(index, _)| {
+                ComputeEngine::with_instance(instance.clone(), &DeviceSelector::Index(index))
+            })
+            .collect::<Vec<_>>();
+
+        Self::from_engines(engines)
+    }
+
+    /// Creates a `ComputeCluster` of existing engines, e.g. a single one for testing.
+    pub fn from_engines(engines: Vec<ComputeEngine>) -> Self {
+        assert!(
+            !engines.is_empty(),
+            "no compute capable physical device found"
+        );
+
+        let scores = engines
+            .iter()
+            .map(|engine| get_capability_score(&engine.get_logical_device().get_physical_device()))
+            .collect();
+
+        let cluster = Self { engines, scores };
+        cluster.print_devices(log::Level::Debug);
+
+        cluster
+    }
+
+    /// Returns the engines of the cluster.
+    pub fn get_engines(&self) -> &[ComputeEngine] {
+        &self.engines
+    }
+
+    /// Returns the capability score of every engine.
+    pub fn get_scores(&self) -> &[f64] {
+        &self.scores
+    }
+
+    /// Overrides the capability score of every engine, e.g. with measured throughputs.
+    pub fn set_scores(&mut self, scores: Vec<f64>) {
+        assert_eq!(
+            scores.len(),
+            self.engines.len(),
+            "every engine needs a score"
+        );
+        assert!(
+            scores.iter().all(|score| *score >= 0.0) && scores.iter().any(|score| *score > 0.0),
+            "scores must be non-negative and at least one must be positive"
+        );
+
+        self.scores = scores;
+    }
+
+    /// Sets the scores of the timed devices to their measured throughput, so following workloads are split
+    /// by how fast every device actually was. Devices without timing keep their score.
+    pub fn rebalance(&mut self, timings: &[DeviceTiming]) {
+        let mut scores = self.scores.clone();
+        for timing in timings {
+            let seconds = timing.duration.as_secs_f64();
+            if !timing.work.is_empty() && seconds > 0.0 {
+                scores[timing.engine_index] = timing.work.len() as f64 / seconds;
+            }
+        }
+
+        // Throughputs and the heuristic scores of untimed devices aren't comparable
+        if timings.len() < self.engines.len() {
+            log::warn!("Rebalancing with timings of only some devices");
+        }
+
+        self.set_scores(scores);
+    }
+
+    /// Splits `0..count` into one contiguous range per engine, proportionally to the scores.
+    pub fn split(&self, count: usize) -> Vec<Range<usize>> {
+        split_proportionally(count, &self.scores)
+    }
+
+    /// Splits `work_group_count` work groups across the devices and calls `run` on every device with its share,
+    /// which is expected to record, execute and read back the dispatch, e.g. via `AbstractEngine::compute`.
+    /// Shaders indexing global data have to offset `gl_WorkGroupID` by the start of the share themselves.
+    /// The results are in engine order, devices without a share are skipped.
+    pub fn dispatch<R, F>(&self, work_group_count: u32, run: F) -> ClusterReport<R>
+    where
+        R: Send,
+        F: Fn(&ComputeEngine, Range<u32>) -> R + Sync,
+    {
+        log::debug!("ComputeCluster::dispatch");
+
+        let shares = self
+            .split(work_group_count as usize)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, share)| !share.is_empty())
+            .map(|(index, share)| (index, share, ()))
+            .collect::<Vec<_>>();
+
+        let (results, timings) = self.run_shares(shares, |engine, share, _| {
+            run(engine, share.start as u32..share.end as u32)
+        });
+
+        ClusterReport { results, timings }
+    }
+
+    /// Splits the jobs across the devices and calls `run` for every job on the device it was assigned to.
+    /// Every device processes its jobs one after another, the results are in the order of the jobs.
+    pub fn run_jobs<J, R, F>(&self, jobs: Vec<J>, run: F) -> ClusterReport<R>
+    where
+        J: Send,
+        R: Send,
+        F: Fn(&ComputeEngine, J) -> R + Sync,
+    {
+        log::debug!("ComputeCluster::run_jobs");
+
+        let mut jobs = jobs.into_iter();
+        let shares = self
+            .split(jobs.len())
+            .into_iter()
+            .enumerate()
+            .filter(|(_, share)| !share.is_empty())
+            .map(|(index, share)| {
+                let share_jobs = jobs.by_ref().take(share.len()).collect::<Vec<_>>();
+                (index, share, share_jobs)
+            })
+            .collect::<Vec<_>>();
+
+        let (results, timings) = self.run_shares(shares, |engine, _, share_jobs| {
+            share_jobs
+                .into_iter()
+                .map(|job| run(engine, job))
+                .collect::<Vec<_>>()
+        });
+
+        ClusterReport {
+            results: results.into_iter().flatten().collect(),
+            timings,
+        }
+    }
+
+    /// Runs every share, given as engine index, work range and data, on a thread of its own and times it.
+    fn run_shares<S, R, F>(
+        &self,
+        shares: Vec<(usize, Range<usize>, S)>,
+        run: F,
+    ) -> (Vec<R>, Vec<DeviceTiming>)
+    where
+        S: Send,
+        R: Send,
+        F: Fn(&ComputeEngine, Range<usize>, S) -> R + Sync,
+    {
+        let run = &run;
+
+        std::thread::scope(|scope| {
+            let handles = shares
+                .into_iter()
+                .map(|(engine_index, work, data)| {
+                    let engine = &self.engines[engine_index];
+                    scope.spawn(move || {
+                        let start = Instant::now();
+                        let result = run(engine, work.clone(), data);
+                        let duration = start.elapsed();
+
+                        let timing = DeviceTiming {
+                            engine_index,
+                            device_name: engine.get_logical_device().get_device_name(),
+                            work,
+                            duration,
+                        };
+
+                        (result, timing)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("compute cluster device panicked"))
+                .unzip()
+        })
+    }
+
+    /// Prints out every device of the cluster with its capability score.
+    pub fn print_devices(&self, log_level: log::Level) {
+        for (index, (engine, score)) in self.engines.iter().zip(&self.scores).enumerate() {
+            log::log!(
+                log_level,
+                "Cluster Device {}: {}; Score: {:.2}",
+                index,
+                engine.get_logical_device().get_device_name(),
+                score
+            );
+        }
+    }
+}
+
+impl Default for ComputeCluster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits `0..count` into one contiguous range per score, proportionally to the scores.
+fn split_proportionally(count: usize, scores: &[f64]) -> Vec<Range<usize>> {
+    let total_score: f64 = scores.iter().sum();
+    let shares = scores
+        .iter()
+        .map(|score| count as f64 * score / total_score)
+        .collect::<Vec<_>>();
+
+    // Largest remainder: everyone gets the floor, the rest goes to the largest fractions
+    let mut counts = shares
+        .iter()
+        .map(|share| share.floor() as usize)
+        .collect::<Vec<_>>();
+    let mut by_remainder = (0..shares.len()).collect::<Vec<_>>();
+    by_remainder.sort_by(|a, b| {
+        (shares[*b] - shares[*b].floor()).total_cmp(&(shares[*a] - shares[*a].floor()))
+    });
+    let missing = count.saturating_sub(counts.iter().sum::<usize>());
+    for index in by_remainder.into_iter().take(missing) {
+        counts[index] += 1;
+    }
+
+    let mut start = 0;
+    counts
+        .into_iter()
+        .map(|count| {
+            start += count;
+            start - count..start
+        })
+        .collect()
+}
+
+/// A rough estimate of the compute throughput of a device, based on its type and its compute limits.
+/// Use `ComputeCluster::rebalance` with measured timings for a precise split.
+pub fn get_capability_score(physical_device: &PhysicalDevice) -> f64 {
+    let type_weight = match physical_device.properties().device_type {
+        PhysicalDeviceType::DiscreteGpu => 8.0,
+        PhysicalDeviceType::IntegratedGpu => 2.0,
+        PhysicalDeviceType::VirtualGpu => 2.0,
+        PhysicalDeviceType::Cpu => 0.25,
+        _ => 1.0,
+    };
+
+    // Relative to the common limit of 1024 invocations per work group
+    let invocations = physical_device
+        .properties()
+        .max_compute_work_group_invocations as f64;
+
+    type_weight * (invocations / 1024.0).clamp(0.5, 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_single_engine() {
+        assert_eq!(split_proportionally(10, &[0.25]), vec![0..10]);
+        assert_eq!(split_proportionally(0, &[8.0]), vec![0..0]);
+    }
+
+    #[test]
+    fn split_unequal_scores() {
+        // Shares of 6.67 and 3.33: the floors add up to 9, the larger remainder gets the last one
+        assert_eq!(split_proportionally(10, &[2.0, 1.0]), vec![0..7, 7..10]);
+
+        // Shares of 1.4, 2.8 and 2.8: the two largest remainders get one more each
+        assert_eq!(
+            split_proportionally(7, &[1.0, 2.0, 2.0]),
+            vec![0..1, 1..4, 4..7]
+        );
+
+        // Engines with a score of zero get nothing
+        assert_eq!(split_proportionally(5, &[0.0, 1.0]), vec![0..0, 0..5]);
+    }
+
+    #[test]
+    fn split_fewer_items_than_engines() {
+        let ranges = split_proportionally(2, &[1.0, 1.0, 1.0, 4.0]);
+
+        assert_eq!(ranges.len(), 4);
+        assert_eq!(ranges.iter().map(|range| range.len()).sum::<usize>(), 2);
+        assert_eq!(ranges[3], 1..2);
+        assert_eq!(ranges.last().unwrap().end, 2);
+        assert!(ranges.windows(2).all(|pair| pair[0].end == pair[1].start));
+    }
+}
@@ -1,55 +1,115 @@
-use std::{sync::Arc, time::Instant};
+use std::sync::Arc;
 
 use vulkano::{
-    command_buffer::{
-        allocator::{StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo},
-        PrimaryAutoCommandBuffer, PrimaryCommandBufferAbstract,
+    command_buffer::allocator::{
+        StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo,
     },
-    device::{
-        physical::PhysicalDevice, Device, DeviceCreateInfo, Queue, QueueCreateInfo, QueueFlags,
-    },
-    instance::{Instance, InstanceCreateInfo},
-    sync::GpuFuture,
+    device::{physical::PhysicalDevice, Device, DeviceCreateInfo, Queue, QueueFlags},
+    instance::{Instance, InstanceCreateInfo, InstanceExtensions},
     VulkanLibrary,
 };
 
-use crate::{AbstractEngine, LogicalDevice};
+use crate::{
+    AbstractEngine, DeviceSelector, LeakCheck, LogicalDevice, RecoveryCallback, RecoveryStage,
+};
 
-#[derive(Debug)]
 pub struct ComputeEngine {
     instance: Arc<Instance>,
     logical_device: Arc<LogicalDevice>,
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    recovery_callback: Option<RecoveryCallback<Self>>,
+    /// Last, to report the tracked resources which outlived the engine's own ones.
+    leak_check: LeakCheck,
 }
 
 impl ComputeEngine {
     /// Creates a `ComputeEngine` instance and initializes everything needed for computing tasks.
+    /// The device is selected by `DeviceSelector::Automatic`.
     pub fn new() -> Self {
-        log::debug!("ComputeEngine::startup");
+        Self::with_device_selector(&DeviceSelector::Automatic)
+    }
 
-        let instance = Self::create_instance();
-        Self::print_api_information(instance.clone(), log::Level::Debug);
+    /// Creates a `ComputeEngine` on the `PhysicalDevice` chosen by the `DeviceSelector`.
+    pub fn with_device_selector(selector: &DeviceSelector) -> Self {
+        Self::with_instance(Self::make_instance(), selector)
+    }
 
-        let physical_device = Self::get_physical_device(instance.clone());
-        let queue_family_index = Self::find_best_suited_queue_family(physical_device.clone());
+    /// Creates a `ComputeEngine` on an existing instance, e.g. to run several engines on different devices.
+    pub fn with_instance(instance: Arc<Instance>, selector: &DeviceSelector) -> Self {
+        log::debug!("ComputeEngine::startup");
+
+        let (physical_device, queue_family_index) = Self::get_physical_device(&instance, selector);
         let logical_device = Self::create_logical_device(physical_device, queue_family_index);
 
         logical_device.print_interesting_information(log::Level::Debug);
 
-        let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
-            logical_device.get_device(),
-            StandardCommandBufferAllocatorCreateInfo {
-                ..Default::default()
-            },
-        ));
+        let command_buffer_allocator = Self::create_command_buffer_allocator(&logical_device);
 
         Self {
             instance,
+            leak_check: LeakCheck::new(logical_device.get_device()),
             logical_device,
             command_buffer_allocator,
+            recovery_callback: None,
+        }
+    }
+
+    fn create_command_buffer_allocator(
+        logical_device: &LogicalDevice,
+    ) -> Arc<StandardCommandBufferAllocator> {
+        Arc::new(StandardCommandBufferAllocator::new(
+            logical_device.get_device(),
+            StandardCommandBufferAllocatorCreateInfo {
+                ..Default::default()
+            },
+        ))
+    }
+
+    /// Sets the callback `recover` calls before and after the device is recreated, see `RecoveryStage`.
+    pub fn set_recovery_callback(
+        &mut self,
+        callback: impl FnMut(&Self, RecoveryStage) + Send + Sync + 'static,
+    ) {
+        self.recovery_callback = Some(Box::new(callback));
+    }
+
+    /// Recreates the `LogicalDevice` and the command buffer allocator on the same `PhysicalDevice`,
+    /// e.g. after `try_compute` failed with an error which `EngineError::requires_recovery`.
+    /// Calls the recovery callback before and afterwards.
+    pub fn recover(&mut self) {
+        log::warn!("ComputeEngine::recover");
+
+        self.run_recovery_callback(RecoveryStage::Release);
+
+        self.logical_device = Self::create_logical_device(
+            self.logical_device.get_physical_device(),
+            self.logical_device.get_queue_family_index(),
+        );
+        self.command_buffer_allocator = Self::create_command_buffer_allocator(&self.logical_device);
+
+        self.run_recovery_callback(RecoveryStage::Recreate);
+
+        // The callback replaced all resources of the old device, the remaining ones leaked
+        self.leak_check = LeakCheck::new(self.logical_device.get_device());
+    }
+
+    fn run_recovery_callback(&mut self, stage: RecoveryStage) {
+        if let Some(mut callback) = self.recovery_callback.take() {
+            callback(self, stage);
+            self.recovery_callback = Some(callback);
         }
     }
 
+    /// Creates a new instance of the Vulkan API for `ComputeEngine`s.
+    pub fn make_instance() -> Arc<Instance> {
+        log::debug!("ComputeEngine::make_instance");
+
+        let instance = Self::create_instance();
+        Self::print_api_information(instance.clone(), log::Level::Debug);
+
+        instance
+    }
+
     /// Creates a Vulkan(o) instance.
     fn create_instance() -> Arc<Instance> {
         log::debug!("ComputeEngine::create_instance");
@@ -57,49 +117,45 @@ impl ComputeEngine {
         let library = VulkanLibrary::new()
             .expect("failed to load Vulkan library. Make sure you have the Vulkan SDK installed.");
 
-        Instance::new(library, InstanceCreateInfo::application_from_cargo_toml())
-            .expect("failed to create Vulkan instance")
+        // Debug utils allow naming objects and labeling command buffers for debugging tools
+        let enabled_extensions = InstanceExtensions {
+            ext_debug_utils: library.supported_extensions().ext_debug_utils,
+            ..InstanceExtensions::empty()
+        };
+
+        Instance::new(
+            library,
+            InstanceCreateInfo {
+                enabled_extensions,
+                ..InstanceCreateInfo::application_from_cargo_toml()
+            },
+        )
+        .expect("failed to create Vulkan instance")
     }
 
-    /// Queries all `PhysicalDevice`'s and returns the best match.
-    fn get_physical_device(instance: Arc<Instance>) -> Arc<PhysicalDevice> {
+    /// Returns the `PhysicalDevice` chosen by the `DeviceSelector` among the ones with a compute queue family,
+    /// together with the index of that family.
+    fn get_physical_device(
+        instance: &Arc<Instance>,
+        selector: &DeviceSelector,
+    ) -> (Arc<PhysicalDevice>, u32) {
         log::debug!("ComputeEngine::get_physical_device");
 
-        instance
-            .enumerate_physical_devices()
-            .expect("failed enumerating physical devices")
-            .filter(|physical_device: &Arc<PhysicalDevice>| {
-                physical_device
-                    .queue_family_properties()
-                    .iter()
-                    .any(|queue_family| queue_family.queue_flags.contains(QueueFlags::COMPUTE))
-            })
-            .min_by_key(|physical_device: &Arc<PhysicalDevice>| {
-                match physical_device.properties().device_type {
-                    vulkano::device::physical::PhysicalDeviceType::DiscreteGpu => 0,
-                    vulkano::device::physical::PhysicalDeviceType::IntegratedGpu => 1,
-                    vulkano::device::physical::PhysicalDeviceType::VirtualGpu => 2,
-                    vulkano::device::physical::PhysicalDeviceType::Cpu => 3,
-                    vulkano::device::physical::PhysicalDeviceType::Other => 4,
-                    _ => 5,
-                }
-            })
-            .expect("no physical device found or available")
+        let (physical_device, queue_family_index) = selector.select(instance, |physical_device| {
+            Self::find_best_suited_queue_family(physical_device)
+        });
+
+        log::debug!("Queue family index: {}", queue_family_index);
+        (physical_device, queue_family_index)
     }
 
     /// Finds the best suited `QueueFamily` of a given device or none if no suitable family was found.
-    fn find_best_suited_queue_family(physical_device: Arc<PhysicalDevice>) -> u32 {
-        log::debug!("ComputeEngine::find_best_suited_queue_family");
-
-        let queue_family_index = physical_device
+    fn find_best_suited_queue_family(physical_device: &PhysicalDevice) -> Option<u32> {
+        physical_device
             .queue_family_properties()
             .iter()
-            .enumerate()
-            .position(|(_, q)| q.queue_flags.contains(QueueFlags::COMPUTE)) // Find a compute capable queue family
-            .expect("no suitable queue family found") as u32;
-
-        log::debug!("Queue family index: {}", queue_family_index);
-        queue_family_index
+            .position(|q| q.queue_flags.contains(QueueFlags::COMPUTE)) // Find a compute capable queue family
+            .map(|index| index as u32)
     }
 
     /// Creates a `LogicalDevice` given a `PhysicalDevice` and a `QueueFamilyIndex`.
@@ -109,50 +165,33 @@ impl ComputeEngine {
     ) -> Arc<LogicalDevice> {
         log::debug!("ComputeEngine::create_logical_device");
 
+        let enabled_features = LogicalDevice::retrieve_optional_features(&physical_device);
+        let enabled_extensions = LogicalDevice::retrieve_optional_extensions(&physical_device);
+
+        let queue_create_infos =
+            LogicalDevice::get_queue_create_infos(&physical_device, queue_family_index);
         let (device, raw_queues) = Device::new(
             physical_device,
             DeviceCreateInfo {
-                queue_create_infos: vec![QueueCreateInfo {
-                    queue_family_index,
-                    ..Default::default()
-                }],
+                queue_create_infos,
+                enabled_extensions,
+                enabled_features,
                 ..Default::default()
             },
         )
         .expect("failed to create logical device");
 
-        let queues: Vec<Arc<Queue>> = raw_queues.collect();
+        let mut queues: Vec<Arc<Queue>> = raw_queues.collect();
+        // The additional compute queue is created last
+        let compute_queue = if queues.len() > 1 { queues.pop() } else { None };
 
-        let logical_device = LogicalDevice::new(device, queue_family_index, queues);
+        let logical_device = LogicalDevice::new(device, queue_family_index, queues)
+            .with_compute_queue(compute_queue);
         Arc::new(logical_device)
     }
 }
 
 impl AbstractEngine<StandardCommandBufferAllocator> for ComputeEngine {
-    fn compute(&self, operation: &dyn (Fn(&Self) -> PrimaryAutoCommandBuffer)) {
-        let command_buffer = operation(self);
-
-        #[cfg(debug_assertions)]
-        let start_fence = Instant::now();
-
-        command_buffer
-            .execute(self.get_logical_device().get_first_queue())
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap()
-            .wait(None)
-            .unwrap();
-
-        #[cfg(debug_assertions)]
-        {
-            let end_fence = Instant::now();
-            log::debug!(
-                "Compute operation took: {}ms",
-                end_fence.duration_since(start_fence).as_millis()
-            );
-        }
-    }
-
     fn get_instance(&self) -> Arc<Instance> {
         self.instance.clone()
     }
@@ -166,6 +205,16 @@ impl AbstractEngine<StandardCommandBufferAllocator> for ComputeEngine {
     }
 }
 
+impl std::fmt::Debug for ComputeEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComputeEngine")
+            .field("instance", &self.instance)
+            .field("logical_device", &self.logical_device)
+            .field("command_buffer_allocator", &self.command_buffer_allocator)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Default for ComputeEngine {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,442 @@
+use std::{f32::consts::TAU, sync::Arc};
+
+use vulkano::{
+    buffer::{
+        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo},
+        BufferContents, BufferUsage,
+    },
+    command_buffer::{RenderPassBeginInfo, SubpassContents},
+    format::Format,
+    image::{view::ImageView, ImageAccess, ImageViewAbstract, SwapchainImage},
+    memory::allocator::{MemoryUsage, StandardMemoryAllocator},
+    pipeline::{
+        graphics::{
+            color_blend::ColorBlendState,
+            depth_stencil::{CompareOp, DepthState, DepthStencilState},
+            input_assembly::{InputAssemblyState, PrimitiveTopology},
+            vertex_input::Vertex,
+            viewport::{Viewport, ViewportState},
+        },
+        GraphicsPipeline, Pipeline, StateMode,
+    },
+    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass},
+    shader::ShaderModule,
+};
+
+use crate::{
+    track_resource, track_resource_once, AbstractEngine, EngineCommandBufferBuilder,
+    GlslShaderKind, GraphicalEngine, LogicalDevice, ResourceKind, SDebugVertex,
+};
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct DebugDrawPushConstants {
+    view_projection: [[f32; 4]; 4],
+}
+
+/// Immediate-mode drawing of debug lines, boxes, spheres, frustums, grids and axes.
+///
+/// Primitives are accumulated during a frame, e.g. `debug_draw.line(a, b, color)`,
+/// and recorded by `record` in an own render pass on top of the `SwapchainImage` the main pass rendered into.
+/// All positions are in world space, transformed by the matrix set with `set_view_projection`.
+///
+/// If created with a depth format, primitives added while the depth test is enabled are hidden behind the scene,
+/// using the depth buffer of the main pass. All other primitives are always drawn on top.
+pub struct DebugDraw {
+    render_pass: Arc<RenderPass>,
+    overlay_pipeline: Arc<GraphicsPipeline>,
+    depth_tested_pipeline: Option<Arc<GraphicsPipeline>>,
+    swap_chain_image_views: Vec<Arc<ImageView<SwapchainImage>>>,
+    buffer_allocator: SubbufferAllocator,
+    view_projection: [[f32; 4]; 4],
+    depth_test: bool,
+    overlay_vertices: Vec<SDebugVertex>,
+    depth_tested_vertices: Vec<SDebugVertex>,
+}
+
+impl DebugDraw {
+    /// The amount of segments spheres are approximated with.
+    const CIRCLE_SEGMENTS: u32 = 32;
+
+    /// Creates a `DebugDraw` drawing onto the `Swapchain` of the `GraphicalEngine`.
+    /// `depth_format` is the `Format` of the main pass' depth buffer, or `None` if depth testing isn't needed.
+    #[track_caller]
+    pub fn new(
+        engine: &GraphicalEngine,
+        memory_allocator: Arc<StandardMemoryAllocator>,
+        depth_format: Option<Format>,
+    ) -> Self {
+        log::debug!("DebugDraw::new");
+
+        let device = engine.get_logical_device();
+        let color_format = engine.get_swap_chain().image_format();
+
+        // Both attachments are loaded, so the debug primitives are drawn on top of the main pass
+        let render_pass = match depth_format {
+            None => vulkano::single_pass_renderpass!(
+                device.get_device(),
+                attachments: {
+                    color: {
+                        load: Load,
+                        store: Store,
+                        format: color_format,
+                        samples: 1,
+                    }
+                },
+                pass: {
+                    color: [color],
+                    depth_stencil: {}
+                }
+            ),
+            Some(depth_format) => vulkano::single_pass_renderpass!(
+                device.get_device(),
+                attachments: {
+                    color: {
+                        load: Load,
+                        store: Store,
+                        format: color_format,
+                        samples: 1,
+                    },
+                    depth: {
+                        load: Load,
+                        store: DontCare,
+                        format: depth_format,
+                        samples: 1,
+                    }
+                },
+                pass: {
+                    color: [color],
+                    depth_stencil: {depth}
+                }
+            ),
+        }
+        .expect("failed to create debug draw render pass");
+
+        let vertex_shader = device.create_glsl_shader_module(
+            include_str!("../shaders/debug_draw/line.vert"),
+            GlslShaderKind::Vertex,
+        );
+        let fragment_shader = device.create_glsl_shader_module(
+            include_str!("../shaders/debug_draw/line.frag"),
+            GlslShaderKind::Fragment,
+        );
+        let shaders = [vertex_shader, fragment_shader];
+        let overlay_pipeline = Self::create_pipeline(
+            &device,
+            &render_pass,
+            &shaders,
+            DepthStencilState::disabled(),
+        );
+        // Debug primitives are tested against the scene, but don't occlude each other
+        let depth_tested_pipeline = if depth_format.is_some() {
+            Some(Self::create_pipeline(
+                &device,
+                &render_pass,
+                &shaders,
+                DepthStencilState {
+                    depth: Some(DepthState {
+                        enable_dynamic: false,
+                        write_enable: StateMode::Fixed(false),
+                        compare_op: StateMode::Fixed(CompareOp::LessOrEqual),
+                    }),
+                    ..DepthStencilState::disabled()
+                },
+            ))
+        } else {
+            None
+        };
+
+        Self {
+            render_pass,
+            overlay_pipeline,
+            depth_tested_pipeline,
+            swap_chain_image_views: Self::create_swap_chain_image_views(engine),
+            buffer_allocator: SubbufferAllocator::new(
+                memory_allocator,
+                SubbufferAllocatorCreateInfo {
+                    buffer_usage: BufferUsage::VERTEX_BUFFER,
+                    memory_usage: MemoryUsage::Upload,
+                    ..Default::default()
+                },
+            ),
+            view_projection: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            depth_test: depth_format.is_some(),
+            overlay_vertices: Vec::new(),
+            depth_tested_vertices: Vec::new(),
+        }
+    }
+
+    /// Creates a line pipeline from the vertex and the fragment shader.
+    #[track_caller]
+    fn create_pipeline(
+        device: &LogicalDevice,
+        render_pass: &Arc<RenderPass>,
+        [vertex_shader, fragment_shader]: &[Arc<ShaderModule>; 2],
+        depth_stencil_state: DepthStencilState,
+    ) -> Arc<GraphicsPipeline> {
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input_state(SDebugVertex::per_vertex())
+            .vertex_shader(vertex_shader.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new().topology(PrimitiveTopology::LineList))
+            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .fragment_shader(fragment_shader.entry_point("main").unwrap(), ())
+            .color_blend_state(ColorBlendState::new(1).blend_alpha())
+            .depth_stencil_state(depth_stencil_state)
+            .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
+            .build(device.get_device())
+            .expect("failed to create debug draw pipeline");
+        track_resource(ResourceKind::Pipeline, &pipeline);
+        device.set_debug_name(&*pipeline, "DebugDraw Pipeline");
+        GraphicalEngine::validate_push_constants::<DebugDrawPushConstants>(pipeline.layout());
+
+        pipeline
+    }
+
+    fn create_swap_chain_image_views(
+        engine: &GraphicalEngine,
+    ) -> Vec<Arc<ImageView<SwapchainImage>>> {
+        engine
+            .get_swap_chain_images()
+            .into_iter()
+            .map(|image| ImageView::new_default(image).expect("failed to create image view"))
+            .collect()
+    }
+
+    /// Must be called after `GraphicalEngine::recreate_swap_chain_and_images`.
+    pub fn resize(&mut self, engine: &GraphicalEngine) {
+        self.swap_chain_image_views = Self::create_swap_chain_image_views(engine);
+    }
+
+    /// Sets the column-major matrix transforming world space into clip space, usually `projection * view`.
+    pub fn set_view_projection(&mut self, view_projection: [[f32; 4]; 4]) {
+        self.view_projection = view_projection;
+    }
+
+    /// Sets whether primitives added from now on are hidden behind the scene.
+    /// Ignored if the `DebugDraw` was created without a depth format.
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        self.depth_test = depth_test;
+    }
+
+    /// Returns how many lines are queued.
+    pub fn get_line_count(&self) -> usize {
+        (self.overlay_vertices.len() + self.depth_tested_vertices.len()) / 2
+    }
+
+    /// Adds a line from `a` to `b`.
+    pub fn line(&mut self, a: [f32; 3], b: [f32; 3], color: [f32; 4]) {
+        let vertices = if self.depth_test && self.depth_tested_pipeline.is_some() {
+            &mut self.depth_tested_vertices
+        } else {
+            &mut self.overlay_vertices
+        };
+
+        vertices.push(SDebugVertex { position: a, color });
+        vertices.push(SDebugVertex { position: b, color });
+    }
+
+    /// Adds the edges of a box given by its 8 corners.
+    /// The bits of a corner's index select the maximum (`1`) or minimum (`0`) along x (bit 0), y (bit 1) and z (bit 2).
+    fn box_edges(&mut self, corners: [[f32; 3]; 8], color: [f32; 4]) {
+        for (i, corner) in corners.iter().enumerate() {
+            for axis_bit in [1, 2, 4] {
+                if i & axis_bit == 0 {
+                    self.line(*corner, corners[i | axis_bit], color);
+                }
+            }
+        }
+    }
+
+    /// Adds an axis-aligned bounding box.
+    pub fn aabb(&mut self, min: [f32; 3], max: [f32; 3], color: [f32; 4]) {
+        let corners = std::array::from_fn(|i| {
+            [
+                if i & 1 == 0 { min[0] } else { max[0] },
+                if i & 2 == 0 { min[1] } else { max[1] },
+                if i & 4 == 0 { min[2] } else { max[2] },
+            ]
+        });
+
+        self.box_edges(corners, color);
+    }
+
+    /// Adds a circle spanned by two perpendicular unit axes.
+    fn circle(
+        &mut self,
+        center: [f32; 3],
+        axis_u: [f32; 3],
+        axis_v: [f32; 3],
+        radius: f32,
+        color: [f32; 4],
+    ) {
+        let point = |segment: u32| {
+            let (sin, cos) = (segment as f32 / Self::CIRCLE_SEGMENTS as f32 * TAU).sin_cos();
+            std::array::from_fn(|i| center[i] + (axis_u[i] * cos + axis_v[i] * sin) * radius)
+        };
+
+        for segment in 0..Self::CIRCLE_SEGMENTS {
+            self.line(point(segment), point(segment + 1), color);
+        }
+    }
+
+    /// Adds a sphere, drawn as one circle around every axis.
+    pub fn sphere(&mut self, center: [f32; 3], radius: f32, color: [f32; 4]) {
+        let [x, y, z] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+        self.circle(center, x, y, radius, color);
+        self.circle(center, y, z, radius, color);
+        self.circle(center, z, x, radius, color);
+    }
+
+    /// Adds the frustum of a camera, given the inverse of its column-major `projection * view` matrix.
+    pub fn frustum(&mut self, inverse_view_projection: [[f32; 4]; 4], color: [f32; 4]) {
+        let m = inverse_view_projection;
+        let corners = std::array::from_fn(|i| {
+            // Vulkan's clip space has a depth range of [0, 1]
+            let ndc = [
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { 0.0 } else { 1.0 },
+                1.0,
+            ];
+            let world: [f32; 4] =
+                std::array::from_fn(|row| (0..4).map(|column| m[column][row] * ndc[column]).sum());
+
+            [
+                world[0] / world[3],
+                world[1] / world[3],
+                world[2] / world[3],
+            ]
+        });
+
+        self.box_edges(corners, color);
+    }
+
+    /// Adds a grid on the XZ plane with `cells` cells of `cell_size` per direction.
+    pub fn grid(&mut self, center: [f32; 3], cell_size: f32, cells: u32, color: [f32; 4]) {
+        let half_size = cell_size * cells as f32 / 2.0;
+
+        for i in 0..=cells {
+            let offset = i as f32 * cell_size - half_size;
+
+            self.line(
+                [center[0] + offset, center[1], center[2] - half_size],
+                [center[0] + offset, center[1], center[2] + half_size],
+                color,
+            );
+            self.line(
+                [center[0] - half_size, center[1], center[2] + offset],
+                [center[0] + half_size, center[1], center[2] + offset],
+                color,
+            );
+        }
+    }
+
+    /// Adds the x (red), y (green) and z (blue) axes of a coordinate system.
+    pub fn axes(&mut self, origin: [f32; 3], length: f32) {
+        let [x, y, z] = origin;
+
+        self.line(origin, [x + length, y, z], [1.0, 0.0, 0.0, 1.0]);
+        self.line(origin, [x, y + length, z], [0.0, 1.0, 0.0, 1.0]);
+        self.line(origin, [x, y, z + length], [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    /// Discards all queued primitives.
+    pub fn clear(&mut self) {
+        self.overlay_vertices.clear();
+        self.depth_tested_vertices.clear();
+    }
+
+    /// Records all queued primitives onto the `SwapchainImage` with the given index and clears the queue.
+    /// Must be recorded after the main pass.
+    ///
+    /// `depth` is the depth buffer of the main pass and is required if the `DebugDraw` was created with a depth format.
+    /// The main pass has to store it.
+    pub fn record(
+        &mut self,
+        builder: &mut EngineCommandBufferBuilder,
+        image_index: u32,
+        depth: Option<Arc<dyn ImageViewAbstract>>,
+    ) {
+        assert_eq!(
+            depth.is_some(),
+            self.depth_tested_pipeline.is_some(),
+            "a depth buffer must be given if and only if the debug draw was created with a depth format"
+        );
+
+        let color = self.swap_chain_image_views[image_index as usize].clone();
+        let [width, height] = color.image().dimensions().width_height();
+        let mut attachments: Vec<Arc<dyn ImageViewAbstract>> = vec![color];
+        attachments.extend(depth);
+
+        let frame_buffer = Framebuffer::new(
+            self.render_pass.clone(),
+            FramebufferCreateInfo {
+                attachments,
+                ..Default::default()
+            },
+        )
+        .expect("failed to create debug draw framebuffer");
+
+        builder
+            .begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![None; frame_buffer.attachments().len()],
+                    ..RenderPassBeginInfo::framebuffer(frame_buffer)
+                },
+                SubpassContents::Inline,
+            )
+            .unwrap()
+            .set_viewport(
+                0,
+                [Viewport {
+                    origin: [0.0, 0.0],
+                    dimensions: [width as f32, height as f32],
+                    depth_range: 0.0..1.0,
+                }],
+            );
+
+        let batches = [
+            (
+                self.depth_tested_pipeline.clone(),
+                std::mem::take(&mut self.depth_tested_vertices),
+            ),
+            (
+                Some(self.overlay_pipeline.clone()),
+                std::mem::take(&mut self.overlay_vertices),
+            ),
+        ];
+        for (pipeline, vertices) in batches {
+            let Some(pipeline) = pipeline.filter(|_| !vertices.is_empty()) else {
+                continue;
+            };
+
+            let vertex_buffer = self
+                .buffer_allocator
+                .allocate_slice::<SDebugVertex>(vertices.len() as u64)
+                .expect("failed to allocate debug draw vertices");
+            track_resource_once(ResourceKind::Buffer, vertex_buffer.buffer());
+            vertex_buffer.write().unwrap().copy_from_slice(&vertices);
+
+            builder
+                .bind_pipeline_graphics(pipeline.clone())
+                .bind_vertex_buffers(0, vertex_buffer)
+                .push_constants(
+                    pipeline.layout().clone(),
+                    0,
+                    DebugDrawPushConstants {
+                        view_projection: self.view_projection,
+                    },
+                )
+                .draw(vertices.len() as u32, 1, 0, 0)
+                .expect("failed to draw debug primitives");
+        }
+
+        builder.end_render_pass().unwrap();
+    }
+}
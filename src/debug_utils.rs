@@ -0,0 +1,103 @@
+use vulkano::{
+    command_buffer::{allocator::CommandBufferAllocator, AutoCommandBufferBuilder},
+    device::{Device, DeviceOwned},
+    instance::debug::DebugUtilsLabel,
+    VulkanObject,
+};
+
+use crate::name_tracked_resource;
+
+/// Whether `VK_EXT_debug_utils` is enabled on the instance of a `Device`.
+/// Without it, object names and labels are silently skipped.
+pub fn is_debug_utils_enabled(device: &Device) -> bool {
+    device.instance().enabled_extensions().ext_debug_utils
+}
+
+/// Names a Vulkan object owned by a `Device`, see `LogicalDevice::set_debug_name`.
+/// The name is also shown for the object by `print_live_resources`.
+pub fn set_debug_name<T>(device: &Device, object: &T, name: &str)
+where
+    T: VulkanObject + DeviceOwned,
+{
+    name_tracked_resource(object as *const T as *const (), name);
+
+    if is_debug_utils_enabled(device) {
+        device
+            .set_debug_utils_object_name(object, Some(name))
+            .expect("failed to set debug name");
+    }
+}
+
+/// Debug labels on command buffers, shown by tools like RenderDoc or Nsight to group commands.
+/// All methods are no-ops if `VK_EXT_debug_utils` isn't enabled.
+///
+/// ```ignore
+/// builder.label("shadows", [0.2, 0.2, 0.8, 1.0], |builder| shadows.record(builder));
+/// ```
+pub trait CommandBufferLabels {
+    /// Opens a label region, closed by `end_label`. A color of `[0.0; 4]` is ignored.
+    fn begin_label(&mut self, name: &str, color: [f32; 4]) -> &mut Self;
+
+    /// Closes the innermost label region opened with `begin_label` in this command buffer.
+    /// Prefer `label`, which always closes the region it opened.
+    ///
+    /// # Safety
+    /// A label region opened by `begin_label` must still be open in this command buffer.
+    unsafe fn end_label(&mut self) -> &mut Self;
+
+    /// Inserts a single label without opening a region.
+    fn insert_label(&mut self, name: &str, color: [f32; 4]) -> &mut Self;
+
+    /// Records `record` inside a label region.
+    fn label<R>(&mut self, name: &str, color: [f32; 4], record: impl FnOnce(&mut Self) -> R) -> R {
+        self.begin_label(name, color);
+        let result = record(self);
+        // Safety: closes the region opened above, `record` can't close it without `unsafe` itself
+        unsafe {
+            self.end_label();
+        }
+
+        result
+    }
+}
+
+impl<L, A> CommandBufferLabels for AutoCommandBufferBuilder<L, A>
+where
+    A: CommandBufferAllocator,
+{
+    fn begin_label(&mut self, name: &str, color: [f32; 4]) -> &mut Self {
+        if is_debug_utils_enabled(self.device()) {
+            self.begin_debug_utils_label(DebugUtilsLabel {
+                label_name: name.to_string(),
+                color,
+                ..Default::default()
+            })
+            .expect("failed to begin debug label");
+        }
+
+        self
+    }
+
+    unsafe fn end_label(&mut self) -> &mut Self {
+        if is_debug_utils_enabled(self.device()) {
+            // Safety: the caller guarantees an open label region
+            self.end_debug_utils_label()
+                .expect("failed to end debug label");
+        }
+
+        self
+    }
+
+    fn insert_label(&mut self, name: &str, color: [f32; 4]) -> &mut Self {
+        if is_debug_utils_enabled(self.device()) {
+            self.insert_debug_utils_label(DebugUtilsLabel {
+                label_name: name.to_string(),
+                color,
+                ..Default::default()
+            })
+            .expect("failed to insert debug label");
+        }
+
+        self
+    }
+}
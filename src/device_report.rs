@@ -0,0 +1,385 @@
+use std::{ops::RangeInclusive, sync::Arc};
+
+use vulkano::{
+    device::physical::PhysicalDevice,
+    format::{Format, FormatFeatures},
+    instance::Instance,
+};
+
+use crate::format_device_uuid;
+
+/// Raw values of the formats checked by `PhysicalDeviceReport::formats`:
+/// all core formats, the multi-planar YCbCr formats and the 4444 formats.
+const REPORTED_FORMATS: [RangeInclusive<i32>; 3] =
+    [1..=184, 1000156000..=1000156033, 1000340000..=1000340001];
+
+/// Capabilities of all physical devices of an instance, see `DeviceReport::new`.
+/// With the `serde` feature it can be (de)serialized, e.g. to attach it to bug reports as JSON.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceReport {
+    pub instance_api_version: String,
+    pub devices: Vec<PhysicalDeviceReport>,
+}
+
+/// Capabilities of a single physical device.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalDeviceReport {
+    /// Index of the device in `Instance::enumerate_physical_devices`.
+    pub index: usize,
+    pub name: String,
+    pub device_type: String,
+    pub api_version: String,
+    pub driver_version: u32,
+    pub driver_name: Option<String>,
+    pub driver_info: Option<String>,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    /// Hex encoded, `None` if the device doesn't report one.
+    pub device_uuid: Option<String>,
+    pub queue_families: Vec<QueueFamilyReport>,
+    pub memory_heaps: Vec<MemoryHeapReport>,
+    pub memory_types: Vec<MemoryTypeReport>,
+    pub limits: DeviceLimitsReport,
+    /// Only formats with at least one supported feature.
+    pub formats: Vec<FormatReport>,
+    pub extensions: Vec<String>,
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueueFamilyReport {
+    pub index: u32,
+    pub queue_flags: Vec<String>,
+    pub queue_count: u32,
+    pub timestamp_valid_bits: Option<u32>,
+    pub min_image_transfer_granularity: [u32; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryHeapReport {
+    pub index: u32,
+    pub size: u64,
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryTypeReport {
+    pub index: u32,
+    pub heap_index: u32,
+    pub property_flags: Vec<String>,
+}
+
+/// The limits most relevant for the engine, the full list is available through `PhysicalDevice::properties`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceLimitsReport {
+    pub max_image_dimension_2d: u32,
+    pub max_image_dimension_3d: u32,
+    pub max_image_array_layers: u32,
+    pub max_framebuffer_width: u32,
+    pub max_framebuffer_height: u32,
+    pub max_color_attachments: u32,
+    pub max_viewports: u32,
+    pub max_uniform_buffer_range: u32,
+    pub max_storage_buffer_range: u32,
+    pub max_push_constants_size: u32,
+    pub max_bound_descriptor_sets: u32,
+    pub max_per_stage_descriptor_samplers: u32,
+    pub max_memory_allocation_count: u32,
+    pub max_memory_allocation_size: Option<u64>,
+    pub min_uniform_buffer_offset_alignment: u64,
+    pub min_storage_buffer_offset_alignment: u64,
+    pub non_coherent_atom_size: u64,
+    pub max_compute_shared_memory_size: u32,
+    pub max_compute_work_group_count: [u32; 3],
+    pub max_compute_work_group_invocations: u32,
+    pub max_compute_work_group_size: [u32; 3],
+    pub subgroup_size: Option<u32>,
+    pub max_sampler_anisotropy: f32,
+    pub timestamp_period: f32,
+    pub timestamp_compute_and_graphics: bool,
+    pub framebuffer_color_sample_counts: Vec<String>,
+}
+
+/// Features of a format with linear and optimal tiling and in buffers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormatReport {
+    pub format: String,
+    pub linear_tiling_features: Vec<String>,
+    pub optimal_tiling_features: Vec<String>,
+    pub buffer_features: Vec<String>,
+}
+
+impl DeviceReport {
+    /// Enumerates all physical devices of the instance and collects their capabilities.
+    pub fn new(instance: &Arc<Instance>) -> Self {
+        log::debug!("DeviceReport::new");
+
+        let devices = instance
+            .enumerate_physical_devices()
+            .expect("failed to enumerate physical devices")
+            .enumerate()
+            .map(|(index, physical_device)| PhysicalDeviceReport::new(index, &physical_device))
+            .collect();
+
+        Self {
+            instance_api_version: instance.api_version().to_string(),
+            devices,
+        }
+    }
+}
+
+impl PhysicalDeviceReport {
+    /// Collects the capabilities of a physical device, `index` being its position in the enumeration.
+    pub fn new(index: usize, physical_device: &PhysicalDevice) -> Self {
+        let properties = physical_device.properties();
+        let memory_properties = physical_device.memory_properties();
+
+        let queue_families = physical_device
+            .queue_family_properties()
+            .iter()
+            .enumerate()
+            .map(|(index, queue_family)| QueueFamilyReport {
+                index: index as u32,
+                queue_flags: flag_names(queue_family.queue_flags),
+                queue_count: queue_family.queue_count,
+                timestamp_valid_bits: queue_family.timestamp_valid_bits,
+                min_image_transfer_granularity: queue_family.min_image_transfer_granularity,
+            })
+            .collect();
+
+        let memory_heaps = memory_properties
+            .memory_heaps
+            .iter()
+            .enumerate()
+            .map(|(index, heap)| MemoryHeapReport {
+                index: index as u32,
+                size: heap.size,
+                flags: flag_names(heap.flags),
+            })
+            .collect();
+
+        let memory_types = memory_properties
+            .memory_types
+            .iter()
+            .enumerate()
+            .map(|(index, memory_type)| MemoryTypeReport {
+                index: index as u32,
+                heap_index: memory_type.heap_index,
+                property_flags: flag_names(memory_type.property_flags),
+            })
+            .collect();
+
+        let limits = DeviceLimitsReport {
+            max_image_dimension_2d: properties.max_image_dimension2_d,
+            max_image_dimension_3d: properties.max_image_dimension3_d,
+            max_image_array_layers: properties.max_image_array_layers,
+            max_framebuffer_width: properties.max_framebuffer_width,
+            max_framebuffer_height: properties.max_framebuffer_height,
+            max_color_attachments: properties.max_color_attachments,
+            max_viewports: properties.max_viewports,
+            max_uniform_buffer_range: properties.max_uniform_buffer_range,
+            max_storage_buffer_range: properties.max_storage_buffer_range,
+            max_push_constants_size: properties.max_push_constants_size,
+            max_bound_descriptor_sets: properties.max_bound_descriptor_sets,
+            max_per_stage_descriptor_samplers: properties.max_per_stage_descriptor_samplers,
+            max_memory_allocation_count: properties.max_memory_allocation_count,
+            max_memory_allocation_size: properties.max_memory_allocation_size,
+            min_uniform_buffer_offset_alignment: properties
+                .min_uniform_buffer_offset_alignment
+                .as_devicesize(),
+            min_storage_buffer_offset_alignment: properties
+                .min_storage_buffer_offset_alignment
+                .as_devicesize(),
+            non_coherent_atom_size: properties.non_coherent_atom_size.as_devicesize(),
+            max_compute_shared_memory_size: properties.max_compute_shared_memory_size,
+            max_compute_work_group_count: properties.max_compute_work_group_count,
+            max_compute_work_group_invocations: properties.max_compute_work_group_invocations,
+            max_compute_work_group_size: properties.max_compute_work_group_size,
+            subgroup_size: properties.subgroup_size,
+            max_sampler_anisotropy: properties.max_sampler_anisotropy,
+            timestamp_period: properties.timestamp_period,
+            timestamp_compute_and_graphics: properties.timestamp_compute_and_graphics,
+            framebuffer_color_sample_counts: flag_names(properties.framebuffer_color_sample_counts),
+        };
+
+        // Formats of extensions the device doesn't support fail validation and are skipped
+        let formats = REPORTED_FORMATS
+            .into_iter()
+            .flatten()
+            .filter_map(|raw| Format::try_from(ash::vk::Format::from_raw(raw)).ok())
+            .filter_map(|format| {
+                let format_properties = physical_device.format_properties(format).ok()?;
+                let features = format_properties.linear_tiling_features
+                    | format_properties.optimal_tiling_features
+                    | format_properties.buffer_features;
+
+                (features != FormatFeatures::empty()).then(|| FormatReport {
+                    format: format!("{:?}", format),
+                    linear_tiling_features: flag_names(format_properties.linear_tiling_features),
+                    optimal_tiling_features: flag_names(format_properties.optimal_tiling_features),
+                    buffer_features: flag_names(format_properties.buffer_features),
+                })
+            })
+            .collect();
+
+        let extensions = physical_device
+            .supported_extensions()
+            .into_iter()
+            .filter(|(_, supported)| *supported)
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        let features = physical_device
+            .supported_features()
+            .into_iter()
+            .filter(|(_, supported)| *supported)
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        Self {
+            index,
+            name: properties.device_name.clone(),
+            device_type: format!("{:?}", properties.device_type),
+            api_version: physical_device.api_version().to_string(),
+            driver_version: properties.driver_version,
+            driver_name: properties.driver_name.clone(),
+            driver_info: properties.driver_info.clone(),
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            device_uuid: properties.device_uuid.as_ref().map(format_device_uuid),
+            queue_families,
+            memory_heaps,
+            memory_types,
+            limits,
+            formats,
+            extensions,
+            features,
+        }
+    }
+}
+
+impl std::fmt::Display for DeviceReport {
+    /// Formats the report as human readable tables.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Instance API Version: {}", self.instance_api_version)?;
+
+        for device in &self.devices {
+            writeln!(f)?;
+            write!(f, "{}", device)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for PhysicalDeviceReport {
+    /// Formats the report as human readable tables.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "=== Device {}: {} ===", self.index, self.name)?;
+        writeln!(f, "{:<24} {}", "Type", self.device_type)?;
+        writeln!(f, "{:<24} {}", "API Version", self.api_version)?;
+        writeln!(f, "{:<24} {}", "Driver Version", self.driver_version)?;
+        if let Some(driver_name) = &self.driver_name {
+            writeln!(f, "{:<24} {}", "Driver Name", driver_name)?;
+        }
+        if let Some(driver_info) = &self.driver_info {
+            writeln!(f, "{:<24} {}", "Driver Info", driver_info)?;
+        }
+        writeln!(f, "{:<24} {:#06x}", "Vendor ID", self.vendor_id)?;
+        writeln!(f, "{:<24} {:#06x}", "Device ID", self.device_id)?;
+        if let Some(device_uuid) = &self.device_uuid {
+            writeln!(f, "{:<24} {}", "Device UUID", device_uuid)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:<6} {:<7} {:<10} Flags", "Queue", "Count", "Timestamp")?;
+        for queue_family in &self.queue_families {
+            writeln!(
+                f,
+                "{:<6} {:<7} {:<10} {}",
+                queue_family.index,
+                queue_family.queue_count,
+                queue_family
+                    .timestamp_valid_bits
+                    .map_or("-".to_string(), |bits| format!("{} bits", bits)),
+                queue_family.queue_flags.join(" | ")
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:<6} {:<14} Flags", "Heap", "Size (MiB)")?;
+        for heap in &self.memory_heaps {
+            writeln!(
+                f,
+                "{:<6} {:<14.2} {}",
+                heap.index,
+                heap.size as f64 / (1024.0 * 1024.0),
+                heap.flags.join(" | ")
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:<6} {:<6} Properties", "Type", "Heap")?;
+        for memory_type in &self.memory_types {
+            writeln!(
+                f,
+                "{:<6} {:<6} {}",
+                memory_type.index,
+                memory_type.heap_index,
+                memory_type.property_flags.join(" | ")
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:#?}", self.limits)?;
+
+        writeln!(f)?;
+        writeln!(f, "Formats (number of supported features):")?;
+        writeln!(
+            f,
+            "{:<36} {:<8} {:<8} Buffer",
+            "Format", "Linear", "Optimal"
+        )?;
+        for format in &self.formats {
+            writeln!(
+                f,
+                "{:<36} {:<8} {:<8} {}",
+                format.format,
+                format.linear_tiling_features.len(),
+                format.optimal_tiling_features.len(),
+                format.buffer_features.len()
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Extensions ({}):", self.extensions.len())?;
+        for extension in &self.extensions {
+            writeln!(f, "  {}", extension)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Features ({}):", self.features.len())?;
+        for feature in &self.features {
+            writeln!(f, "  {}", feature)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits the `Debug` output of vulkano's flag types, e.g. `GRAPHICS | COMPUTE`, into the flag names.
+fn flag_names<T: std::fmt::Debug>(flags: T) -> Vec<String> {
+    let flags = format!("{:?}", flags);
+    if flags == "empty()" {
+        return Vec::new();
+    }
+
+    flags.split(" | ").map(str::to_string).collect()
+}
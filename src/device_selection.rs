@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use vulkano::{
+    device::physical::{PhysicalDevice, PhysicalDeviceType},
+    instance::Instance,
+};
+
+/// Environment variable overriding `DeviceSelector::Automatic`, parsed with `DeviceSelector::parse`.
+pub const DEVICE_SELECTOR_ENV: &str = "VULKAN_ENGINE_DEVICE";
+
+/// Selects the `PhysicalDevice` an engine is created on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DeviceSelector {
+    /// The device given by `DEVICE_SELECTOR_ENV` if set, otherwise the best device type:
+    /// discrete, integrated, virtual, CPU, other.
+    #[default]
+    Automatic,
+    /// Index of the device in `Instance::enumerate_physical_devices`, as listed by `print_physical_devices`.
+    Index(usize),
+    /// Case-insensitive substring of the device name. The best device type wins if several match.
+    Name(String),
+    /// The `device_uuid` of the device.
+    Uuid([u8; 16]),
+}
+
+impl DeviceSelector {
+    /// Parses `index:<n>`, `name:<substring>` or `uuid:<hex>`.
+    /// Without a prefix, numbers are indices, 32 hex digits (dashes are ignored) are UUIDs and anything else is a name.
+    /// An empty value is `Automatic`. Returns an error for an invalid prefixed index or UUID.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+
+        if value.is_empty() {
+            Ok(Self::Automatic)
+        } else if let Some(index) = value.strip_prefix("index:") {
+            index
+                .trim()
+                .parse()
+                .map(Self::Index)
+                .map_err(|_| format!("invalid device index '{}'", index))
+        } else if let Some(name) = value.strip_prefix("name:") {
+            Ok(Self::Name(name.trim().to_string()))
+        } else if let Some(uuid) = value.strip_prefix("uuid:") {
+            parse_device_uuid(uuid)
+                .map(Self::Uuid)
+                .ok_or_else(|| format!("invalid device UUID '{}'", uuid))
+        } else if let Ok(index) = value.parse() {
+            Ok(Self::Index(index))
+        } else if let Some(uuid) = parse_device_uuid(value) {
+            Ok(Self::Uuid(uuid))
+        } else {
+            Ok(Self::Name(value.to_string()))
+        }
+    }
+
+    /// Returns the selector given by `DEVICE_SELECTOR_ENV`.
+    /// Falls back to `Automatic` if it isn't set or invalid, the latter with a warning.
+    pub fn from_env() -> Self {
+        match std::env::var(DEVICE_SELECTOR_ENV) {
+            Ok(value) => Self::parse(&value).unwrap_or_else(|e| {
+                log::warn!("Ignoring {}: {}", DEVICE_SELECTOR_ENV, e);
+                Self::Automatic
+            }),
+            Err(_) => Self::Automatic,
+        }
+    }
+
+    /// Whether a device, at `index` in the enumeration, is matched. `Automatic` matches every device.
+    pub fn matches(&self, index: usize, physical_device: &PhysicalDevice) -> bool {
+        let properties = physical_device.properties();
+
+        match self {
+            Self::Automatic => true,
+            Self::Index(selected) => *selected == index,
+            Self::Name(name) => properties
+                .device_name
+                .to_lowercase()
+                .contains(&name.to_lowercase()),
+            Self::Uuid(uuid) => properties.device_uuid == Some(*uuid),
+        }
+    }
+
+    /// Selects the best device matched by this selector for which `suitable` returns `Some`,
+    /// e.g. with the queue family to use. `Automatic` is replaced by `DEVICE_SELECTOR_ENV` if set.
+    /// Panics if no device matches or none of the matching ones is suitable.
+    pub(crate) fn select<T>(
+        &self,
+        instance: &Arc<Instance>,
+        suitable: impl Fn(&Arc<PhysicalDevice>) -> Option<T>,
+    ) -> (Arc<PhysicalDevice>, T) {
+        let selector = match self {
+            Self::Automatic => Self::from_env(),
+            selector => selector.clone(),
+        };
+        log::debug!("Selecting physical device: {}", selector);
+
+        let matching = instance
+            .enumerate_physical_devices()
+            .expect("failed enumerating physical devices")
+            .enumerate()
+            .filter(|(index, physical_device)| selector.matches(*index, physical_device))
+            .map(|(_, physical_device)| physical_device)
+            .collect::<Vec<_>>();
+        if matching.is_empty() {
+            panic!("no physical device matches {}", selector);
+        }
+
+        matching
+            .into_iter()
+            .filter_map(|physical_device| {
+                let suitable = suitable(&physical_device);
+                if suitable.is_none() {
+                    log::debug!(
+                        "Physical device '{}' isn't suitable",
+                        physical_device.properties().device_name
+                    );
+                }
+
+                suitable.map(|suitable| (physical_device, suitable))
+            })
+            .min_by_key(|(physical_device, _)| get_device_type_rank(physical_device))
+            .unwrap_or_else(|| panic!("no physical device matching {} is suitable", selector))
+    }
+}
+
+impl std::fmt::Display for DeviceSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Automatic => write!(f, "the best device"),
+            Self::Index(index) => write!(f, "index {}", index),
+            Self::Name(name) => write!(f, "name '{}'", name),
+            Self::Uuid(uuid) => write!(f, "UUID {}", format_device_uuid(uuid)),
+        }
+    }
+}
+
+/// Formats a `device_uuid` as 32 lowercase hex digits, as accepted by `DeviceSelector::parse`.
+pub fn format_device_uuid(uuid: &[u8; 16]) -> String {
+    uuid.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Parses 32 hex digits, ignoring dashes.
+fn parse_device_uuid(value: &str) -> Option<[u8; 16]> {
+    let digits = value.trim().replace('-', "");
+    if digits.len() != 32 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut uuid = [0; 16];
+    for (index, byte) in uuid.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).ok()?;
+    }
+
+    Some(uuid)
+}
+
+/// Lower is better: discrete, integrated, virtual, CPU, other.
+fn get_device_type_rank(physical_device: &PhysicalDevice) -> u32 {
+    match physical_device.properties().device_type {
+        PhysicalDeviceType::DiscreteGpu => 0,
+        PhysicalDeviceType::IntegratedGpu => 1,
+        PhysicalDeviceType::VirtualGpu => 2,
+        PhysicalDeviceType::Cpu => 3,
+        PhysicalDeviceType::Other => 4,
+        _ => 5,
+    }
+}
+
+/// Prints out the index, name, type and UUID of all physical devices, to be used with `DeviceSelector`.
+pub fn print_physical_devices(instance: &Arc<Instance>, log_level: log::Level) {
+    for (index, physical_device) in instance
+        .enumerate_physical_devices()
+        .expect("failed enumerating physical devices")
+        .enumerate()
+    {
+        let properties = physical_device.properties();
+        log::log!(
+            log_level,
+            "Physical Device {}: {} ({:?}); UUID: {}",
+            index,
+            properties.device_name,
+            properties.device_type,
+            properties
+                .device_uuid
+                .as_ref()
+                .map_or("-".to_string(), format_device_uuid)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: [u8; 16] = [
+        0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd,
+        0xef,
+    ];
+
+    #[test]
+    fn parse_selectors() {
+        assert_eq!(DeviceSelector::parse(" "), Ok(DeviceSelector::Automatic));
+        assert_eq!(DeviceSelector::parse("2"), Ok(DeviceSelector::Index(2)));
+        assert_eq!(
+            DeviceSelector::parse("index: 3"),
+            Ok(DeviceSelector::Index(3))
+        );
+        assert_eq!(
+            DeviceSelector::parse("NVIDIA"),
+            Ok(DeviceSelector::Name("NVIDIA".to_string()))
+        );
+        assert_eq!(
+            DeviceSelector::parse("name:42"),
+            Ok(DeviceSelector::Name("42".to_string()))
+        );
+        assert_eq!(
+            DeviceSelector::parse("0123456789abcdef0123456789abcdef"),
+            Ok(DeviceSelector::Uuid(UUID))
+        );
+        assert_eq!(
+            DeviceSelector::parse("uuid:01234567-89ab-cdef-0123-456789ABCDEF"),
+            Ok(DeviceSelector::Uuid(UUID))
+        );
+    }
+
+    #[test]
+    fn parse_invalid_selectors() {
+        assert!(DeviceSelector::parse("index:first").is_err());
+        assert!(DeviceSelector::parse("index:-1").is_err());
+        assert!(DeviceSelector::parse("uuid:0123").is_err());
+        assert!(DeviceSelector::parse("uuid:0123456789abcdef0123456789abcdeg").is_err());
+
+        // Without a prefix, anything which isn't an index or a UUID is a name
+        assert_eq!(
+            DeviceSelector::parse("0123"),
+            Ok(DeviceSelector::Index(123))
+        );
+        assert_eq!(
+            DeviceSelector::parse("-1"),
+            Ok(DeviceSelector::Name("-1".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_uuids() {
+        assert_eq!(
+            parse_device_uuid("0123456789abcdef0123456789abcdef"),
+            Some(UUID)
+        );
+        assert_eq!(
+            parse_device_uuid(" 01234567-89AB-CDEF-0123-456789abcdef "),
+            Some(UUID)
+        );
+        assert_eq!(parse_device_uuid(&format_device_uuid(&UUID)), Some(UUID));
+
+        assert_eq!(parse_device_uuid(""), None);
+        assert_eq!(parse_device_uuid("0123456789abcdef0123456789abcde"), None);
+        assert_eq!(parse_device_uuid("0123456789abcdef0123456789abcdef0"), None);
+        assert_eq!(parse_device_uuid("0123456789abcdef0123456789abcdeg"), None);
+        assert_eq!(parse_device_uuid("+123456789abcdef0123456789abcdef"), None);
+    }
+}
@@ -0,0 +1,530 @@
+use std::{collections::HashMap, sync::Arc};
+
+use egui::{
+    epaint::{ImageDelta, Primitive},
+    ClippedPrimitive, ImageData, TextureFilter, TextureId, TexturesDelta, ViewportId,
+};
+use egui_winit::EventResponse;
+use vulkano::{
+    buffer::{
+        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo},
+        BufferContents, BufferUsage,
+    },
+    command_buffer::{
+        BufferImageCopy, CopyBufferToImageInfo, RenderPassBeginInfo, SubpassContents,
+    },
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    device::Device,
+    format::{Format, NumericType},
+    image::{
+        view::ImageView, ImageAccess, ImageCreateFlags, ImageDimensions, ImageUsage,
+        ImageViewAbstract, StorageImage, SwapchainImage,
+    },
+    memory::allocator::{MemoryUsage, StandardMemoryAllocator},
+    pipeline::{
+        graphics::{
+            color_blend::{AttachmentBlend, BlendFactor, BlendOp, ColorBlendState},
+            input_assembly::InputAssemblyState,
+            vertex_input::Vertex,
+            viewport::{Scissor, Viewport, ViewportState},
+        },
+        GraphicsPipeline, Pipeline, PipelineBindPoint,
+    },
+    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass},
+    sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo},
+};
+use winit::event::WindowEvent;
+
+use crate::{
+    convert_rgba8_pixels, track_resource, track_resource_once, AbstractEngine,
+    EngineCommandBufferBuilder, FormatUsage, GlslShaderKind, GraphicalEngine, ResourceKind,
+    SSpriteVertex, SRGB_FORMAT_PREFERENCES,
+};
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct EguiPushConstants {
+    screen_size: [f32; 2],
+    srgb_target: u32,
+}
+
+struct EguiTexture {
+    /// `None` for user textures, which can't be updated by egui.
+    image: Option<Arc<StorageImage>>,
+    set: Arc<PersistentDescriptorSet>,
+}
+
+/// Draws an egui user interface as an overlay onto the `Swapchain` of a `GraphicalEngine`.
+///
+/// Per frame, forward all `WindowEvent`s to `on_window_event`, build the UI inside `run`
+/// and record it with `record` after the scene has been rendered.
+pub struct EguiRenderer {
+    context: egui::Context,
+    winit_state: egui_winit::State,
+    device: Arc<Device>,
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    render_pass: Arc<RenderPass>,
+    pipeline: Arc<GraphicsPipeline>,
+    swap_chain_image_views: Vec<Arc<ImageView<SwapchainImage>>>,
+    srgb_target: bool,
+    /// The format of the textures egui uploads
+    texture_format: Format,
+    buffer_allocator: SubbufferAllocator,
+    descriptor_set_allocator: StandardDescriptorSetAllocator,
+    textures: HashMap<TextureId, EguiTexture>,
+    next_user_texture_id: u64,
+    textures_delta: TexturesDelta,
+    primitives: Vec<ClippedPrimitive>,
+    pixels_per_point: f32,
+}
+
+impl EguiRenderer {
+    /// Creates an `EguiRenderer` for the window of the `GraphicalEngine`.
+    #[track_caller]
+    pub fn new(engine: &GraphicalEngine, memory_allocator: Arc<StandardMemoryAllocator>) -> Self {
+        log::debug!("EguiRenderer::new");
+
+        let device = engine.get_logical_device();
+        let window = engine.get_winit_window();
+        let format = engine.get_swap_chain().image_format();
+
+        let winit_state = egui_winit::State::new(
+            ViewportId::ROOT,
+            &*window,
+            Some(window.scale_factor() as f32),
+            Some(
+                device
+                    .get_device()
+                    .physical_device()
+                    .properties()
+                    .max_image_dimension2_d as usize,
+            ),
+        );
+
+        // The scene is loaded, so the UI is drawn on top of it
+        let render_pass = vulkano::single_pass_renderpass!(
+            device.get_device(),
+            attachments: {
+                color: {
+                    load: Load,
+                    store: Store,
+                    format: format,
+                    samples: 1,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        )
+        .expect("failed to create egui render pass");
+
+        let vertex_shader = device.create_glsl_shader_module(
+            include_str!("../shaders/egui/egui.vert"),
+            GlslShaderKind::Vertex,
+        );
+        let fragment_shader = device.create_glsl_shader_module(
+            include_str!("../shaders/egui/egui.frag"),
+            GlslShaderKind::Fragment,
+        );
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input_state(SSpriteVertex::per_vertex())
+            .vertex_shader(vertex_shader.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .fragment_shader(fragment_shader.entry_point("main").unwrap(), ())
+            // egui outputs premultiplied alpha
+            .color_blend_state(ColorBlendState::new(1).blend(AttachmentBlend {
+                color_op: BlendOp::Add,
+                color_source: BlendFactor::One,
+                color_destination: BlendFactor::OneMinusSrcAlpha,
+                alpha_op: BlendOp::Add,
+                alpha_source: BlendFactor::OneMinusDstAlpha,
+                alpha_destination: BlendFactor::One,
+            }))
+            .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
+            .build(device.get_device())
+            .expect("failed to create egui pipeline");
+        track_resource(ResourceKind::Pipeline, &pipeline);
+        device.set_debug_name(&*pipeline, "EguiRenderer Pipeline");
+        GraphicalEngine::validate_push_constants::<EguiPushConstants>(pipeline.layout());
+
+        Self {
+            context: egui::Context::default(),
+            winit_state,
+            device: device.get_device(),
+            memory_allocator: memory_allocator.clone(),
+            render_pass,
+            pipeline,
+            swap_chain_image_views: Self::create_swap_chain_image_views(engine),
+            srgb_target: Self::is_srgb(format),
+            texture_format: device.select_format(&SRGB_FORMAT_PREFERENCES, &[FormatUsage::Sampled]),
+            buffer_allocator: SubbufferAllocator::new(
+                memory_allocator,
+                SubbufferAllocatorCreateInfo {
+                    buffer_usage: BufferUsage::VERTEX_BUFFER
+                        | BufferUsage::INDEX_BUFFER
+                        | BufferUsage::TRANSFER_SRC,
+                    memory_usage: MemoryUsage::Upload,
+                    ..Default::default()
+                },
+            ),
+            descriptor_set_allocator: StandardDescriptorSetAllocator::new(device.get_device()),
+            textures: HashMap::new(),
+            next_user_texture_id: 0,
+            textures_delta: TexturesDelta::default(),
+            primitives: Vec::new(),
+            pixels_per_point: window.scale_factor() as f32,
+        }
+    }
+
+    fn create_swap_chain_image_views(
+        engine: &GraphicalEngine,
+    ) -> Vec<Arc<ImageView<SwapchainImage>>> {
+        engine
+            .get_swap_chain_images()
+            .into_iter()
+            .map(|image| ImageView::new_default(image).expect("failed to create image view"))
+            .collect()
+    }
+
+    fn is_srgb(format: Format) -> bool {
+        format.type_color() == Some(NumericType::SRGB)
+    }
+
+    /// Must be called after `GraphicalEngine::recreate_swap_chain_and_images`.
+    /// The render pass and pipeline stay bound to the device and the swapchain format the renderer was created with,
+    /// so on `GraphicalEngine::recover` the renderer has to be dropped in `RecoveryStage::Release` and recreated with `new`.
+    pub fn resize(&mut self, engine: &GraphicalEngine) {
+        let format = engine.get_swap_chain().image_format();
+        assert!(
+            Arc::ptr_eq(&self.device, &engine.get_logical_device().get_device())
+                && self.render_pass.attachments()[0].format == Some(format),
+            "EguiRenderer must be recreated after the device or the swapchain format changed"
+        );
+
+        self.swap_chain_image_views = Self::create_swap_chain_image_views(engine);
+        self.srgb_target = Self::is_srgb(format);
+    }
+
+    /// Returns the egui `Context`, e.g. to change the style.
+    pub fn get_context(&self) -> egui::Context {
+        self.context.clone()
+    }
+
+    /// Translates a winit `WindowEvent` into egui input.
+    /// If the returned response is `consumed`, the event shouldn't be handled by the application.
+    pub fn on_window_event(&mut self, event: &WindowEvent) -> EventResponse {
+        self.winit_state.on_window_event(&self.context, event)
+    }
+
+    /// Runs the UI code of a frame and prepares its meshes and textures for `record`.
+    pub fn run<F>(&mut self, engine: &GraphicalEngine, run_ui: F)
+    where
+        F: FnOnce(&egui::Context),
+    {
+        let window = engine.get_winit_window();
+
+        let input = self.winit_state.take_egui_input(&window);
+        let output = self.context.run(input, run_ui);
+        self.winit_state
+            .handle_platform_output(&window, &self.context, output.platform_output);
+
+        self.primitives = self
+            .context
+            .tessellate(output.shapes, output.pixels_per_point);
+        self.pixels_per_point = output.pixels_per_point;
+        self.textures_delta.append(output.textures_delta);
+    }
+
+    fn create_sampler(&self, mag_filter: Filter, min_filter: Filter) -> Arc<Sampler> {
+        Sampler::new(
+            self.device.clone(),
+            SamplerCreateInfo {
+                mag_filter,
+                min_filter,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )
+        .expect("failed to create egui sampler")
+    }
+
+    fn get_filter(filter: TextureFilter) -> Filter {
+        match filter {
+            TextureFilter::Nearest => Filter::Nearest,
+            TextureFilter::Linear => Filter::Linear,
+        }
+    }
+
+    #[track_caller]
+    fn create_descriptor_set(
+        &self,
+        image_view: Arc<dyn ImageViewAbstract>,
+        mag_filter: Filter,
+        min_filter: Filter,
+    ) -> Arc<PersistentDescriptorSet> {
+        let set = PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            self.pipeline.layout().set_layouts()[0].clone(),
+            [
+                WriteDescriptorSet::image_view(0, image_view),
+                WriteDescriptorSet::sampler(1, self.create_sampler(mag_filter, min_filter)),
+            ],
+        )
+        .expect("failed to create egui descriptor set");
+        track_resource(ResourceKind::DescriptorSet, &set);
+
+        set
+    }
+
+    /// Makes an image usable in egui, e.g. with `egui::Image`.
+    /// The image must have been created with `ImageUsage::SAMPLED`.
+    #[track_caller]
+    pub fn register_user_texture(
+        &mut self,
+        image_view: Arc<dyn ImageViewAbstract>,
+        filter: Filter,
+    ) -> TextureId {
+        let id = TextureId::User(self.next_user_texture_id);
+        self.next_user_texture_id += 1;
+
+        let set = self.create_descriptor_set(image_view, filter, filter);
+        self.textures.insert(id, EguiTexture { image: None, set });
+
+        id
+    }
+
+    /// Removes an image registered with `register_user_texture`.
+    pub fn unregister_user_texture(&mut self, id: TextureId) {
+        self.textures.remove(&id);
+    }
+
+    #[track_caller]
+    fn update_texture(
+        &mut self,
+        builder: &mut EngineCommandBufferBuilder,
+        id: TextureId,
+        delta: ImageDelta,
+    ) {
+        let [width, height] = delta.image.size().map(|value| value as u32);
+        let mut pixels: Vec<u8> = match &delta.image {
+            ImageData::Color(image) => image
+                .pixels
+                .iter()
+                .flat_map(|color| color.to_array())
+                .collect(),
+            ImageData::Font(image) => image
+                .srgba_pixels(None)
+                .flat_map(|color| color.to_array())
+                .collect(),
+        };
+        convert_rgba8_pixels(self.texture_format, &mut pixels);
+
+        let upload_buffer = self
+            .buffer_allocator
+            .allocate_slice::<u8>(pixels.len() as u64)
+            .expect("failed to allocate egui texture upload buffer");
+        track_resource_once(ResourceKind::Buffer, upload_buffer.buffer());
+        upload_buffer.write().unwrap().copy_from_slice(&pixels);
+
+        let (image, offset) = match delta.pos {
+            // Partial update of an existing texture, e.g. when new glyphs are added to the font texture
+            Some([x, y]) => (
+                self.textures
+                    .get(&id)
+                    .and_then(|texture| texture.image.clone())
+                    .expect("egui updated an unknown texture"),
+                [x as u32, y as u32, 0],
+            ),
+            None => {
+                let image = StorageImage::with_usage(
+                    &self.memory_allocator,
+                    ImageDimensions::Dim2d {
+                        width,
+                        height,
+                        array_layers: 1,
+                    },
+                    self.texture_format,
+                    ImageUsage::SAMPLED | ImageUsage::TRANSFER_DST,
+                    ImageCreateFlags::empty(),
+                    None,
+                )
+                .expect("failed to create egui texture");
+                track_resource(ResourceKind::Image, image.inner().image);
+
+                let set = self.create_descriptor_set(
+                    ImageView::new_default(image.clone())
+                        .expect("failed to create egui texture view"),
+                    Self::get_filter(delta.options.magnification),
+                    Self::get_filter(delta.options.minification),
+                );
+                self.textures.insert(
+                    id,
+                    EguiTexture {
+                        image: Some(image.clone()),
+                        set,
+                    },
+                );
+
+                (image, [0, 0, 0])
+            }
+        };
+
+        builder
+            .copy_buffer_to_image(CopyBufferToImageInfo {
+                regions: [BufferImageCopy {
+                    image_subresource: image.subresource_layers(),
+                    image_offset: offset,
+                    image_extent: [width, height, 1],
+                    ..Default::default()
+                }]
+                .into(),
+                ..CopyBufferToImageInfo::buffer_image(upload_buffer, image)
+            })
+            .expect("failed to upload egui texture");
+    }
+
+    /// Records the UI of the last `run` onto the `SwapchainImage` with the given index.
+    /// Must be recorded after the scene and outside of any render pass, as texture uploads are recorded as well.
+    #[track_caller]
+    pub fn record(&mut self, builder: &mut EngineCommandBufferBuilder, image_index: u32) {
+        for (id, delta) in std::mem::take(&mut self.textures_delta.set) {
+            self.update_texture(builder, id, delta);
+        }
+
+        let color = self.swap_chain_image_views[image_index as usize].clone();
+        let [width, height] = color.image().dimensions().width_height();
+        let frame_buffer = Framebuffer::new(
+            self.render_pass.clone(),
+            FramebufferCreateInfo {
+                attachments: vec![color],
+                ..Default::default()
+            },
+        )
+        .expect("failed to create egui framebuffer");
+
+        let layout = self.pipeline.layout().clone();
+        builder
+            .begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![None],
+                    ..RenderPassBeginInfo::framebuffer(frame_buffer)
+                },
+                SubpassContents::Inline,
+            )
+            .unwrap()
+            .set_viewport(
+                0,
+                [Viewport {
+                    origin: [0.0, 0.0],
+                    dimensions: [width as f32, height as f32],
+                    depth_range: 0.0..1.0,
+                }],
+            )
+            .bind_pipeline_graphics(self.pipeline.clone())
+            .push_constants(
+                layout.clone(),
+                0,
+                EguiPushConstants {
+                    screen_size: [
+                        width as f32 / self.pixels_per_point,
+                        height as f32 / self.pixels_per_point,
+                    ],
+                    srgb_target: self.srgb_target as u32,
+                },
+            );
+
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in &self.primitives
+        {
+            let mesh = match primitive {
+                Primitive::Mesh(mesh) if !mesh.indices.is_empty() => mesh,
+                Primitive::Mesh(_) => continue,
+                Primitive::Callback(_) => {
+                    log::warn!("egui paint callbacks are not supported");
+                    continue;
+                }
+            };
+            let Some(texture) = self.textures.get(&mesh.texture_id) else {
+                log::warn!("egui used an unknown texture: {:?}", mesh.texture_id);
+                continue;
+            };
+
+            // The clip rectangle is in points and may exceed the framebuffer
+            let min_x = (clip_rect.min.x * self.pixels_per_point)
+                .round()
+                .clamp(0.0, width as f32) as u32;
+            let min_y = (clip_rect.min.y * self.pixels_per_point)
+                .round()
+                .clamp(0.0, height as f32) as u32;
+            let max_x = (clip_rect.max.x * self.pixels_per_point)
+                .round()
+                .clamp(min_x as f32, width as f32) as u32;
+            let max_y = (clip_rect.max.y * self.pixels_per_point)
+                .round()
+                .clamp(min_y as f32, height as f32) as u32;
+            if max_x == min_x || max_y == min_y {
+                continue;
+            }
+
+            let vertex_buffer = self
+                .buffer_allocator
+                .allocate_slice::<SSpriteVertex>(mesh.vertices.len() as u64)
+                .expect("failed to allocate egui vertices");
+            track_resource_once(ResourceKind::Buffer, vertex_buffer.buffer());
+            for (vertex, egui_vertex) in vertex_buffer
+                .write()
+                .unwrap()
+                .iter_mut()
+                .zip(&mesh.vertices)
+            {
+                *vertex = SSpriteVertex {
+                    position: [egui_vertex.pos.x, egui_vertex.pos.y],
+                    uv: [egui_vertex.uv.x, egui_vertex.uv.y],
+                    color: egui_vertex
+                        .color
+                        .to_array()
+                        .map(|value| value as f32 / 255.0),
+                };
+            }
+
+            let index_buffer = self
+                .buffer_allocator
+                .allocate_slice::<u32>(mesh.indices.len() as u64)
+                .expect("failed to allocate egui indices");
+            track_resource_once(ResourceKind::Buffer, index_buffer.buffer());
+            index_buffer.write().unwrap().copy_from_slice(&mesh.indices);
+
+            builder
+                .set_scissor(
+                    0,
+                    [Scissor {
+                        origin: [min_x, min_y],
+                        dimensions: [max_x - min_x, max_y - min_y],
+                    }],
+                )
+                .bind_descriptor_sets(
+                    PipelineBindPoint::Graphics,
+                    layout.clone(),
+                    0,
+                    texture.set.clone(),
+                )
+                .bind_vertex_buffers(0, vertex_buffer)
+                .bind_index_buffer(index_buffer)
+                .draw_indexed(mesh.indices.len() as u32, 1, 0, 0, 0)
+                .expect("failed to draw egui mesh");
+        }
+
+        builder.end_render_pass().unwrap();
+
+        // egui frees textures only after the frame using them has been drawn
+        for id in std::mem::take(&mut self.textures_delta.free) {
+            self.textures.remove(&id);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use vulkano::{
+    command_buffer::CommandBufferExecError,
+    swapchain::AcquireError,
+    sync::{fence::FenceError, FlushError},
+    OomError, VulkanError,
+};
+
+/// An error of the submit or present path of an engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    /// The device was lost, e.g. after a GPU hang or driver reset.
+    DeviceLost,
+    /// Free device memory and retry, recreating the device doesn't help.
+    OutOfDeviceMemory,
+    OutOfHostMemory,
+    /// The window surface was lost, e.g. because the window was closed.
+    SurfaceLost,
+    /// Waiting for the GPU took longer than the given timeout.
+    Timeout,
+    /// The device can't be recovered while the application still references the old `Swapchain`, see `RecoveryStage::Release`.
+    SwapchainInUse,
+    /// Any other error, described by its message.
+    Other(String),
+}
+
+impl EngineError {
+    /// Whether the device has to be recreated with `recover` before it can be used again.
+    pub fn requires_recovery(&self) -> bool {
+        matches!(self, Self::DeviceLost)
+    }
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DeviceLost => write!(f, "the device was lost"),
+            Self::OutOfDeviceMemory => write!(f, "out of device memory"),
+            Self::OutOfHostMemory => write!(f, "out of host memory"),
+            Self::SurfaceLost => write!(f, "the surface was lost"),
+            Self::Timeout => write!(f, "the timeout elapsed"),
+            Self::SwapchainInUse => write!(
+                f,
+                "the old swapchain is still in use, release all framebuffers and image views of it in RecoveryStage::Release"
+            ),
+            Self::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<OomError> for EngineError {
+    fn from(error: OomError) -> Self {
+        match error {
+            OomError::OutOfDeviceMemory => Self::OutOfDeviceMemory,
+            OomError::OutOfHostMemory => Self::OutOfHostMemory,
+        }
+    }
+}
+
+impl From<FlushError> for EngineError {
+    fn from(error: FlushError) -> Self {
+        match error {
+            FlushError::DeviceLost => Self::DeviceLost,
+            FlushError::OomError(error) => error.into(),
+            FlushError::SurfaceLost => Self::SurfaceLost,
+            error => Self::Other(error.to_string()),
+        }
+    }
+}
+
+impl From<AcquireError> for EngineError {
+    fn from(error: AcquireError) -> Self {
+        match error {
+            AcquireError::DeviceLost => Self::DeviceLost,
+            AcquireError::OomError(error) => error.into(),
+            AcquireError::SurfaceLost => Self::SurfaceLost,
+            error => Self::Other(error.to_string()),
+        }
+    }
+}
+
+impl From<VulkanError> for EngineError {
+    fn from(error: VulkanError) -> Self {
+        match error {
+            VulkanError::DeviceLost => Self::DeviceLost,
+            VulkanError::OutOfDeviceMemory => Self::OutOfDeviceMemory,
+            VulkanError::OutOfHostMemory => Self::OutOfHostMemory,
+            VulkanError::SurfaceLost => Self::SurfaceLost,
+            error => Self::Other(error.to_string()),
+        }
+    }
+}
+
+impl From<FenceError> for EngineError {
+    fn from(error: FenceError) -> Self {
+        match error {
+            FenceError::DeviceLost => Self::DeviceLost,
+            FenceError::OomError(error) => error.into(),
+            FenceError::Timeout => Self::Timeout,
+            error => Self::Other(error.to_string()),
+        }
+    }
+}
+
+impl From<CommandBufferExecError> for EngineError {
+    fn from(error: CommandBufferExecError) -> Self {
+        Self::Other(error.to_string())
+    }
+}
+
+/// The stage of `recover` a `RecoveryCallback` is called in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryStage {
+    /// Before the device and the `Swapchain`s are recreated.
+    /// The application must drop everything referencing the old `Swapchain` images,
+    /// i.e. its `Framebuffer`s, swapchain `ImageView`s and renderers holding them like `EguiRenderer`,
+    /// as a surface can't get a new `Swapchain` while the old one is alive.
+    Release,
+    /// After the device and the `Swapchain`s have been recreated.
+    /// All resources of the old device are invalid and have to be recreated and re-uploaded.
+    Recreate,
+}
+
+/// Called twice by `recover`, to release the application's resources of the lost device and to recreate them afterwards.
+pub type RecoveryCallback<E> = Box<dyn FnMut(&E, RecoveryStage) + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_device_loss_requires_recovery() {
+        assert!(EngineError::from(VulkanError::DeviceLost).requires_recovery());
+        assert!(!EngineError::from(VulkanError::OutOfDeviceMemory).requires_recovery());
+        assert!(!EngineError::from(FenceError::Timeout).requires_recovery());
+    }
+}
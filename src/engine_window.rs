@@ -0,0 +1,538 @@
+use std::sync::Arc;
+
+use vulkano::{
+    command_buffer::{
+        allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, CommandBufferUsage,
+        PrimaryAutoCommandBuffer, PrimaryCommandBufferAbstract,
+    },
+    image::{view::ImageView, ImageAccess, ImageUsage, SwapchainImage},
+    memory::allocator::StandardMemoryAllocator,
+    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass},
+    swapchain::{
+        self, AcquireError, PresentMode, Surface, Swapchain, SwapchainAcquireFuture,
+        SwapchainCreateInfo, SwapchainCreationError, SwapchainPresentInfo,
+    },
+    sync::{FlushError, GpuFuture},
+};
+use winit::{
+    event::WindowEvent,
+    window::{CursorGrabMode, Window, WindowId},
+};
+
+use crate::{
+    apply_cursor, EngineError, FrameCapture, LogicalDevice, WindowConfig, WindowFullscreen,
+    WindowMonitor,
+};
+
+/// A window of the `GraphicalEngine` with its own `Swapchain`.
+/// All windows of an engine share its `LogicalDevice`.
+pub struct EngineWindow {
+    logical_device: Arc<LogicalDevice>,
+    surface: Arc<Surface>,
+    id: Option<WindowId>,
+    swap_chain: Arc<Swapchain>,
+    swap_chain_images: Vec<Arc<SwapchainImage>>,
+    vsync: bool,
+    fullscreen: WindowFullscreen,
+    fullscreen_mode: WindowFullscreen,
+    monitor: WindowMonitor,
+    scale_factor: f64,
+    swap_chain_outdated: bool,
+    frame_capture: Option<PresentedFrameCapture>,
+}
+
+/// Captures every presented image, see `EngineWindow::set_frame_capture`.
+struct PresentedFrameCapture {
+    memory_allocator: StandardMemoryAllocator,
+    command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    last_capture: Option<FrameCapture>,
+}
+
+impl PresentedFrameCapture {
+    fn new(logical_device: &LogicalDevice) -> Self {
+        Self {
+            memory_allocator: StandardMemoryAllocator::new_default(logical_device.get_device()),
+            command_buffer_allocator: Arc::new(StandardCommandBufferAllocator::new(
+                logical_device.get_device(),
+                Default::default(),
+            )),
+            last_capture: None,
+        }
+    }
+
+    /// Records a command buffer copying the given image, executed right before it's presented.
+    fn record(
+        &self,
+        logical_device: &LogicalDevice,
+        image: Arc<SwapchainImage>,
+    ) -> (PrimaryAutoCommandBuffer, FrameCapture) {
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &self.command_buffer_allocator,
+            logical_device.get_queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+        let capture = FrameCapture::record(&mut builder, &self.memory_allocator, image);
+
+        (builder.build().unwrap(), capture)
+    }
+}
+
+impl EngineWindow {
+    /// Creates the `Swapchain` for a `Surface`, the first queue of the `LogicalDevice` must be able to present to it.
+    pub(crate) fn new(
+        logical_device: Arc<LogicalDevice>,
+        surface: Arc<Surface>,
+        config: &WindowConfig,
+    ) -> Self {
+        log::debug!("EngineWindow::new");
+
+        assert!(
+            logical_device
+                .get_physical_device()
+                .surface_support(logical_device.get_queue_family_index(), &surface)
+                .unwrap_or(false),
+            "the device can't present to the window"
+        );
+
+        let (swap_chain, swap_chain_images) =
+            Self::create_swap_chain(logical_device.clone(), surface.clone(), config.vsync);
+
+        // Surfaces which weren't created from winit have no id and keep a scale factor of 1
+        let window = surface
+            .object()
+            .and_then(|object| object.downcast_ref::<Window>());
+
+        let engine_window = Self {
+            id: window.map(Window::id),
+            scale_factor: window.map_or(1.0, Window::scale_factor),
+            logical_device,
+            surface,
+            swap_chain,
+            swap_chain_images,
+            vsync: config.vsync,
+            fullscreen: config.fullscreen,
+            // Toggling from windowed needs a fullscreen mode to go to
+            fullscreen_mode: match config.fullscreen {
+                WindowFullscreen::Windowed => WindowFullscreen::Borderless,
+                fullscreen => fullscreen,
+            },
+            monitor: config.monitor.clone(),
+            swap_chain_outdated: false,
+            frame_capture: None,
+        };
+        engine_window.name_swap_chain_images();
+
+        engine_window
+    }
+
+    /// Creates a `Swapchain` given a `LogicalDevice` and a `Window`.
+    /// Vulkan uses `Swapchain`s to store images while they are still ready and swaps them out once a new image is ready to be displayed (i.e. finished rendering).
+    fn create_swap_chain(
+        logical_device: Arc<LogicalDevice>,
+        window: Arc<Surface>,
+        vsync: bool,
+    ) -> (Arc<Swapchain>, Vec<Arc<SwapchainImage>>) {
+        // Get surface capabilities
+        let capabilities = logical_device
+            .get_physical_device()
+            .surface_capabilities(&window, Default::default())
+            .expect("failed to get surface capabilities");
+
+        // Get alphas
+        let composite_alpha = capabilities
+            .supported_composite_alpha
+            .into_iter()
+            .next()
+            .expect("no composite alpha found!");
+
+        // Get image format
+        let image_format = Some(
+            logical_device
+                .get_physical_device()
+                .surface_formats(&window, Default::default())
+                .unwrap()[0]
+                .0,
+        );
+
+        let present_mode = Self::select_present_mode(&logical_device, &window, vsync);
+
+        // Create Swap Chain
+        Swapchain::new(
+            logical_device.get_device(),
+            window,
+            SwapchainCreateInfo {
+                // How many buffers (images) are in the swap chain
+                min_image_count: capabilities.min_image_count + 1,
+                // Format of the images
+                image_format,
+                // Dimensions of the images
+                image_extent: [0, 0],
+                // Usage of the images, transfers allow capturing frames
+                image_usage: ImageUsage::COLOR_ATTACHMENT
+                    | (capabilities.supported_usage_flags & ImageUsage::TRANSFER_SRC),
+                // Alpha of the images
+                composite_alpha,
+                // Vsync or not
+                present_mode,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    /// Picks `Fifo` with vsync, otherwise the first supported of `Mailbox` and `Immediate`.
+    /// `Fifo` is the only mode every surface supports.
+    fn select_present_mode(
+        logical_device: &LogicalDevice,
+        window: &Surface,
+        vsync: bool,
+    ) -> PresentMode {
+        if vsync {
+            return PresentMode::Fifo;
+        }
+
+        let supported = logical_device
+            .get_physical_device()
+            .surface_present_modes(window)
+            .map(|modes| modes.collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        [PresentMode::Mailbox, PresentMode::Immediate]
+            .into_iter()
+            .find(|mode| supported.contains(mode))
+            .unwrap_or(PresentMode::Fifo)
+    }
+
+    /// Recreates the `SwapChain` and `SwapChainImages`, while also rebuilding the `Framebuffer`s given a `RenderPass` is submitted.
+    ///
+    /// Can return `None` on `SwapchainCreationError::ImageExtentNotSupported` which **should be ignored**.
+    pub fn recreate_swap_chain_and_images(
+        &mut self,
+        render_pass: Arc<RenderPass>,
+    ) -> Option<Vec<Arc<Framebuffer>>> {
+        log::debug!("EngineWindow::recreate_swap_chain");
+
+        let (new_swapchain, new_images) = match self.swap_chain.recreate(SwapchainCreateInfo {
+            image_extent: [0, 0],
+            present_mode: Self::select_present_mode(
+                &self.logical_device,
+                &self.surface,
+                self.vsync,
+            ),
+            ..self.swap_chain.create_info()
+        }) {
+            Ok(r) => r,
+            Err(SwapchainCreationError::ImageExtentNotSupported { .. }) => return None,
+            Err(e) => panic!("Failed to recreate swapchain: {:?}", e),
+        };
+
+        self.swap_chain = new_swapchain;
+        self.swap_chain_images = new_images;
+        self.swap_chain_outdated = false;
+        self.name_swap_chain_images();
+        Some(self.create_frame_buffers(render_pass))
+    }
+
+    /// Whether anything besides the window itself references the `Swapchain` or its images,
+    /// e.g. `Framebuffer`s or `ImageView`s the application didn't release.
+    pub(crate) fn is_swap_chain_in_use(&self) -> bool {
+        // Every `SwapchainImage` holds a reference to its `Swapchain`
+        Arc::strong_count(&self.swap_chain) > 1 + self.swap_chain_images.len()
+            || self
+                .swap_chain_images
+                .iter()
+                .any(|image| Arc::strong_count(image) > 1)
+    }
+
+    /// Creates a new `Swapchain` on a recreated `LogicalDevice`, the old one belongs to the lost device.
+    /// A surface can only have a single `Swapchain`, so the old one is destroyed first,
+    /// which requires the application to have released everything referencing it, see `is_swap_chain_in_use`.
+    pub(crate) fn recreate_for_device(mut self, logical_device: Arc<LogicalDevice>) -> Self {
+        debug_assert!(
+            !self.is_swap_chain_in_use(),
+            "the old swapchain is still in use"
+        );
+        self.swap_chain_images.clear();
+        drop(self.swap_chain);
+
+        let (swap_chain, swap_chain_images) =
+            Self::create_swap_chain(logical_device.clone(), self.surface.clone(), self.vsync);
+
+        // Captures of the old device are dropped, capturing continues on the new one
+        let frame_capture = self
+            .frame_capture
+            .as_ref()
+            .map(|_| PresentedFrameCapture::new(&logical_device));
+
+        let engine_window = Self {
+            logical_device,
+            swap_chain,
+            swap_chain_images,
+            swap_chain_outdated: false,
+            frame_capture,
+            ..self
+        };
+        engine_window.name_swap_chain_images();
+
+        engine_window
+    }
+
+    /// Names the `SwapchainImage`s after their index for debugging tools.
+    fn name_swap_chain_images(&self) {
+        for (index, image) in self.swap_chain_images.iter().enumerate() {
+            self.logical_device.set_debug_name(
+                &**image.inner().image,
+                &format!("Swapchain Image {}", index),
+            );
+        }
+    }
+
+    /// Creates a `Framebuffer` from a given `RenderPass` and `SwapChainImages`.
+    /// A `Framebuffer` wraps around `SwapchainImage`'s and creates `ImageView`s from them given the correct format from the given `RenderPass`.
+    pub fn create_frame_buffers(&self, render_pass: Arc<RenderPass>) -> Vec<Arc<Framebuffer>> {
+        self.swap_chain_images
+            .iter()
+            .map(|image| {
+                let view = ImageView::new_default(image.clone()).unwrap();
+                Framebuffer::new(
+                    render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments: vec![view],
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+            })
+            .collect::<Vec<Arc<Framebuffer>>>()
+    }
+
+    /// Acquires the next `SwapchainImage` to render into.
+    /// Returns `None` and marks the `Swapchain` outdated if it has to be recreated first.
+    /// Panics on any other error, see `try_acquire_next_image`.
+    pub fn acquire_next_image(&mut self) -> Option<(u32, SwapchainAcquireFuture)> {
+        self.try_acquire_next_image()
+            .unwrap_or_else(|e| panic!("Failed to acquire next image: {}", e))
+    }
+
+    /// Acquires the next `SwapchainImage` to render into, like `acquire_next_image` but returning errors.
+    pub fn try_acquire_next_image(
+        &mut self,
+    ) -> Result<Option<(u32, SwapchainAcquireFuture)>, EngineError> {
+        match swapchain::acquire_next_image(self.swap_chain.clone(), None) {
+            Ok((image_index, suboptimal, acquire_future)) => {
+                if suboptimal {
+                    self.swap_chain_outdated = true;
+                }
+                Ok(Some((image_index, acquire_future)))
+            }
+            Err(AcquireError::OutOfDate) => {
+                self.swap_chain_outdated = true;
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Executes the command buffer rendering into the acquired image, presents it and waits for the GPU to finish.
+    /// Errors are logged, see `try_present`.
+    pub fn present<Cb>(
+        &mut self,
+        acquire_future: SwapchainAcquireFuture,
+        command_buffer: Cb,
+        image_index: u32,
+    ) where
+        Cb: PrimaryCommandBufferAbstract + 'static,
+    {
+        if let Err(e) = self.try_present(acquire_future, command_buffer, image_index) {
+            log::error!("Failed to present: {}", e);
+        }
+    }
+
+    /// Executes the command buffer rendering into the acquired image, presents it and waits for the GPU to finish.
+    /// An outdated `Swapchain` isn't an error, it's marked for recreation instead.
+    /// If frame capture is enabled, the image is copied right before it's presented, see `set_frame_capture`.
+    pub fn try_present<Cb>(
+        &mut self,
+        acquire_future: SwapchainAcquireFuture,
+        command_buffer: Cb,
+        image_index: u32,
+    ) -> Result<(), EngineError>
+    where
+        Cb: PrimaryCommandBufferAbstract + 'static,
+    {
+        let queue = self.logical_device.get_first_queue();
+
+        let mut future = acquire_future
+            .then_execute(queue.clone(), command_buffer)?
+            .boxed();
+        let mut capture = None;
+        if let Some(frame_capture) = &self.frame_capture {
+            let (capture_command_buffer, frame) = frame_capture.record(
+                &self.logical_device,
+                self.swap_chain_images[image_index as usize].clone(),
+            );
+            future = future
+                .then_execute(queue.clone(), capture_command_buffer)?
+                .boxed();
+            capture = Some(frame);
+        }
+
+        let execution = future
+            .then_swapchain_present(
+                queue,
+                SwapchainPresentInfo::swapchain_image_index(self.swap_chain.clone(), image_index),
+            )
+            .then_signal_fence_and_flush()
+            .and_then(|future| future.wait(None));
+
+        match execution {
+            Ok(()) => {
+                if let Some(frame_capture) = &mut self.frame_capture {
+                    frame_capture.last_capture = capture;
+                }
+                Ok(())
+            }
+            Err(FlushError::OutOfDate) => {
+                self.swap_chain_outdated = true;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Tracks window changes which need the `Swapchain` to be recreated, forward all `WindowEvent`s of this window.
+    pub fn on_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::Resized(_) => self.swap_chain_outdated = true,
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                log::debug!("Scale factor changed to {}", scale_factor);
+                self.scale_factor = *scale_factor;
+                self.swap_chain_outdated = true;
+            }
+            _ => (),
+        }
+    }
+
+    /// Whether the window changed since the `Swapchain` was created, call `recreate_swap_chain_and_images` if so.
+    pub fn is_swap_chain_outdated(&self) -> bool {
+        self.swap_chain_outdated
+    }
+
+    /// Switches between windowed and fullscreen on the configured monitor.
+    pub fn set_fullscreen(&mut self, fullscreen: WindowFullscreen) {
+        log::debug!("EngineWindow::set_fullscreen {:?}", fullscreen);
+
+        let window = self.get_winit_window();
+        let monitor = self.monitor.resolve(
+            window.available_monitors().collect(),
+            window.primary_monitor(),
+            window.current_monitor(),
+        );
+        window.set_fullscreen(fullscreen.to_winit(monitor));
+
+        if fullscreen != WindowFullscreen::Windowed {
+            self.fullscreen_mode = fullscreen;
+        }
+        self.fullscreen = fullscreen;
+        // Not every platform sends a resize when the fullscreen mode changes
+        self.swap_chain_outdated = true;
+    }
+
+    /// Toggles between windowed and the last used (or configured) fullscreen mode.
+    pub fn toggle_fullscreen(&mut self) {
+        match self.fullscreen {
+            WindowFullscreen::Windowed => self.set_fullscreen(self.fullscreen_mode),
+            _ => self.set_fullscreen(WindowFullscreen::Windowed),
+        }
+    }
+
+    /// Returns the current fullscreen mode, `Windowed` if not fullscreen.
+    pub fn get_fullscreen(&self) -> WindowFullscreen {
+        self.fullscreen
+    }
+
+    /// Changes vsync, taking effect once the `Swapchain` is recreated.
+    pub fn set_vsync(&mut self, vsync: bool) {
+        if self.vsync != vsync {
+            self.vsync = vsync;
+            self.swap_chain_outdated = true;
+        }
+    }
+
+    /// Whether vsync is requested, which may not be applied until the `Swapchain` is recreated.
+    pub fn get_vsync(&self) -> bool {
+        self.vsync
+    }
+
+    /// Grabs and shows or hides the cursor.
+    pub fn set_cursor(&self, grab: CursorGrabMode, visible: bool) {
+        apply_cursor(&self.get_winit_window(), grab, visible);
+    }
+
+    /// Returns the DPI scale factor, physical pixels per logical pixel.
+    pub fn get_scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Returns the winit `WindowId`, `None` if the `Surface` wasn't created from winit.
+    pub fn get_id(&self) -> Option<WindowId> {
+        self.id
+    }
+
+    /// Returns the `Surface`
+    pub fn get_surface(&self) -> Arc<Surface> {
+        self.surface.clone()
+    }
+
+    /// Returns the winit `Window` the `Surface` was created from.
+    pub fn get_winit_window(&self) -> Arc<Window> {
+        self.surface
+            .object()
+            .expect("surface has no window")
+            .clone()
+            .downcast::<Window>()
+            .expect("surface wasn't created from a winit window")
+    }
+
+    /// Returns the `Swapchain`
+    pub fn get_swap_chain(&self) -> Arc<Swapchain> {
+        self.swap_chain.clone()
+    }
+
+    /// Returns the `SwapchainImage`s
+    pub fn get_swap_chain_images(&self) -> Vec<Arc<SwapchainImage>> {
+        self.swap_chain_images.clone()
+    }
+
+    /// Enables or disables capturing every image presented by `present` and `try_present`.
+    /// Each capture is an additional copy on the presenting queue, so only enable it while capturing is needed.
+    /// Panics if the surface doesn't support capturing swapchain images.
+    #[track_caller]
+    pub fn set_frame_capture(&mut self, enabled: bool) {
+        if !enabled {
+            self.frame_capture = None;
+        } else if self.frame_capture.is_none() {
+            assert!(
+                self.swap_chain
+                    .image_usage()
+                    .intersects(ImageUsage::TRANSFER_SRC),
+                "the surface doesn't support capturing swapchain images"
+            );
+            self.frame_capture = Some(PresentedFrameCapture::new(&self.logical_device));
+        }
+    }
+
+    /// Whether presented images are captured, see `set_frame_capture`.
+    pub fn is_frame_capture_enabled(&self) -> bool {
+        self.frame_capture.is_some()
+    }
+
+    /// Returns the capture of the last presented image.
+    /// `None` if frame capture isn't enabled or no image was presented since.
+    pub fn get_last_frame_capture(&self) -> Option<&FrameCapture> {
+        self.frame_capture
+            .as_ref()
+            .and_then(|frame_capture| frame_capture.last_capture.as_ref())
+    }
+}
@@ -0,0 +1,142 @@
+use vulkano::{
+    device::physical::PhysicalDevice,
+    format::{Format, FormatFeatures},
+    image::ImageUsage,
+};
+
+/// Depth formats from most to least preferred, see `LogicalDevice::select_depth_format`.
+pub const DEPTH_FORMAT_PREFERENCES: [Format; 4] = [
+    Format::D32_SFLOAT,
+    Format::D32_SFLOAT_S8_UINT,
+    Format::D24_UNORM_S8_UINT,
+    Format::D16_UNORM,
+];
+
+/// HDR color formats from most to least preferred, see `PostProcessingChain::select_hdr_format`.
+pub const HDR_FORMAT_PREFERENCES: [Format; 2] =
+    [Format::R16G16B16A16_SFLOAT, Format::R32G32B32A32_SFLOAT];
+
+/// 8-bit linear color formats from most to least preferred.
+/// Pixels of the BGRA fallback have to be converted with `convert_rgba8_pixels`.
+pub const COLOR_FORMAT_PREFERENCES: [Format; 2] = [Format::R8G8B8A8_UNORM, Format::B8G8R8A8_UNORM];
+
+/// 8-bit sRGB color formats from most to least preferred, e.g. for textures.
+/// Pixels of the BGRA fallback have to be converted with `convert_rgba8_pixels`.
+pub const SRGB_FORMAT_PREFERENCES: [Format; 2] = [Format::R8G8B8A8_SRGB, Format::B8G8R8A8_SRGB];
+
+/// Color grading LUT formats from most to least preferred, see `ColorGradingStage::create_lut`.
+pub const LUT_FORMAT_PREFERENCES: [Format; 2] =
+    [Format::R8G8B8A8_UNORM, Format::R16G16B16A16_UNORM];
+
+/// A usage of an image with optimal tiling which requires support by its format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FormatUsage {
+    Storage,
+    ColorAttachment,
+    DepthStencilAttachment,
+    Sampled,
+    BlitSrc,
+    BlitDst,
+}
+
+impl FormatUsage {
+    /// Returns the format features required for this usage.
+    pub fn get_required_features(self) -> FormatFeatures {
+        match self {
+            Self::Storage => FormatFeatures::STORAGE_IMAGE,
+            Self::ColorAttachment => FormatFeatures::COLOR_ATTACHMENT,
+            Self::DepthStencilAttachment => FormatFeatures::DEPTH_STENCIL_ATTACHMENT,
+            Self::Sampled => FormatFeatures::SAMPLED_IMAGE,
+            Self::BlitSrc => FormatFeatures::BLIT_SRC,
+            Self::BlitDst => FormatFeatures::BLIT_DST,
+        }
+    }
+
+    /// Returns the usages of an `ImageUsage` which require support by the format.
+    /// Transfers are supported by every format and hence ignored.
+    pub fn from_image_usage(usage: ImageUsage) -> Vec<Self> {
+        [
+            (ImageUsage::STORAGE, Self::Storage),
+            (ImageUsage::COLOR_ATTACHMENT, Self::ColorAttachment),
+            (
+                ImageUsage::DEPTH_STENCIL_ATTACHMENT,
+                Self::DepthStencilAttachment,
+            ),
+            (ImageUsage::SAMPLED, Self::Sampled),
+        ]
+        .into_iter()
+        .filter(|(image_usage, _)| usage.intersects(*image_usage))
+        .map(|(_, format_usage)| format_usage)
+        .collect()
+    }
+}
+
+/// Converts 8-bit RGBA pixels into the channel order of `format` by swapping red and blue for BGRA formats.
+/// As the swap is its own inverse, this also converts pixels read back from such an image into RGBA.
+pub fn convert_rgba8_pixels(format: Format, pixels: &mut [u8]) {
+    if matches!(format, Format::B8G8R8A8_UNORM | Format::B8G8R8A8_SRGB) {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+}
+
+/// Returns the GLSL layout qualifier of a storage image format used by the built-in shaders.
+pub(crate) fn get_glsl_format_qualifier(format: Format) -> &'static str {
+    match format {
+        Format::R8G8B8A8_UNORM => "rgba8",
+        Format::R16G16B16A16_UNORM => "rgba16",
+        Format::R16G16B16A16_SFLOAT => "rgba16f",
+        Format::R32G32B32A32_SFLOAT => "rgba32f",
+        format => panic!("format {:?} has no GLSL qualifier", format),
+    }
+}
+
+/// Returns the optimal tiling features of a format, empty if the format isn't supported at all.
+pub(crate) fn get_format_features(
+    physical_device: &PhysicalDevice,
+    format: Format,
+) -> FormatFeatures {
+    physical_device
+        .format_properties(format)
+        .map_or(FormatFeatures::empty(), |properties| {
+            properties.optimal_tiling_features
+        })
+}
+
+/// Returns the first of the preferred formats supporting all usages.
+pub(crate) fn find_supported_format(
+    physical_device: &PhysicalDevice,
+    preferences: &[Format],
+    usages: &[FormatUsage],
+) -> Option<Format> {
+    let required = usages
+        .iter()
+        .fold(FormatFeatures::empty(), |features, usage| {
+            features | usage.get_required_features()
+        });
+
+    preferences
+        .iter()
+        .copied()
+        .find(|format| get_format_features(physical_device, *format).contains(required))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba8_pixels_are_swizzled_for_bgra() {
+        let mut pixels = vec![1, 2, 3, 4, 5, 6, 7, 8];
+
+        convert_rgba8_pixels(Format::R8G8B8A8_SRGB, &mut pixels);
+        assert_eq!(pixels, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        convert_rgba8_pixels(Format::B8G8R8A8_SRGB, &mut pixels);
+        assert_eq!(pixels, [3, 2, 1, 4, 7, 6, 5, 8]);
+
+        convert_rgba8_pixels(Format::B8G8R8A8_UNORM, &mut pixels);
+        assert_eq!(pixels, [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+}
@@ -0,0 +1,238 @@
+use std::{cell::RefCell, path::Path, sync::Arc};
+
+use image::{ImageFormat, ImageResult, Rgba32FImage, RgbaImage};
+use vulkano::{
+    buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer},
+    command_buffer::{
+        allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, CommandBufferUsage,
+        CopyImageToBufferInfo,
+    },
+    format::Format,
+    image::ImageAccess,
+    memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
+};
+
+use crate::{track_resource, AbstractEngine, EngineCommandBufferBuilder, ResourceKind};
+
+/// A copy of an image (e.g. a `SwapchainImage` or an offscreen render target) into host visible memory.
+///
+/// The copy is recorded into a command buffer by `FrameCapture::record`, usually the one rendering the frame,
+/// so capturing doesn't need an additional submission.
+/// The pixels can be read once that command buffer finished executing.
+pub struct FrameCapture {
+    buffer: Subbuffer<[u8]>,
+    dimensions: [u32; 2],
+    format: Format,
+}
+
+impl FrameCapture {
+    /// Records a copy of a given image into a new download buffer.
+    /// The image must have been created with `ImageUsage::TRANSFER_SRC`.
+    ///
+    /// Supported formats are `R8G8B8A8`, `B8G8R8A8` (both `UNORM` and `SRGB`), `R16G16B16A16_SFLOAT` and `R32G32B32A32_SFLOAT`.
+    #[track_caller]
+    pub fn record(
+        builder: &mut EngineCommandBufferBuilder,
+        memory_allocator: &StandardMemoryAllocator,
+        image: Arc<dyn ImageAccess>,
+    ) -> Self {
+        let format = image.format();
+        let dimensions = image.dimensions().width_height();
+        assert!(
+            Self::is_format_supported(format),
+            "capturing images of format {:?} is not supported",
+            format
+        );
+
+        let buffer = Buffer::new_slice::<u8>(
+            memory_allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Download,
+                ..Default::default()
+            },
+            dimensions[0] as u64
+                * dimensions[1] as u64
+                * format.block_size().expect("format has no block size"),
+        )
+        .expect("failed to create capture buffer");
+        track_resource(ResourceKind::Buffer, buffer.buffer());
+
+        builder
+            .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(image, buffer.clone()))
+            .expect("failed to record image capture");
+
+        Self {
+            buffer,
+            dimensions,
+            format,
+        }
+    }
+
+    /// Captures a given image on a separate submission and waits for it.
+    /// Prefer `record` inside the frame loop, as this stalls until the GPU is done.
+    #[track_caller]
+    pub fn capture<E>(
+        engine: &E,
+        memory_allocator: &StandardMemoryAllocator,
+        image: Arc<dyn ImageAccess>,
+    ) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &engine.get_command_buffer_allocator(),
+            engine.get_logical_device().get_queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+        // Recorded outside of the closure, so the leak tracker sees the caller
+        let capture = Self::record(&mut builder, memory_allocator, image);
+
+        // `compute` only accepts `Fn` closures, hence the command buffer is moved out of a `RefCell`
+        let command_buffer = RefCell::new(Some(builder.build().unwrap()));
+        engine.compute(&|_| {
+            command_buffer
+                .borrow_mut()
+                .take()
+                .expect("capture was already computed")
+        });
+
+        capture
+    }
+
+    /// Returns whether images of a given `Format` can be captured.
+    pub fn is_format_supported(format: Format) -> bool {
+        matches!(
+            format,
+            Format::R8G8B8A8_UNORM
+                | Format::R8G8B8A8_SRGB
+                | Format::B8G8R8A8_UNORM
+                | Format::B8G8R8A8_SRGB
+                | Format::R16G16B16A16_SFLOAT
+                | Format::R32G32B32A32_SFLOAT
+        )
+    }
+
+    /// Returns the dimensions of the captured image.
+    pub fn get_dimensions(&self) -> [u32; 2] {
+        self.dimensions
+    }
+
+    /// Returns the `Format` of the captured image.
+    pub fn get_format(&self) -> Format {
+        self.format
+    }
+
+    /// Converts the captured pixels into an 8-bit RGBA image.
+    /// BGRA images are swizzled, float images are clamped to [0, 1].
+    /// 8-bit images keep their values, so sRGB images stay sRGB encoded as expected by PNG and JPEG.
+    ///
+    /// Panics if the command buffer containing the capture hasn't finished executing.
+    pub fn to_rgba_image(&self) -> RgbaImage {
+        let content = self
+            .buffer
+            .read()
+            .expect("capture is still in use by the GPU");
+        let [width, height] = self.dimensions;
+
+        let pixels: Vec<u8> = match self.format {
+            Format::R8G8B8A8_UNORM | Format::R8G8B8A8_SRGB => content.to_vec(),
+            Format::B8G8R8A8_UNORM | Format::B8G8R8A8_SRGB => content
+                .chunks_exact(4)
+                .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+                .collect(),
+            Format::R16G16B16A16_SFLOAT | Format::R32G32B32A32_SFLOAT => self
+                .decode_floats(&content)
+                .into_iter()
+                .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+                .collect(),
+            format => unreachable!("unsupported capture format {:?}", format),
+        };
+
+        RgbaImage::from_raw(width, height, pixels).expect("capture has the wrong size")
+    }
+
+    /// Converts the captured pixels into a linear 32-bit float RGBA image, e.g. for EXR.
+    /// sRGB images are decoded to linear colors, float images keep their full range.
+    ///
+    /// Panics if the command buffer containing the capture hasn't finished executing.
+    pub fn to_rgba32f_image(&self) -> Rgba32FImage {
+        let [width, height] = self.dimensions;
+
+        let pixels: Vec<f32> = if matches!(
+            self.format,
+            Format::R16G16B16A16_SFLOAT | Format::R32G32B32A32_SFLOAT
+        ) {
+            let content = self
+                .buffer
+                .read()
+                .expect("capture is still in use by the GPU");
+            self.decode_floats(&content)
+        } else {
+            let is_srgb = matches!(self.format, Format::R8G8B8A8_SRGB | Format::B8G8R8A8_SRGB);
+
+            self.to_rgba_image()
+                .pixels()
+                .flat_map(|pixel| {
+                    let [r, g, b, a] = pixel.0.map(|value| value as f32 / 255.0);
+                    if is_srgb {
+                        [
+                            Self::srgb_to_linear(r),
+                            Self::srgb_to_linear(g),
+                            Self::srgb_to_linear(b),
+                            a,
+                        ]
+                    } else {
+                        [r, g, b, a]
+                    }
+                })
+                .collect()
+        };
+
+        Rgba32FImage::from_raw(width, height, pixels).expect("capture has the wrong size")
+    }
+
+    /// Saves the captured image to a file, the image format is derived from the file extension.
+    /// EXR files are written from `to_rgba32f_image`, everything else (e.g. PNG or JPEG) from `to_rgba_image`.
+    pub fn save<P>(&self, path: P) -> ImageResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        match ImageFormat::from_path(path)? {
+            ImageFormat::OpenExr => self.to_rgba32f_image().save(path),
+            // JPEG has no alpha channel
+            ImageFormat::Jpeg => image::DynamicImage::ImageRgba8(self.to_rgba_image())
+                .into_rgb8()
+                .save(path),
+            _ => self.to_rgba_image().save(path),
+        }
+    }
+
+    fn decode_floats(&self, content: &[u8]) -> Vec<f32> {
+        if self.format == Format::R32G32B32A32_SFLOAT {
+            content
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect()
+        } else {
+            content
+                .chunks_exact(2)
+                .map(|bytes| half::f16::from_le_bytes([bytes[0], bytes[1]]).to_f32())
+                .collect()
+        }
+    }
+
+    fn srgb_to_linear(value: f32) -> f32 {
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    time::Duration,
+};
+
+use crate::FrameCapture;
+
+/// Where a `FrameRecorder` writes the recorded frames to.
+#[derive(Clone, Debug)]
+pub enum FrameRecorderOutput {
+    /// Numbered PNG files `<prefix>000000.png`, `<prefix>000001.png`, ... inside a directory.
+    ImageSequence { directory: PathBuf, prefix: String },
+    /// A video file encoded by a local `ffmpeg` executable.
+    /// The codec is chosen by the file extension: `.webm` uses VP9, everything else H.264.
+    Video { path: PathBuf, ffmpeg: PathBuf },
+}
+
+/// Records every N-th frame of a window or headless renderer into an image sequence or a video.
+///
+/// Time is advanced by a fixed time step per frame instead of real time, so recordings are deterministic
+/// no matter how long a frame takes to render. Animations should be driven by `get_time`.
+///
+/// Per frame, call `should_capture` and if it returns `true` record a `FrameCapture` of the frame.
+/// Once the frame finished executing, hand the capture (or `None`) to `submit_frame`.
+pub struct FrameRecorder {
+    output: FrameRecorderOutput,
+    frame_interval: u64,
+    time_step: Duration,
+    frame_index: u64,
+    recorded_frames: u64,
+    ffmpeg: Option<Child>,
+    /// The dimensions of the video, ffmpeg expects every raw frame to have them.
+    video_dimensions: Option<(u32, u32)>,
+}
+
+impl FrameRecorder {
+    /// Creates a `FrameRecorder` capturing every `frame_interval`-th frame, advancing the time by `time_step` per frame.
+    pub fn new(output: FrameRecorderOutput, frame_interval: u32, time_step: Duration) -> Self {
+        assert!(frame_interval > 0, "frame interval must be at least 1");
+        assert!(!time_step.is_zero(), "time step must not be zero");
+
+        if let FrameRecorderOutput::ImageSequence { directory, .. } = &output {
+            std::fs::create_dir_all(directory).expect("failed to create output directory");
+        }
+
+        Self {
+            output,
+            frame_interval: frame_interval as u64,
+            time_step,
+            frame_index: 0,
+            recorded_frames: 0,
+            ffmpeg: None,
+            video_dimensions: None,
+        }
+    }
+
+    /// Returns whether the current frame has to be captured.
+    pub fn should_capture(&self) -> bool {
+        self.frame_index % self.frame_interval == 0
+    }
+
+    /// Returns the simulated time of the current frame.
+    pub fn get_time(&self) -> Duration {
+        Duration::from_nanos((self.time_step.as_nanos() * self.frame_index as u128) as u64)
+    }
+
+    /// Returns the fixed time step between two frames.
+    pub fn get_time_step(&self) -> Duration {
+        self.time_step
+    }
+
+    /// Returns the index of the current frame.
+    pub fn get_frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Returns how many frames have been written so far.
+    pub fn get_recorded_frames(&self) -> u64 {
+        self.recorded_frames
+    }
+
+    /// Returns the frame rate of the recording, i.e. how many recorded frames make up a second of simulated time.
+    pub fn get_frame_rate(&self) -> f64 {
+        1.0 / (self.time_step.as_secs_f64() * self.frame_interval as f64)
+    }
+
+    /// Finishes the current frame and advances to the next one.
+    /// `capture` must be `Some` if `should_capture` returned `true` for this frame and the frame must have finished executing.
+    /// Video frames must keep the dimensions of the first one, e.g. a resized window returns an `InvalidInput` error.
+    pub fn submit_frame(&mut self, capture: Option<FrameCapture>) -> io::Result<()> {
+        if let Some(capture) = capture {
+            debug_assert!(
+                self.should_capture(),
+                "captured a frame that isn't recorded"
+            );
+
+            let image = capture.to_rgba_image();
+            match &self.output {
+                FrameRecorderOutput::ImageSequence { directory, prefix } => {
+                    let path = directory.join(format!("{}{:06}.png", prefix, self.recorded_frames));
+                    image.save(&path).map_err(io::Error::other)?;
+                }
+                FrameRecorderOutput::Video { .. } => {
+                    match self.video_dimensions {
+                        None => {
+                            self.ffmpeg = Some(self.spawn_ffmpeg(image.dimensions())?);
+                            self.video_dimensions = Some(image.dimensions());
+                        }
+                        Some(dimensions) if dimensions != image.dimensions() => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!(
+                                    "frame has dimensions {:?}, but the video has {:?}",
+                                    image.dimensions(),
+                                    dimensions
+                                ),
+                            ));
+                        }
+                        Some(_) => (),
+                    }
+
+                    self.ffmpeg
+                        .as_mut()
+                        .and_then(|ffmpeg| ffmpeg.stdin.as_mut())
+                        .expect("ffmpeg has no stdin")
+                        .write_all(image.as_raw())?;
+                }
+            }
+
+            self.recorded_frames += 1;
+        } else if self.should_capture() {
+            log::warn!("Frame {} should have been captured", self.frame_index);
+        }
+
+        self.frame_index += 1;
+        Ok(())
+    }
+
+    fn spawn_ffmpeg(&self, (width, height): (u32, u32)) -> io::Result<Child> {
+        let FrameRecorderOutput::Video { path, ffmpeg } = &self.output else {
+            unreachable!("ffmpeg is only used for videos");
+        };
+
+        let codec: &[&str] = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("webm") => &["-c:v", "libvpx-vp9"],
+            _ => &["-c:v", "libx264"],
+        };
+
+        log::debug!("Starting ffmpeg to record into {:?}", path);
+
+        Command::new(ffmpeg)
+            .args(["-y", "-loglevel", "error"])
+            // Raw RGBA frames on stdin
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{}x{}", width, height)])
+            .args(["-r", &self.get_frame_rate().to_string()])
+            .args(["-i", "-"])
+            .args(codec)
+            // Most players only support 4:2:0 chroma subsampling, which requires even dimensions
+            .args(["-pix_fmt", "yuv420p"])
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+    }
+
+    /// Finishes the recording, waiting for ffmpeg to finish encoding if a video is recorded.
+    /// Also called when the `FrameRecorder` is dropped, but errors are only logged then.
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.video_dimensions = None;
+        if let Some(mut ffmpeg) = self.ffmpeg.take() {
+            // Closing stdin signals ffmpeg the end of the video
+            drop(ffmpeg.stdin.take());
+
+            let status = ffmpeg.wait()?;
+            if !status.success() {
+                return Err(io::Error::other(format!("ffmpeg failed: {}", status)));
+            }
+        }
+
+        log::debug!("Recorded {} frames", self.recorded_frames);
+        Ok(())
+    }
+}
+
+impl Drop for FrameRecorder {
+    fn drop(&mut self) {
+        if self.ffmpeg.is_none() {
+            return;
+        }
+
+        if let Err(e) = self.finish() {
+            log::error!("Failed to finish recording: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A video recorder, which doesn't start ffmpeg or touch the file system until a frame is captured.
+    fn create_recorder(frame_interval: u32, time_step: Duration) -> FrameRecorder {
+        FrameRecorder::new(
+            FrameRecorderOutput::Video {
+                path: PathBuf::from("recording.mp4"),
+                ffmpeg: PathBuf::from("ffmpeg"),
+            },
+            frame_interval,
+            time_step,
+        )
+    }
+
+    #[test]
+    fn every_nth_frame_is_captured() {
+        let mut recorder = create_recorder(3, Duration::from_millis(10));
+
+        let captured = (0..7)
+            .map(|_| {
+                let should_capture = recorder.should_capture();
+                recorder.submit_frame(None).unwrap();
+                should_capture
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(captured, [true, false, false, true, false, false, true]);
+        assert_eq!(recorder.get_frame_index(), 7);
+        assert_eq!(recorder.get_recorded_frames(), 0);
+    }
+
+    #[test]
+    fn time_advances_by_the_time_step() {
+        let mut recorder = create_recorder(2, Duration::from_millis(20));
+        assert_eq!(recorder.get_time(), Duration::ZERO);
+
+        for _ in 0..5 {
+            recorder.submit_frame(None).unwrap();
+        }
+
+        assert_eq!(recorder.get_time(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn frame_rate_counts_recorded_frames() {
+        // Time steps of 1/64 s, which are exact in binary
+        let time_step = Duration::from_micros(15625);
+
+        assert_eq!(create_recorder(1, time_step).get_frame_rate(), 64.0);
+        assert_eq!(create_recorder(4, time_step).get_frame_rate(), 16.0);
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use winit::event_loop::ControlFlow;
+
+/// Configuration of a `GameLoop`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameLoopOptions {
+    /// Fixed updates per second.
+    pub update_rate: f64,
+    /// Upper bound of fixed updates per frame. Time beyond that is dropped, so a slow frame
+    /// doesn't cause ever more updates in the next one.
+    pub max_updates_per_frame: u32,
+    /// Maximal frames per second, `None` renders as fast as possible.
+    pub frame_cap: Option<f64>,
+    /// Amount of recent frames the `FrameStatistics` are computed over.
+    pub statistics_window: usize,
+    /// Logs the `FrameStatistics` at this interval, `None` disables logging.
+    pub log_interval: Option<Duration>,
+}
+
+impl Default for GameLoopOptions {
+    fn default() -> Self {
+        Self {
+            update_rate: 60.0,
+            max_updates_per_frame: 5,
+            frame_cap: None,
+            statistics_window: 240,
+            log_interval: None,
+        }
+    }
+}
+
+/// What to do in the current frame, returned by `GameLoop::begin_frame`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTick {
+    /// Fixed updates to run before rendering, each advancing `GameLoop::get_fixed_delta`.
+    pub updates: u32,
+    /// How far the render time is between the last and the next fixed update, in `0..1`.
+    /// Interpolate `previous * (1 - alpha) + current * alpha` for smooth rendering.
+    pub alpha: f64,
+    /// Time since the previous frame.
+    pub delta: Duration,
+}
+
+/// Frame time statistics over the recent frames.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameStatistics {
+    /// Amount of frames the statistics are computed over, at most `GameLoopOptions::statistics_window`.
+    pub frame_count: usize,
+    pub min: Duration,
+    pub average: Duration,
+    pub max: Duration,
+    /// Median frame time.
+    pub percentile_50: Duration,
+    /// Frame time 95% of the frames are at or below.
+    pub percentile_95: Duration,
+    /// Frame time 99% of the frames are at or below.
+    pub percentile_99: Duration,
+    /// Based on the average frame time.
+    pub frames_per_second: f64,
+}
+
+impl FrameStatistics {
+    fn compute(frame_times: &VecDeque<Duration>) -> Self {
+        if frame_times.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted = frame_times.iter().copied().collect::<Vec<_>>();
+        sorted.sort();
+
+        let percentile = |p: f64| {
+            let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+            sorted[index]
+        };
+        let total: Duration = sorted.iter().sum();
+        let average = total / sorted.len() as u32;
+
+        Self {
+            frame_count: sorted.len(),
+            min: sorted[0],
+            average,
+            max: sorted[sorted.len() - 1],
+            percentile_50: percentile(0.5),
+            percentile_95: percentile(0.95),
+            percentile_99: percentile(0.99),
+            frames_per_second: if average.is_zero() {
+                0.0
+            } else {
+                1.0 / average.as_secs_f64()
+            },
+        }
+    }
+}
+
+impl fmt::Display for FrameStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+        write!(
+            f,
+            "{:.1} FPS | avg {:.2} ms | min {:.2} ms | max {:.2} ms | p50 {:.2} ms | p95 {:.2} ms | p99 {:.2} ms",
+            self.frames_per_second,
+            ms(self.average),
+            ms(self.min),
+            ms(self.max),
+            ms(self.percentile_50),
+            ms(self.percentile_95),
+            ms(self.percentile_99)
+        )
+    }
+}
+
+/// Drives fixed-timestep updates and variable rendering from inside the winit event loop.
+///
+/// ```ignore
+/// Event::RedrawEventsCleared => {
+///     *control_flow = game_loop.get_control_flow();
+///     let Some(tick) = game_loop.begin_frame() else { return };
+///     for _ in 0..tick.updates {
+///         update(game_loop.get_fixed_delta());
+///     }
+///     render(tick.alpha);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct GameLoop {
+    options: GameLoopOptions,
+    fixed_delta: Duration,
+    accumulator: Duration,
+    last_frame: Option<Instant>,
+    frame_times: VecDeque<Duration>,
+    statistics: FrameStatistics,
+    last_log: Instant,
+    frame_index: u64,
+    update_index: u64,
+}
+
+impl GameLoop {
+    /// Creates a `GameLoop`, panics on invalid options.
+    pub fn new(options: GameLoopOptions) -> Self {
+        log::debug!("GameLoop::new");
+
+        assert!(options.update_rate > 0.0, "update rate must be positive");
+        assert!(
+            options.max_updates_per_frame > 0,
+            "at least one update per frame is required"
+        );
+        assert!(
+            options.statistics_window > 0,
+            "statistics window must not be empty"
+        );
+        Self::validate_frame_cap(options.frame_cap);
+
+        Self {
+            fixed_delta: Duration::from_secs_f64(1.0 / options.update_rate),
+            accumulator: Duration::ZERO,
+            last_frame: None,
+            frame_times: VecDeque::with_capacity(options.statistics_window),
+            statistics: FrameStatistics::default(),
+            last_log: Instant::now(),
+            frame_index: 0,
+            update_index: 0,
+            options,
+        }
+    }
+
+    /// Returns the options the loop runs with.
+    pub fn get_options(&self) -> &GameLoopOptions {
+        &self.options
+    }
+
+    /// Changes the frame cap, `None` removes it.
+    /// Panics if the frame cap isn't positive, like `new`.
+    #[track_caller]
+    pub fn set_frame_cap(&mut self, frame_cap: Option<f64>) {
+        Self::validate_frame_cap(frame_cap);
+        self.options.frame_cap = frame_cap;
+    }
+
+    #[track_caller]
+    fn validate_frame_cap(frame_cap: Option<f64>) {
+        assert!(
+            frame_cap.is_none_or(|cap| cap > 0.0),
+            "frame cap must be positive"
+        );
+    }
+
+    /// Duration of a single fixed update.
+    pub fn get_fixed_delta(&self) -> Duration {
+        self.fixed_delta
+    }
+
+    /// Frames started so far.
+    pub fn get_frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Fixed updates handed out so far.
+    pub fn get_update_index(&self) -> u64 {
+        self.update_index
+    }
+
+    /// Frame time statistics over the last `GameLoopOptions::statistics_window` frames.
+    pub fn get_statistics(&self) -> FrameStatistics {
+        self.statistics
+    }
+
+    /// When the next frame is due, `None` without a frame cap or before the first frame.
+    pub fn get_next_frame_time(&self) -> Option<Instant> {
+        let frame_cap = self.options.frame_cap?;
+        self.last_frame
+            .map(|last_frame| last_frame + Duration::from_secs_f64(1.0 / frame_cap))
+    }
+
+    /// Control flow that wakes the event loop for the next frame.
+    pub fn get_control_flow(&self) -> ControlFlow {
+        match self.get_next_frame_time() {
+            Some(next_frame) => ControlFlow::WaitUntil(next_frame),
+            None => ControlFlow::Poll,
+        }
+    }
+
+    /// Starts a frame, `None` if the frame cap says it's too early.
+    pub fn begin_frame(&mut self) -> Option<FrameTick> {
+        let now = Instant::now();
+        if self
+            .get_next_frame_time()
+            .is_some_and(|next_frame| now < next_frame)
+        {
+            return None;
+        }
+
+        let delta = self
+            .last_frame
+            .map_or(Duration::ZERO, |last_frame| now - last_frame);
+        self.last_frame = Some(now);
+
+        Some(self.advance(delta))
+    }
+
+    /// Advances the loop by a frame which took `delta`.
+    fn advance(&mut self, delta: Duration) -> FrameTick {
+        self.frame_index += 1;
+
+        if !delta.is_zero() {
+            self.record_frame_time(delta);
+        }
+
+        self.accumulator += delta;
+        let mut updates = 0;
+        while self.accumulator >= self.fixed_delta {
+            self.accumulator -= self.fixed_delta;
+            updates += 1;
+
+            if updates == self.options.max_updates_per_frame {
+                // Drop the time which can't be caught up with, keeping the phase for alpha
+                self.accumulator = Duration::from_secs_f64(
+                    self.accumulator.as_secs_f64() % self.fixed_delta.as_secs_f64(),
+                );
+                break;
+            }
+        }
+        self.update_index += updates as u64;
+
+        FrameTick {
+            updates,
+            alpha: self.accumulator.as_secs_f64() / self.fixed_delta.as_secs_f64(),
+            delta,
+        }
+    }
+
+    fn record_frame_time(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == self.options.statistics_window {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+        self.statistics = FrameStatistics::compute(&self.frame_times);
+
+        if let Some(log_interval) = self.options.log_interval {
+            if self.last_log.elapsed() >= log_interval {
+                log::info!("{}", self.statistics);
+                self.last_log = Instant::now();
+            }
+        }
+    }
+}
+
+impl Default for GameLoop {
+    fn default() -> Self {
+        Self::new(GameLoopOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_loop(update_rate: f64, max_updates_per_frame: u32) -> GameLoop {
+        GameLoop::new(GameLoopOptions {
+            update_rate,
+            max_updates_per_frame,
+            ..Default::default()
+        })
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn fixed_steps_accumulate() {
+        let mut game_loop = game_loop(100.0, 5);
+
+        let tick = game_loop.advance(Duration::from_millis(25));
+        assert_eq!(tick.updates, 2);
+        assert_close(tick.alpha, 0.5);
+
+        // The remaining 5ms and these 5ms make up another update
+        let tick = game_loop.advance(Duration::from_millis(5));
+        assert_eq!(tick.updates, 1);
+        assert_close(tick.alpha, 0.0);
+
+        let tick = game_loop.advance(Duration::from_millis(3));
+        assert_eq!(tick.updates, 0);
+        assert_close(tick.alpha, 0.3);
+
+        assert_eq!(game_loop.get_frame_index(), 3);
+        assert_eq!(game_loop.get_update_index(), 3);
+    }
+
+    #[test]
+    fn fixed_steps_are_clamped() {
+        let mut game_loop = game_loop(100.0, 5);
+
+        // 7 updates are due, only 5 are run and the time of the other 2 is dropped
+        let tick = game_loop.advance(Duration::from_millis(73));
+        assert_eq!(tick.updates, 5);
+        assert_close(tick.alpha, 0.3);
+
+        let tick = game_loop.advance(Duration::from_millis(7));
+        assert_eq!(tick.updates, 1);
+        assert_close(tick.alpha, 0.0);
+        assert_eq!(game_loop.get_update_index(), 6);
+    }
+
+    #[test]
+    fn frame_statistics() {
+        assert_eq!(
+            FrameStatistics::compute(&VecDeque::new()),
+            FrameStatistics::default()
+        );
+
+        // 1ms to 100ms, shuffled
+        let frame_times = (1..=100)
+            .map(|i| Duration::from_millis((i * 37) % 100 + 1))
+            .collect::<VecDeque<_>>();
+        let statistics = FrameStatistics::compute(&frame_times);
+
+        assert_eq!(statistics.frame_count, 100);
+        assert_eq!(statistics.min, Duration::from_millis(1));
+        assert_eq!(statistics.max, Duration::from_millis(100));
+        assert_eq!(statistics.average, Duration::from_micros(50_500));
+        assert_eq!(statistics.percentile_50, Duration::from_millis(51));
+        assert_eq!(statistics.percentile_95, Duration::from_millis(95));
+        assert_eq!(statistics.percentile_99, Duration::from_millis(99));
+        assert_close(statistics.frames_per_second, 1.0 / 0.0505);
+    }
+
+    #[test]
+    fn frame_cap_can_be_changed() {
+        let mut game_loop = game_loop(100.0, 5);
+
+        game_loop.set_frame_cap(Some(30.0));
+        assert_eq!(game_loop.get_options().frame_cap, Some(30.0));
+
+        game_loop.set_frame_cap(None);
+        assert_eq!(game_loop.get_options().frame_cap, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "frame cap must be positive")]
+    fn zero_frame_cap_is_rejected() {
+        game_loop(100.0, 5).set_frame_cap(Some(0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "frame cap must be positive")]
+    fn negative_frame_cap_is_rejected() {
+        game_loop(100.0, 5).set_frame_cap(Some(-60.0));
+    }
+}
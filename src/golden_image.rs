@@ -0,0 +1,330 @@
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+
+/// Setting this environment variable (to anything but `0`) makes `GoldenImage::assert_matches`
+/// overwrite the golden images with the rendered ones instead of comparing them.
+pub const GOLDEN_IMAGE_BLESS_VARIABLE: &str = "VULKAN_ENGINE_BLESS";
+
+/// The directory diff images and rendered images of failed comparisons are written to.
+pub const GOLDEN_IMAGE_FAILURE_DIRECTORY: &str = "target/golden_images";
+
+/// How much a rendered image may differ from its golden image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GoldenImageTolerance {
+    /// The maximal difference per color channel for a pixel to still count as matching.
+    pub channel_difference: u8,
+    /// The maximal ratio of mismatching pixels, between `0.0` and `1.0`.
+    pub mismatched_ratio: f64,
+    /// The minimal structural similarity (SSIM), between `0.0` and `1.0`.
+    pub structural_similarity: f64,
+}
+
+impl Default for GoldenImageTolerance {
+    /// Tolerates slight rasterization differences between drivers, e.g. at triangle edges.
+    fn default() -> Self {
+        Self {
+            channel_difference: 2,
+            mismatched_ratio: 0.001,
+            structural_similarity: 0.99,
+        }
+    }
+}
+
+/// The result of comparing a rendered image against its golden image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GoldenImageComparison {
+    /// The amount of pixels with a channel differing more than the tolerance.
+    pub mismatched_pixels: u64,
+    /// The ratio of mismatching pixels to all pixels.
+    pub mismatched_ratio: f64,
+    /// The largest difference of any channel of any pixel.
+    pub max_channel_difference: u8,
+    /// The peak signal-to-noise ratio in decibel, infinite for identical images.
+    pub peak_signal_to_noise_ratio: f64,
+    /// The mean structural similarity (SSIM) of the luma, `1.0` for identical images.
+    pub structural_similarity: f64,
+}
+
+impl GoldenImageComparison {
+    /// Compares two images of the same size.
+    /// Also returns a diff image, highlighting mismatching pixels in red on top of a dimmed expected image.
+    pub fn compare(
+        expected: &RgbaImage,
+        actual: &RgbaImage,
+        channel_tolerance: u8,
+    ) -> (Self, RgbaImage) {
+        assert_eq!(
+            expected.dimensions(),
+            actual.dimensions(),
+            "images must have the same size"
+        );
+
+        let mut diff = RgbaImage::new(expected.width(), expected.height());
+        let mut mismatched_pixels = 0;
+        let mut max_channel_difference = 0;
+        let mut squared_error_sum = 0.0;
+
+        for ((expected_pixel, actual_pixel), diff_pixel) in expected
+            .pixels()
+            .zip(actual.pixels())
+            .zip(diff.pixels_mut())
+        {
+            let difference = expected_pixel
+                .0
+                .iter()
+                .zip(actual_pixel.0)
+                .map(|(expected, actual)| expected.abs_diff(actual))
+                .max()
+                .unwrap_or(0);
+
+            squared_error_sum += expected_pixel
+                .0
+                .iter()
+                .zip(actual_pixel.0)
+                .map(|(expected, actual)| (*expected as f64 - actual as f64).powi(2))
+                .sum::<f64>();
+            max_channel_difference = max_channel_difference.max(difference);
+
+            *diff_pixel = if difference > channel_tolerance {
+                mismatched_pixels += 1;
+                Rgba([difference.max(128), 0, 0, 255])
+            } else {
+                let dimmed = (Self::luma(expected_pixel) / 4.0) as u8;
+                Rgba([dimmed, dimmed, dimmed, 255])
+            };
+        }
+
+        let pixel_count = expected.width() as u64 * expected.height() as u64;
+        let mean_squared_error = squared_error_sum / (pixel_count * 4) as f64;
+        let peak_signal_to_noise_ratio = if mean_squared_error == 0.0 {
+            f64::INFINITY
+        } else {
+            10.0 * (255.0 * 255.0 / mean_squared_error).log10()
+        };
+
+        (
+            Self {
+                mismatched_pixels,
+                mismatched_ratio: mismatched_pixels as f64 / pixel_count as f64,
+                max_channel_difference,
+                peak_signal_to_noise_ratio,
+                structural_similarity: Self::structural_similarity(expected, actual),
+            },
+            diff,
+        )
+    }
+
+    /// Returns whether the comparison is within a given tolerance.
+    pub fn is_within(&self, tolerance: &GoldenImageTolerance) -> bool {
+        self.mismatched_ratio <= tolerance.mismatched_ratio
+            && self.structural_similarity >= tolerance.structural_similarity
+    }
+
+    fn luma(pixel: &Rgba<u8>) -> f64 {
+        0.299 * pixel.0[0] as f64 + 0.587 * pixel.0[1] as f64 + 0.114 * pixel.0[2] as f64
+    }
+
+    /// Computes the mean SSIM of the luma over non-overlapping 8x8 windows.
+    fn structural_similarity(expected: &RgbaImage, actual: &RgbaImage) -> f64 {
+        const WINDOW_SIZE: u32 = 8;
+        const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+        const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+        let mut ssim_sum = 0.0;
+        let mut window_count = 0;
+        for window_y in (0..expected.height()).step_by(WINDOW_SIZE as usize) {
+            for window_x in (0..expected.width()).step_by(WINDOW_SIZE as usize) {
+                let pixels: Vec<(f64, f64)> = (window_y
+                    ..(window_y + WINDOW_SIZE).min(expected.height()))
+                    .flat_map(|y| {
+                        (window_x..(window_x + WINDOW_SIZE).min(expected.width())).map(move |x| {
+                            (
+                                Self::luma(expected.get_pixel(x, y)),
+                                Self::luma(actual.get_pixel(x, y)),
+                            )
+                        })
+                    })
+                    .collect();
+
+                let count = pixels.len() as f64;
+                let mean_expected = pixels.iter().map(|(e, _)| e).sum::<f64>() / count;
+                let mean_actual = pixels.iter().map(|(_, a)| a).sum::<f64>() / count;
+                let (mut variance_expected, mut variance_actual, mut covariance) = (0.0, 0.0, 0.0);
+                for (e, a) in &pixels {
+                    variance_expected += (e - mean_expected).powi(2) / count;
+                    variance_actual += (a - mean_actual).powi(2) / count;
+                    covariance += (e - mean_expected) * (a - mean_actual) / count;
+                }
+
+                ssim_sum += ((2.0 * mean_expected * mean_actual + C1) * (2.0 * covariance + C2))
+                    / ((mean_expected.powi(2) + mean_actual.powi(2) + C1)
+                        * (variance_expected + variance_actual + C2));
+                window_count += 1;
+            }
+        }
+
+        ssim_sum / window_count as f64
+    }
+}
+
+/// A stored reference image a rendered image is compared against, e.g. in regression tests.
+#[derive(Clone, Debug)]
+pub struct GoldenImage {
+    path: PathBuf,
+    tolerance: GoldenImageTolerance,
+}
+
+impl GoldenImage {
+    /// Creates a `GoldenImage` stored at a given path, with the default tolerance.
+    pub fn new<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            tolerance: GoldenImageTolerance::default(),
+        }
+    }
+
+    /// Replaces the tolerance.
+    pub fn with_tolerance(mut self, tolerance: GoldenImageTolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Returns whether golden images should be blessed, see `GOLDEN_IMAGE_BLESS_VARIABLE`.
+    pub fn is_blessing() -> bool {
+        std::env::var(GOLDEN_IMAGE_BLESS_VARIABLE).is_ok_and(|value| value != "0")
+    }
+
+    /// Compares a rendered image against the golden image and panics if they differ more than tolerated.
+    /// On failure the rendered image and a diff image are written to `GOLDEN_IMAGE_FAILURE_DIRECTORY`.
+    ///
+    /// If blessing, the golden image is overwritten with the rendered image instead.
+    pub fn assert_matches(&self, actual: &RgbaImage) {
+        if Self::is_blessing() {
+            actual
+                .save(&self.path)
+                .unwrap_or_else(|e| panic!("failed to bless {:?}: {}", self.path, e));
+            log::info!("Blessed golden image {:?}", self.path);
+            return;
+        }
+
+        let expected = image::open(&self.path)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "failed to open golden image {:?}: {}. Run with {}=1 to create it.",
+                    self.path, e, GOLDEN_IMAGE_BLESS_VARIABLE
+                )
+            })
+            .into_rgba8();
+
+        assert_eq!(
+            expected.dimensions(),
+            actual.dimensions(),
+            "rendered image has a different size than golden image {:?}",
+            self.path
+        );
+
+        let (comparison, diff) =
+            GoldenImageComparison::compare(&expected, actual, self.tolerance.channel_difference);
+        log::debug!("Golden image {:?}: {:?}", self.path, comparison);
+
+        if !comparison.is_within(&self.tolerance) {
+            let failure_directory = Path::new(GOLDEN_IMAGE_FAILURE_DIRECTORY);
+            let name = self
+                .path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("golden_image");
+            let actual_path = failure_directory.join(format!("{}.actual.png", name));
+            let diff_path = failure_directory.join(format!("{}.diff.png", name));
+
+            std::fs::create_dir_all(failure_directory)
+                .expect("failed to create golden image failure directory");
+            actual
+                .save(&actual_path)
+                .expect("failed to save rendered image");
+            diff.save(&diff_path).expect("failed to save diff image");
+
+            panic!(
+                "rendered image differs from golden image {:?}: {:?} exceeds {:?}. See {:?} and {:?}, run with {}=1 to accept the change.",
+                self.path,
+                comparison,
+                self.tolerance,
+                actual_path,
+                diff_path,
+                GOLDEN_IMAGE_BLESS_VARIABLE
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient() -> RgbaImage {
+        RgbaImage::from_fn(32, 32, |x, y| {
+            Rgba([(x * 7) as u8, (y * 7) as u8, 100, 255])
+        })
+    }
+
+    #[test]
+    fn identical_images_match_perfectly() {
+        let image = gradient();
+
+        let (comparison, _) = GoldenImageComparison::compare(&image, &image, 0);
+
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert_eq!(comparison.max_channel_difference, 0);
+        assert_eq!(comparison.peak_signal_to_noise_ratio, f64::INFINITY);
+        assert!((comparison.structural_similarity - 1.0).abs() < 1e-9);
+        assert!(comparison.is_within(&GoldenImageTolerance::default()));
+    }
+
+    #[test]
+    fn slight_difference_is_tolerated() {
+        let expected = gradient();
+        let mut actual = expected.clone();
+        for pixel in actual.pixels_mut() {
+            for channel in &mut pixel.0[..3] {
+                *channel += 1;
+            }
+        }
+
+        let (comparison, _) = GoldenImageComparison::compare(&expected, &actual, 2);
+
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert_eq!(comparison.max_channel_difference, 1);
+        // Three of four channels off by one: MSE = 0.75
+        let expected_psnr = 10.0 * (255.0 * 255.0 / 0.75f64).log10();
+        assert!((comparison.peak_signal_to_noise_ratio - expected_psnr).abs() < 1e-9);
+        assert!(comparison.is_within(&GoldenImageTolerance::default()));
+    }
+
+    #[test]
+    fn inverted_block_exceeds_tolerance() {
+        let expected = gradient();
+        let mut actual = expected.clone();
+        for y in 8..16 {
+            for x in 8..16 {
+                let pixel = actual.get_pixel_mut(x, y);
+                for channel in &mut pixel.0[..3] {
+                    *channel = 255 - *channel;
+                }
+            }
+        }
+
+        let (comparison, diff) = GoldenImageComparison::compare(&expected, &actual, 2);
+
+        assert_eq!(comparison.mismatched_pixels, 64);
+        assert_eq!(comparison.mismatched_ratio, 64.0 / 1024.0);
+        assert!(comparison.structural_similarity < 0.99);
+        assert!(!comparison.is_within(&GoldenImageTolerance::default()));
+        // Mismatching pixels are highlighted in red
+        let highlighted = diff.get_pixel(8, 8).0;
+        assert!(highlighted[0] >= 128 && highlighted[1..] == [0, 0, 255]);
+    }
+}
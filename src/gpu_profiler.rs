@@ -0,0 +1,401 @@
+use std::{collections::VecDeque, fmt::Write as _, fs, io, path::Path, sync::Arc};
+
+use vulkano::{
+    command_buffer::allocator::StandardCommandBufferAllocator,
+    query::{QueryPool, QueryPoolCreateInfo, QueryResultFlags, QueryType},
+    sync::PipelineStage,
+};
+
+use crate::{AbstractEngine, CommandBufferLabels, EngineCommandBufferBuilder};
+
+/// Amount of resolved frames the `GpuProfiler` keeps for averages and trace exports.
+pub const GPU_PROFILER_HISTORY_LENGTH: usize = 600;
+
+/// GPU time spent in a profiler scope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuTiming {
+    pub name: String,
+    /// Nesting level, `0` for top level scopes.
+    pub depth: u32,
+    /// Start in milliseconds since the first resolved timestamp of the profiler.
+    pub start: f64,
+    pub duration: f64,
+}
+
+/// All resolved scopes of a single frame, in the order they were opened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuFrameTimings {
+    pub frame_index: u64,
+    pub timings: Vec<GpuTiming>,
+}
+
+impl GpuFrameTimings {
+    /// GPU time from the start of the first to the end of the last top level scope.
+    pub fn get_total(&self) -> f64 {
+        let top_level = self.timings.iter().filter(|timing| timing.depth == 0);
+        let start = top_level
+            .clone()
+            .map(|timing| timing.start)
+            .fold(f64::INFINITY, f64::min);
+        let end = top_level
+            .map(|timing| timing.start + timing.duration)
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        if start.is_finite() {
+            end - start
+        } else {
+            0.0
+        }
+    }
+
+    /// Duration of the first scope with the given name.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.timings
+            .iter()
+            .find(|timing| timing.name == name)
+            .map(|timing| timing.duration)
+    }
+}
+
+struct ProfilerScope {
+    name: String,
+    depth: u32,
+    begin_query: u32,
+    end_query: Option<u32>,
+}
+
+struct ProfilerFrame {
+    query_pool: Arc<QueryPool>,
+    scopes: Vec<ProfilerScope>,
+    next_query: u32,
+    frame_index: u64,
+    pending: bool,
+}
+
+/// Profiles GPU work with timestamp queries.
+///
+/// Every frame in flight records into its own `QueryPool`, results are resolved once the
+/// frame slot comes around again in `begin_frame` or when calling `resolve`.
+///
+/// ```ignore
+/// profiler.begin_frame(&mut builder);
+/// profiler.scope(&mut builder, "scene", |builder| scene.record(builder));
+/// profiler.scope(&mut builder, "ui", |builder| ui.record(builder));
+/// profiler.end_frame();
+/// ```
+pub struct GpuProfiler {
+    frames: Vec<ProfilerFrame>,
+    current_frame: usize,
+    frame_index: u64,
+    open_scopes: Vec<usize>,
+    max_scopes: u32,
+    timestamp_period: f64,
+    timestamp_mask: u64,
+    epoch: Option<u64>,
+    history: VecDeque<GpuFrameTimings>,
+}
+
+impl GpuProfiler {
+    /// Creates a profiler for up to `max_scopes` scopes per frame, with `frames_in_flight` frames
+    /// recorded before the results of the oldest one are needed.
+    /// Panics if the engine's queue family doesn't support timestamps.
+    pub fn new<E>(engine: &E, frames_in_flight: usize, max_scopes: u32) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        log::debug!("GpuProfiler::new");
+
+        assert!(frames_in_flight > 0, "at least one frame is required");
+
+        let logical_device = engine.get_logical_device();
+        let physical_device = logical_device.get_physical_device();
+
+        let valid_bits = physical_device.queue_family_properties()
+            [logical_device.get_queue_family_index() as usize]
+            .timestamp_valid_bits
+            .expect("the queue family doesn't support timestamps");
+        let timestamp_mask = if valid_bits >= 64 {
+            u64::MAX
+        } else {
+            (1 << valid_bits) - 1
+        };
+
+        let frames = (0..frames_in_flight)
+            .map(|_| ProfilerFrame {
+                query_pool: QueryPool::new(
+                    logical_device.get_device(),
+                    QueryPoolCreateInfo {
+                        query_count: max_scopes * 2,
+                        ..QueryPoolCreateInfo::query_type(QueryType::Timestamp)
+                    },
+                )
+                .expect("failed to create query pool"),
+                scopes: Vec::new(),
+                next_query: 0,
+                frame_index: 0,
+                pending: false,
+            })
+            .collect();
+
+        Self {
+            frames,
+            current_frame: 0,
+            frame_index: 0,
+            open_scopes: Vec::new(),
+            max_scopes,
+            // Nanoseconds per tick
+            timestamp_period: physical_device.properties().timestamp_period as f64,
+            timestamp_mask,
+            epoch: None,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Starts profiling a frame, must be recorded outside of a render pass before any scope.
+    /// Waits for the results of the frame previously recorded into the same slot, which must have been submitted.
+    pub fn begin_frame(&mut self, builder: &mut EngineCommandBufferBuilder) {
+        assert!(
+            self.open_scopes.is_empty(),
+            "the previous frame has open scopes"
+        );
+
+        self.current_frame = (self.frame_index % self.frames.len() as u64) as usize;
+        self.resolve_frame(self.current_frame, true);
+        self.frame_index += 1;
+
+        let frame = &mut self.frames[self.current_frame];
+        frame.scopes.clear();
+        frame.next_query = 0;
+        frame.frame_index = self.frame_index;
+
+        unsafe {
+            builder
+                .reset_query_pool(frame.query_pool.clone(), 0..self.max_scopes * 2)
+                .expect("failed to reset query pool");
+        }
+    }
+
+    /// Opens a scope, scopes nest and must be closed in reverse order.
+    /// Every scope is also a debug label, scopes beyond the maximum of the frame are only labeled and skipped with a warning.
+    pub fn begin_scope(&mut self, builder: &mut EngineCommandBufferBuilder, name: &str) {
+        builder.begin_label(name, [0.0; 4]);
+
+        let depth = self.open_scopes.len() as u32;
+        let frame = &mut self.frames[self.current_frame];
+
+        // Every scope takes two queries, so counting scopes keeps open scopes' end queries reserved
+        if frame.scopes.len() as u32 == self.max_scopes {
+            log::warn!("GpuProfiler scope limit reached, skipping '{}'", name);
+            self.open_scopes.push(usize::MAX);
+            return;
+        }
+
+        let begin_query = frame.next_query;
+        frame.next_query += 1;
+        unsafe {
+            builder
+                .write_timestamp(
+                    frame.query_pool.clone(),
+                    begin_query,
+                    PipelineStage::TopOfPipe,
+                )
+                .expect("failed to write timestamp");
+        }
+
+        self.open_scopes.push(frame.scopes.len());
+        frame.scopes.push(ProfilerScope {
+            name: name.to_string(),
+            depth,
+            begin_query,
+            end_query: None,
+        });
+    }
+
+    /// Closes the innermost open scope.
+    /// Prefer `scope`, which always closes the scope in the command buffer it was opened in.
+    ///
+    /// # Safety
+    /// The scope must have been opened by `begin_scope` with the same command buffer, as it closes the scope's debug label.
+    pub unsafe fn end_scope(&mut self, builder: &mut EngineCommandBufferBuilder) {
+        let scope = self.open_scopes.pop().expect("no scope is open");
+        builder.end_label();
+        if scope == usize::MAX {
+            return;
+        }
+
+        let frame = &mut self.frames[self.current_frame];
+        let end_query = frame.next_query;
+        frame.next_query += 1;
+        unsafe {
+            builder
+                .write_timestamp(
+                    frame.query_pool.clone(),
+                    end_query,
+                    PipelineStage::BottomOfPipe,
+                )
+                .expect("failed to write timestamp");
+        }
+        frame.scopes[scope].end_query = Some(end_query);
+    }
+
+    /// Records `record` inside a scope.
+    pub fn scope<R>(
+        &mut self,
+        builder: &mut EngineCommandBufferBuilder,
+        name: &str,
+        record: impl FnOnce(&mut EngineCommandBufferBuilder) -> R,
+    ) -> R {
+        self.begin_scope(builder, name);
+        let result = record(builder);
+        // Safety: closes the scope opened above in the same command buffer
+        unsafe {
+            self.end_scope(builder);
+        }
+
+        result
+    }
+
+    /// Finishes the frame, its results become available once the GPU executed it.
+    pub fn end_frame(&mut self) {
+        assert!(
+            self.open_scopes.is_empty(),
+            "all scopes must be closed before ending the frame"
+        );
+
+        self.frames[self.current_frame].pending = true;
+    }
+
+    /// Resolves all frames the GPU already finished, without waiting for the others.
+    pub fn resolve(&mut self) {
+        let mut pending = (0..self.frames.len())
+            .filter(|&index| self.frames[index].pending)
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|&index| self.frames[index].frame_index);
+
+        for index in pending {
+            self.resolve_frame(index, false);
+        }
+    }
+
+    fn resolve_frame(&mut self, index: usize, wait: bool) {
+        let frame = &mut self.frames[index];
+        if !frame.pending {
+            return;
+        }
+
+        let query_count = frame.next_query;
+        let mut timestamps = vec![0u64; query_count.max(1) as usize];
+        if query_count > 0 {
+            let flags = if wait {
+                QueryResultFlags::WAIT
+            } else {
+                QueryResultFlags::empty()
+            };
+
+            let available = frame
+                .query_pool
+                .queries_range(0..query_count)
+                .unwrap()
+                .get_results(&mut timestamps, flags)
+                .expect("failed to get query results");
+            if !available {
+                return;
+            }
+
+            // Frames without queries have no timestamp to start from
+            self.epoch.get_or_insert(timestamps[0]);
+        }
+        frame.pending = false;
+
+        let epoch = self.epoch.unwrap_or_default();
+        let to_milliseconds =
+            |ticks: u64| (ticks & self.timestamp_mask) as f64 * self.timestamp_period / 1_000_000.0;
+
+        let timings = frame
+            .scopes
+            .iter()
+            .filter_map(|scope| {
+                let begin = timestamps[scope.begin_query as usize];
+                let end = timestamps[scope.end_query? as usize];
+
+                Some(GpuTiming {
+                    name: scope.name.clone(),
+                    depth: scope.depth,
+                    start: to_milliseconds(begin.wrapping_sub(epoch)),
+                    duration: to_milliseconds(end.wrapping_sub(begin)),
+                })
+            })
+            .collect();
+
+        if self.history.len() == GPU_PROFILER_HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(GpuFrameTimings {
+            frame_index: frame.frame_index,
+            timings,
+        });
+    }
+
+    /// Returns the most recently resolved frame.
+    pub fn get_latest_frame(&self) -> Option<&GpuFrameTimings> {
+        self.history.back()
+    }
+
+    /// Returns the resolved frames, oldest first.
+    pub fn get_history(&self) -> impl Iterator<Item = &GpuFrameTimings> {
+        self.history.iter()
+    }
+
+    /// Average duration of a scope over the history in milliseconds, `None` if it never ran.
+    pub fn get_average(&self, name: &str) -> Option<f64> {
+        let durations = self
+            .history
+            .iter()
+            .flat_map(|frame| frame.timings.iter())
+            .filter(|timing| timing.name == name)
+            .map(|timing| timing.duration)
+            .collect::<Vec<_>>();
+
+        if durations.is_empty() {
+            None
+        } else {
+            Some(durations.iter().sum::<f64>() / durations.len() as f64)
+        }
+    }
+
+    /// Converts the history into the Chrome trace event format (`chrome://tracing`, Perfetto).
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events = Vec::new();
+
+        for frame in &self.history {
+            for timing in &frame.timings {
+                let mut name = String::new();
+                for character in timing.name.chars() {
+                    match character {
+                        '"' => name.push_str("\\\""),
+                        '\\' => name.push_str("\\\\"),
+                        character if character.is_control() => {
+                            write!(name, "\\u{:04x}", character as u32).unwrap()
+                        }
+                        character => name.push(character),
+                    }
+                }
+
+                events.push(format!(
+                    "{{\"name\":\"{}\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":0,\"tid\":0,\"args\":{{\"frame\":{}}}}}",
+                    name,
+                    timing.start * 1000.0,
+                    timing.duration * 1000.0,
+                    frame.frame_index
+                ));
+            }
+        }
+
+        format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+    }
+
+    /// Writes `to_chrome_trace` into a file.
+    pub fn write_chrome_trace<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_chrome_trace())
+    }
+}
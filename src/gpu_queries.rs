@@ -0,0 +1,329 @@
+use std::sync::Arc;
+
+use vulkano::query::{
+    QueryControlFlags, QueryPipelineStatisticFlags, QueryPool, QueryPoolCreateInfo,
+    QueryResultFlags, QueryType,
+};
+
+use crate::{EngineCommandBufferBuilder, LogicalDevice};
+
+/// Counters of a `PipelineStatisticsQuery`, `None` for counters which weren't queried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PipelineStatistics {
+    pub input_assembly_vertices: Option<u64>,
+    pub input_assembly_primitives: Option<u64>,
+    pub vertex_shader_invocations: Option<u64>,
+    pub geometry_shader_invocations: Option<u64>,
+    pub geometry_shader_primitives: Option<u64>,
+    pub clipping_invocations: Option<u64>,
+    pub clipping_primitives: Option<u64>,
+    pub fragment_shader_invocations: Option<u64>,
+    pub tessellation_control_shader_patches: Option<u64>,
+    pub tessellation_evaluation_shader_invocations: Option<u64>,
+    pub compute_shader_invocations: Option<u64>,
+}
+
+impl PipelineStatistics {
+    /// Statistic flags in the order Vulkan writes their results.
+    const FLAGS: [QueryPipelineStatisticFlags; 11] = [
+        QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES,
+        QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES,
+        QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS,
+        QueryPipelineStatisticFlags::GEOMETRY_SHADER_INVOCATIONS,
+        QueryPipelineStatisticFlags::GEOMETRY_SHADER_PRIMITIVES,
+        QueryPipelineStatisticFlags::CLIPPING_INVOCATIONS,
+        QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES,
+        QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS,
+        QueryPipelineStatisticFlags::TESSELLATION_CONTROL_SHADER_PATCHES,
+        QueryPipelineStatisticFlags::TESSELLATION_EVALUATION_SHADER_INVOCATIONS,
+        QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS,
+    ];
+
+    fn from_results(flags: QueryPipelineStatisticFlags, results: &[u64]) -> Self {
+        let mut values = [None; 11];
+        let mut results = results.iter();
+        for (value, flag) in values.iter_mut().zip(Self::FLAGS) {
+            if flags.intersects(flag) {
+                *value = results.next().copied();
+            }
+        }
+
+        Self {
+            input_assembly_vertices: values[0],
+            input_assembly_primitives: values[1],
+            vertex_shader_invocations: values[2],
+            geometry_shader_invocations: values[3],
+            geometry_shader_primitives: values[4],
+            clipping_invocations: values[5],
+            clipping_primitives: values[6],
+            fragment_shader_invocations: values[7],
+            tessellation_control_shader_patches: values[8],
+            tessellation_evaluation_shader_invocations: values[9],
+            compute_shader_invocations: values[10],
+        }
+    }
+
+    /// Prints out all queried counters.
+    pub fn print(&self, log_level: log::Level) {
+        let counters = [
+            ("Input Assembly Vertices", self.input_assembly_vertices),
+            ("Input Assembly Primitives", self.input_assembly_primitives),
+            ("Vertex Shader Invocations", self.vertex_shader_invocations),
+            (
+                "Geometry Shader Invocations",
+                self.geometry_shader_invocations,
+            ),
+            (
+                "Geometry Shader Primitives",
+                self.geometry_shader_primitives,
+            ),
+            ("Clipping Invocations", self.clipping_invocations),
+            ("Clipping Primitives", self.clipping_primitives),
+            (
+                "Fragment Shader Invocations",
+                self.fragment_shader_invocations,
+            ),
+            (
+                "Tessellation Control Shader Patches",
+                self.tessellation_control_shader_patches,
+            ),
+            (
+                "Tessellation Evaluation Shader Invocations",
+                self.tessellation_evaluation_shader_invocations,
+            ),
+            (
+                "Compute Shader Invocations",
+                self.compute_shader_invocations,
+            ),
+        ];
+
+        for (name, value) in counters {
+            if let Some(value) = value {
+                log::log!(log_level, "{}: {}", name, value);
+            }
+        }
+    }
+}
+
+/// Counts what the GPU did between `begin` and `end`.
+/// Create it with `AbstractEngine::create_pipeline_statistics_query`.
+pub struct PipelineStatisticsQuery {
+    query_pool: Arc<QueryPool>,
+    flags: QueryPipelineStatisticFlags,
+}
+
+impl PipelineStatisticsQuery {
+    pub(crate) fn new(logical_device: &LogicalDevice, flags: QueryPipelineStatisticFlags) -> Self {
+        log::debug!("PipelineStatisticsQuery::new");
+
+        assert!(
+            logical_device
+                .get_device()
+                .enabled_features()
+                .pipeline_statistics_query,
+            "the device doesn't support pipeline statistics queries"
+        );
+        assert!(
+            !flags.is_empty()
+                && PipelineStatistics::FLAGS
+                    .into_iter()
+                    .fold(flags, |flags, flag| flags.difference(flag))
+                    .is_empty(),
+            "unsupported pipeline statistics flags: {:?}",
+            flags
+        );
+
+        let query_pool = QueryPool::new(
+            logical_device.get_device(),
+            QueryPoolCreateInfo {
+                query_count: 1,
+                ..QueryPoolCreateInfo::query_type(QueryType::PipelineStatistics(flags))
+            },
+        )
+        .expect("failed to create query pool");
+
+        Self { query_pool, flags }
+    }
+
+    /// Resets the query, must be recorded outside of a render pass before `begin`.
+    pub fn reset(&self, builder: &mut EngineCommandBufferBuilder) {
+        unsafe {
+            builder
+                .reset_query_pool(self.query_pool.clone(), 0..1)
+                .expect("failed to reset query pool");
+        }
+    }
+
+    /// Starts counting. Graphics counters need a graphics queue, compute counters a compute one.
+    pub fn begin(&self, builder: &mut EngineCommandBufferBuilder) {
+        unsafe {
+            builder
+                .begin_query(self.query_pool.clone(), 0, QueryControlFlags::empty())
+                .expect("failed to begin pipeline statistics query");
+        }
+    }
+
+    pub fn end(&self, builder: &mut EngineCommandBufferBuilder) {
+        builder
+            .end_query(self.query_pool.clone(), 0)
+            .expect("failed to end pipeline statistics query");
+    }
+
+    /// Reads the counters, `None` if the command buffer hasn't finished yet and `wait` is `false`.
+    pub fn get_results(&self, wait: bool) -> Option<PipelineStatistics> {
+        let mut results = vec![0u64; Self::count_flags(self.flags)];
+
+        let available = self
+            .query_pool
+            .queries_range(0..1)
+            .unwrap()
+            .get_results(&mut results, result_flags(wait))
+            .expect("failed to get query results");
+
+        available.then(|| PipelineStatistics::from_results(self.flags, &results))
+    }
+
+    fn count_flags(flags: QueryPipelineStatisticFlags) -> usize {
+        PipelineStatistics::FLAGS
+            .into_iter()
+            .filter(|flag| flags.intersects(*flag))
+            .count()
+    }
+
+    pub fn get_flags(&self) -> QueryPipelineStatisticFlags {
+        self.flags
+    }
+}
+
+/// Result of a single occlusion query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OcclusionResult {
+    pub index: u32,
+    /// Samples which passed the depth and stencil tests. Without precise queries only zero
+    /// and non zero are meaningful.
+    pub samples_passed: u64,
+}
+
+impl OcclusionResult {
+    pub fn is_visible(&self) -> bool {
+        self.samples_passed > 0
+    }
+}
+
+/// A set of occlusion queries, e.g. one per object tested for visibility.
+/// Create it with `AbstractEngine::create_occlusion_queries`.
+pub struct OcclusionQueries {
+    query_pool: Arc<QueryPool>,
+    precise: bool,
+}
+
+impl OcclusionQueries {
+    pub(crate) fn new(logical_device: &LogicalDevice, count: u32, precise: bool) -> Self {
+        log::debug!("OcclusionQueries::new");
+
+        assert!(
+            !precise
+                || logical_device
+                    .get_device()
+                    .enabled_features()
+                    .occlusion_query_precise,
+            "the device doesn't support precise occlusion queries"
+        );
+
+        let query_pool = QueryPool::new(
+            logical_device.get_device(),
+            QueryPoolCreateInfo {
+                query_count: count,
+                ..QueryPoolCreateInfo::query_type(QueryType::Occlusion)
+            },
+        )
+        .expect("failed to create query pool");
+
+        Self {
+            query_pool,
+            precise,
+        }
+    }
+
+    /// Resets all queries, must be recorded outside of a render pass before any `begin`.
+    pub fn reset(&self, builder: &mut EngineCommandBufferBuilder) {
+        unsafe {
+            builder
+                .reset_query_pool(self.query_pool.clone(), 0..self.get_count())
+                .expect("failed to reset query pool");
+        }
+    }
+
+    /// Starts counting samples for the query with the given index, inside a render pass.
+    pub fn begin(&self, builder: &mut EngineCommandBufferBuilder, index: u32) {
+        let flags = if self.precise {
+            QueryControlFlags::PRECISE
+        } else {
+            QueryControlFlags::empty()
+        };
+
+        unsafe {
+            builder
+                .begin_query(self.query_pool.clone(), index, flags)
+                .expect("failed to begin occlusion query");
+        }
+    }
+
+    pub fn end(&self, builder: &mut EngineCommandBufferBuilder, index: u32) {
+        builder
+            .end_query(self.query_pool.clone(), index)
+            .expect("failed to end occlusion query");
+    }
+
+    /// Reads all results, `None` if the command buffer hasn't finished yet and `wait` is `false`.
+    /// Every query must have been begun and ended.
+    pub fn get_results(&self, wait: bool) -> Option<Vec<OcclusionResult>> {
+        let mut results = vec![0u64; self.get_count() as usize];
+
+        let available = self
+            .query_pool
+            .queries_range(0..self.get_count())
+            .unwrap()
+            .get_results(&mut results, result_flags(wait))
+            .expect("failed to get query results");
+
+        available.then(|| {
+            results
+                .into_iter()
+                .zip(0..)
+                .map(|(samples_passed, index)| OcclusionResult {
+                    index,
+                    samples_passed,
+                })
+                .collect()
+        })
+    }
+
+    /// Prints out the passed samples of every query.
+    pub fn print_results(results: &[OcclusionResult], log_level: log::Level) {
+        for result in results {
+            log::log!(
+                log_level,
+                "Occlusion Query {}: {} samples passed",
+                result.index,
+                result.samples_passed
+            );
+        }
+    }
+
+    pub fn get_count(&self) -> u32 {
+        self.query_pool.query_count()
+    }
+
+    pub fn is_precise(&self) -> bool {
+        self.precise
+    }
+}
+
+fn result_flags(wait: bool) -> QueryResultFlags {
+    if wait {
+        QueryResultFlags::WAIT
+    } else {
+        QueryResultFlags::empty()
+    }
+}
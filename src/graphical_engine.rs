@@ -1,33 +1,44 @@
 use std::sync::Arc;
-use std::time::Instant;
+
+use image::RgbaImage;
 
 use vulkano::{
-    command_buffer::{
-        allocator::{StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo},
-        PrimaryAutoCommandBuffer, PrimaryCommandBufferAbstract,
+    command_buffer::allocator::{
+        StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo,
     },
     device::{
-        physical::PhysicalDevice, Device, DeviceCreateInfo, DeviceExtensions, Queue,
-        QueueCreateInfo, QueueFlags,
+        physical::PhysicalDevice, Device, DeviceCreateInfo, DeviceExtensions, Queue, QueueFlags,
     },
-    image::{view::ImageView, ImageUsage, SwapchainImage},
+    format::Format,
+    image::{ImageUsage, SwapchainImage},
     instance::InstanceExtensions,
     instance::{Instance, InstanceCreateInfo},
-    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass},
-    swapchain::{Surface, Swapchain, SwapchainCreateInfo, SwapchainCreationError},
-    sync::GpuFuture,
+    memory::allocator::StandardMemoryAllocator,
+    render_pass::{Framebuffer, RenderPass},
+    swapchain::{Surface, Swapchain},
     VulkanLibrary,
 };
+use winit::{
+    event::WindowEvent,
+    event_loop::EventLoopWindowTarget,
+    window::{CursorGrabMode, Window, WindowId},
+};
 
-use crate::{AbstractEngine, LogicalDevice};
+use crate::{
+    AbstractEngine, DeviceSelector, EngineCommandBufferBuilder, EngineError, EngineWindow,
+    FrameCapture, LeakCheck, LogicalDevice, RecoveryCallback, RecoveryStage, WindowConfig,
+    WindowFullscreen,
+};
 
 pub struct GraphicalEngine {
     instance: Arc<Instance>,
     logical_device: Arc<LogicalDevice>,
-    window: Arc<Surface>,
-    swap_chain: Arc<Swapchain>,
-    swap_chain_images: Vec<Arc<SwapchainImage>>,
+    /// The primary window first, which the device was selected for.
+    windows: Vec<EngineWindow>,
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    recovery_callback: Option<RecoveryCallback<Self>>,
+    /// Last, to report the tracked resources which outlived the engine's own ones.
+    leak_check: LeakCheck,
 }
 
 impl GraphicalEngine {
@@ -42,41 +53,135 @@ impl GraphicalEngine {
     }
 
     /// Creates a `GraphicalEngine` instance and initializes everything needed for graphical tasks.
+    /// The window is used as it is, presenting with vsync. The device is selected by `DeviceSelector::Automatic`.
     pub fn new(instance: Arc<Instance>, window: Arc<Surface>) -> Self {
+        Self::with_window(
+            instance,
+            window,
+            &WindowConfig::default(),
+            &DeviceSelector::Automatic,
+        )
+    }
+
+    /// Creates the window described by the `WindowConfig` and a `GraphicalEngine` presenting to it.
+    pub fn with_window_config<T>(
+        instance: Arc<Instance>,
+        event_loop: &EventLoopWindowTarget<T>,
+        config: &WindowConfig,
+    ) -> Self {
+        Self::with_device_selector(instance, event_loop, config, &DeviceSelector::Automatic)
+    }
+
+    /// Creates the window described by the `WindowConfig` and a `GraphicalEngine` presenting to it,
+    /// on the `PhysicalDevice` chosen by the `DeviceSelector`.
+    pub fn with_device_selector<T>(
+        instance: Arc<Instance>,
+        event_loop: &EventLoopWindowTarget<T>,
+        config: &WindowConfig,
+        selector: &DeviceSelector,
+    ) -> Self {
+        let window = config.build_surface(instance.clone(), event_loop);
+
+        Self::with_window(instance, window, config, selector)
+    }
+
+    fn with_window(
+        instance: Arc<Instance>,
+        window: Arc<Surface>,
+        config: &WindowConfig,
+        selector: &DeviceSelector,
+    ) -> Self {
         log::debug!("GraphicalEngine::startup");
 
         let (physical_device, queue_family_index) =
-            Self::get_physical_device(instance.clone(), window.clone());
+            Self::get_physical_device(&instance, &window, selector);
 
         let logical_device = Self::create_logical_device(physical_device, queue_family_index);
 
         logical_device.print_interesting_information(log::Level::Debug);
 
-        let (swap_chain, swap_chain_images) =
-            Self::create_swap_chain(logical_device.clone(), window.clone());
+        let primary_window = EngineWindow::new(logical_device.clone(), window, config);
 
-        let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
-            logical_device.get_device(),
-            StandardCommandBufferAllocatorCreateInfo {
-                ..Default::default()
-            },
-        ));
+        let command_buffer_allocator = Self::create_command_buffer_allocator(&logical_device);
 
         Self {
             instance,
+            leak_check: LeakCheck::new(logical_device.get_device()),
             logical_device,
-            window,
-            swap_chain,
-            swap_chain_images,
+            windows: vec![primary_window],
             command_buffer_allocator,
+            recovery_callback: None,
+        }
+    }
+
+    fn create_command_buffer_allocator(
+        logical_device: &LogicalDevice,
+    ) -> Arc<StandardCommandBufferAllocator> {
+        Arc::new(StandardCommandBufferAllocator::new(
+            logical_device.get_device(),
+            StandardCommandBufferAllocatorCreateInfo {
+                ..Default::default()
+            },
+        ))
+    }
+
+    /// Sets the callback `recover` calls before and after the device is recreated, see `RecoveryStage`.
+    /// All resources of the old device (memory allocators, buffers, images, pipelines, render passes, ...)
+    /// are invalid and have to be recreated and re-uploaded in `RecoveryStage::Recreate`.
+    pub fn set_recovery_callback(
+        &mut self,
+        callback: impl FnMut(&Self, RecoveryStage) + Send + Sync + 'static,
+    ) {
+        self.recovery_callback = Some(Box::new(callback));
+    }
+
+    /// Recreates the `LogicalDevice`, the command buffer allocator and the `Swapchain`s of all windows on the same `PhysicalDevice`,
+    /// e.g. after `try_compute` or `EngineWindow::try_present` failed with an error which `EngineError::requires_recovery`.
+    /// Calls the recovery callback before and afterwards.
+    ///
+    /// Returns `EngineError::SwapchainInUse` without recreating anything if the application still references a `Swapchain`
+    /// after `RecoveryStage::Release`, e.g. through its `Framebuffer`s. The recovery can be retried once they are released.
+    pub fn recover(&mut self) -> Result<(), EngineError> {
+        log::warn!("GraphicalEngine::recover");
+
+        self.run_recovery_callback(RecoveryStage::Release);
+        if self.windows.iter().any(EngineWindow::is_swap_chain_in_use) {
+            return Err(EngineError::SwapchainInUse);
+        }
+
+        self.logical_device = Self::create_logical_device(
+            self.logical_device.get_physical_device(),
+            self.logical_device.get_queue_family_index(),
+        );
+        self.command_buffer_allocator = Self::create_command_buffer_allocator(&self.logical_device);
+        self.windows = std::mem::take(&mut self.windows)
+            .into_iter()
+            .map(|window| window.recreate_for_device(self.logical_device.clone()))
+            .collect();
+
+        self.run_recovery_callback(RecoveryStage::Recreate);
+
+        // The callback replaced all resources of the old device, the remaining ones leaked
+        self.leak_check = LeakCheck::new(self.logical_device.get_device());
+
+        Ok(())
+    }
+
+    fn run_recovery_callback(&mut self, stage: RecoveryStage) {
+        if let Some(mut callback) = self.recovery_callback.take() {
+            callback(self, stage);
+            self.recovery_callback = Some(callback);
         }
     }
 
-    /// Retrieves the required extensions to run the engine.
+    /// Retrieves the required extensions to run the engine, plus `VK_EXT_debug_utils` if available.
     fn retrieve_required_instance_extensions(library: &VulkanLibrary) -> InstanceExtensions {
         log::debug!("GraphicalEngine::retrieve_required_instance_extensions");
 
-        vulkano_win::required_extensions(library)
+        InstanceExtensions {
+            ext_debug_utils: library.supported_extensions().ext_debug_utils,
+            ..vulkano_win::required_extensions(library)
+        }
     }
 
     /// Returns the required device extensions.
@@ -108,68 +213,45 @@ impl GraphicalEngine {
         .expect("failed to create Vulkan instance")
     }
 
-    /// Queries all `PhysicalDevice`'s and returns the best match.
-    /// Returns a tuple of the best `PhysicalDevice` with the best `QueueFamily` index.
+    /// Returns the `PhysicalDevice` chosen by the `DeviceSelector` among the ones able to present to the window,
+    /// together with the index of the graphics queue family to use.
     fn get_physical_device(
-        instance: Arc<Instance>,
-        window: Arc<Surface>,
+        instance: &Arc<Instance>,
+        window: &Surface,
+        selector: &DeviceSelector,
     ) -> (Arc<PhysicalDevice>, u32) {
         log::debug!("GraphicalEngine::get_physical_device");
 
-        instance
-            .enumerate_physical_devices()
-            .expect("failed enumerating physical devices")
-            // Filter out any devices that don't have a queue family that supports graphics
-            .filter(|physical_device: &Arc<PhysicalDevice>| {
-                physical_device
-                    .queue_family_properties()
-                    .iter()
-                    .any(|queue_family| queue_family.queue_flags.contains(QueueFlags::GRAPHICS))
-            })
-            // Filter out any device that doesn't support ur required device extensions
-            .filter(|physical_device: &Arc<PhysicalDevice>| {
-                physical_device
-                    .supported_extensions()
-                    .contains(&GraphicalEngine::retrieve_required_device_extensions())
-            })
-            .map(|physical_device| {
-                let best_queue_family_index =
-                    GraphicalEngine::find_best_suited_queue_family(physical_device.clone());
-
-                (physical_device, best_queue_family_index)
-            })
-            // Filter out any device that doesn't support our VkSurface
-            .filter(|(physical_device, queue_family_index)| {
-                physical_device
-                    .surface_support(*queue_family_index, &window)
-                    .unwrap_or(false)
-            })
-            .min_by_key(|(physical_device, _queue_family_index)| {
-                match physical_device.properties().device_type {
-                    vulkano::device::physical::PhysicalDeviceType::DiscreteGpu => 0,
-                    vulkano::device::physical::PhysicalDeviceType::IntegratedGpu => 1,
-                    vulkano::device::physical::PhysicalDeviceType::VirtualGpu => 2,
-                    vulkano::device::physical::PhysicalDeviceType::Cpu => 3,
-                    vulkano::device::physical::PhysicalDeviceType::Other => 4,
-                    _ => 5,
-                }
-            })
-            .expect("no physical device found or available")
+        let (physical_device, queue_family_index) = selector.select(instance, |physical_device| {
+            // Filter out any device that doesn't support our required device extensions
+            if !physical_device
+                .supported_extensions()
+                .contains(&GraphicalEngine::retrieve_required_device_extensions())
+            {
+                return None;
+            }
+
+            // Filter out any device without a graphics queue family supporting our VkSurface
+            GraphicalEngine::find_best_suited_queue_family(physical_device).filter(
+                |queue_family_index| {
+                    physical_device
+                        .surface_support(*queue_family_index, window)
+                        .unwrap_or(false)
+                },
+            )
+        });
+
+        log::debug!("Queue family index: {}", queue_family_index);
+        (physical_device, queue_family_index)
     }
 
     /// Finds the best suited `QueueFamily` of a given device or none if no suitable family was found.
-    fn find_best_suited_queue_family(physical_device: Arc<PhysicalDevice>) -> u32 {
-        log::debug!("GraphicalEngine::find_best_suited_queue_family");
-
-        let queue_family_index = physical_device
+    fn find_best_suited_queue_family(physical_device: &PhysicalDevice) -> Option<u32> {
+        physical_device
             .queue_family_properties()
             .iter()
-            .enumerate()
-            .position(|(_, q)| q.queue_flags.contains(QueueFlags::GRAPHICS)) // Find a compute capable queue family
-            .expect("no suitable queue family found") as u32;
-
-        log::debug!("Queue family index: {}", queue_family_index);
-        queue_family_index
+            .position(|q| q.queue_flags.contains(QueueFlags::GRAPHICS)) // Find a graphics capable queue family
+            .map(|index| index as u32)
     }
 
     /// Creates a `LogicalDevice` given a `PhysicalDevice` and a `QueueFamilyIndex`.
@@ -179,109 +261,61 @@ impl GraphicalEngine {
     ) -> Arc<LogicalDevice> {
         log::debug!("GraphicalEngine::create_logical_device");
 
+        let enabled_features = LogicalDevice::retrieve_optional_features(&physical_device);
+        let enabled_extensions = LogicalDevice::retrieve_optional_extensions(&physical_device);
+
+        let queue_create_infos =
+            LogicalDevice::get_queue_create_infos(&physical_device, queue_family_index);
         let (device, raw_queues) = Device::new(
             physical_device,
             DeviceCreateInfo {
-                queue_create_infos: vec![QueueCreateInfo {
-                    queue_family_index,
-                    ..Default::default()
-                }],
-                enabled_extensions: GraphicalEngine::retrieve_required_device_extensions(),
+                queue_create_infos,
+                enabled_extensions: GraphicalEngine::retrieve_required_device_extensions()
+                    .union(&enabled_extensions),
+                enabled_features,
                 ..Default::default()
             },
         )
         .expect("failed to create logical device");
 
-        let queues: Vec<Arc<Queue>> = raw_queues.collect();
+        let mut queues: Vec<Arc<Queue>> = raw_queues.collect();
+        // The additional compute queue is created last
+        let compute_queue = if queues.len() > 1 { queues.pop() } else { None };
 
-        let logical_device = LogicalDevice::new(device, queue_family_index, queues);
+        let logical_device = LogicalDevice::new(device, queue_family_index, queues)
+            .with_compute_queue(compute_queue);
         Arc::new(logical_device)
     }
 
-    /// Creates a `Swapchain` given a `LogicalDevice` and a `Window`.
-    /// Vulkan uses `Swapchain`s to store images while they are still ready and swaps them out once a new image is ready to be displayed (i.e. finished rendering).
-    fn create_swap_chain(
-        logical_device: Arc<LogicalDevice>,
-        window: Arc<Surface>,
-    ) -> (Arc<Swapchain>, Vec<Arc<SwapchainImage>>) {
-        // Get surface capabilities
-        let capabilities = logical_device
-            .get_physical_device()
-            .surface_capabilities(&window, Default::default())
-            .expect("failed to get surface capabilities");
-
-        // Get alphas
-        let composite_alpha = capabilities
-            .supported_composite_alpha
-            .into_iter()
-            .next()
-            .expect("no composite alpha found!");
-
-        // Get image format
-        let image_format = Some(
-            logical_device
-                .get_physical_device()
-                .surface_formats(&window, Default::default())
-                .unwrap()[0]
-                .0,
-        );
-
-        // Create Swap Chain
-        Swapchain::new(
-            logical_device.get_device(),
-            window,
-            SwapchainCreateInfo {
-                // How many buffers (images) are in the swap chain
-                min_image_count: capabilities.min_image_count + 1,
-                // Format of the images
-                image_format,
-                // Dimensions of the images
-                image_extent: [0, 0],
-                // Usage of the images
-                image_usage: ImageUsage::COLOR_ATTACHMENT,
-                // Alpha of the images
-                composite_alpha,
-                ..Default::default()
-            },
-        )
-        .unwrap()
-    }
-
-    /// Recreates the `SwapChain` and `SwapChainImages`, while also rebuilding the `Framebuffer`s given a `RenderPass` is submitted.
+    /// Recreates the `SwapChain` and `SwapChainImages` of the primary window, while also rebuilding the `Framebuffer`s given a `RenderPass` is submitted.
     ///
     /// Can return `None` on `SwapchainCreationError::ImageExtentNotSupported` which **should be ignored**.
     pub fn recreate_swap_chain_and_images(
         &mut self,
         render_pass: Arc<RenderPass>,
     ) -> Option<Vec<Arc<Framebuffer>>> {
-        log::debug!("GraphicalEngine::recreate_swap_chain");
-
-        let (new_swapchain, new_images) =
-            match self.get_swap_chain().recreate(SwapchainCreateInfo {
-                image_extent: [0, 0],
-                ..self.get_swap_chain().create_info()
-            }) {
-                Ok(r) => r,
-                Err(SwapchainCreationError::ImageExtentNotSupported { .. }) => return None,
-                Err(e) => panic!("Failed to recreate swapchain: {:?}", e),
-            };
-
-        self.swap_chain = new_swapchain;
-        self.swap_chain_images = new_images;
-        Some(self.create_frame_buffers(render_pass))
+        self.get_primary_window_mut()
+            .recreate_swap_chain_and_images(render_pass)
     }
 
     /// Creates a `RenderPass`.
     /// A `RenderPass` is a collection of `Attachment`s and `Subpass`es.
     /// It defines how an image on the `Swapchain` is being used and how it is being rendered.
     pub fn create_render_pass(&self) -> Arc<RenderPass> {
+        // Must be same as swap chain
+        self.create_render_pass_with_format(self.get_swap_chain().image_format())
+    }
+
+    /// Creates a `RenderPass` like `create_render_pass`, but with a custom color attachment `Format`.
+    /// Use this to render into offscreen images instead of the `Swapchain`.
+    pub fn create_render_pass_with_format(&self, format: Format) -> Arc<RenderPass> {
         vulkano::single_pass_renderpass!(
             self.logical_device.get_device(),
             attachments: {
                 color: {
                     load: Clear,
                     store: Store,
-                    format: self.swap_chain.image_format(), // Must be same as swap chain
+                    format: format,
                     samples: 1,
                 }
             },
@@ -293,70 +327,187 @@ impl GraphicalEngine {
         .unwrap()
     }
 
-    /// Creates a `Framebuffer` from a given `RenderPass` and `SwapChainImages`.
-    /// A `Framebuffer` wraps around `SwapchainImage`'s and creates `ImageView`s from them given the correct format from the given `RenderPass`.
+    /// Creates the `Framebuffer`s of the primary window from a given `RenderPass`, see `EngineWindow::create_frame_buffers`.
     pub fn create_frame_buffers(&self, render_pass: Arc<RenderPass>) -> Vec<Arc<Framebuffer>> {
-        let buffers = self
-            .swap_chain_images
+        self.get_primary_window().create_frame_buffers(render_pass)
+    }
+
+    /// Records a capture of the `SwapchainImage` with the given index into the command buffer rendering the frame.
+    /// Must be recorded after rendering, so the capture contains the frame that is presented next.
+    /// The pixels can be read from the returned `FrameCapture` once the frame finished executing.
+    #[track_caller]
+    pub fn record_frame_capture(
+        &self,
+        builder: &mut EngineCommandBufferBuilder,
+        memory_allocator: &StandardMemoryAllocator,
+        image_index: u32,
+    ) -> FrameCapture {
+        assert!(
+            self.get_swap_chain()
+                .image_usage()
+                .intersects(ImageUsage::TRANSFER_SRC),
+            "the surface doesn't support capturing swapchain images"
+        );
+
+        FrameCapture::record(
+            builder,
+            memory_allocator,
+            self.get_swap_chain_images()[image_index as usize].clone(),
+        )
+    }
+
+    /// Enables or disables capturing the images the primary window presents, see `EngineWindow::set_frame_capture`.
+    /// Panics if the surface doesn't support capturing swapchain images.
+    #[track_caller]
+    pub fn set_frame_capture(&mut self, enabled: bool) {
+        self.get_primary_window_mut().set_frame_capture(enabled);
+    }
+
+    /// Returns the last image presented by the primary window.
+    /// `None` if frame capture isn't enabled or no image was presented since, see `set_frame_capture`.
+    /// As `EngineWindow::present` waits for the GPU, this doesn't stall.
+    pub fn capture_frame(&self) -> Option<RgbaImage> {
+        self.get_primary_window()
+            .get_last_frame_capture()
+            .map(FrameCapture::to_rgba_image)
+    }
+
+    /// Creates another window presenting with the same `LogicalDevice` and allocators.
+    /// Panics if the device can't present to it.
+    pub fn add_window<T>(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<T>,
+        config: &WindowConfig,
+    ) -> WindowId {
+        log::debug!("GraphicalEngine::add_window");
+
+        let surface = config.build_surface(self.instance.clone(), event_loop);
+        let window = EngineWindow::new(self.logical_device.clone(), surface, config);
+        let id = window.get_id().expect("window was created from winit");
+
+        self.windows.push(window);
+        id
+    }
+
+    /// Closes a window added with `add_window`, the primary window can't be removed.
+    /// Returns whether the window was found.
+    pub fn remove_window(&mut self, id: WindowId) -> bool {
+        log::debug!("GraphicalEngine::remove_window");
+
+        assert_ne!(
+            self.get_primary_window().get_id(),
+            Some(id),
+            "the primary window can't be removed"
+        );
+
+        let count = self.windows.len();
+        self.windows.retain(|window| window.get_id() != Some(id));
+        self.windows.len() != count
+    }
+
+    /// Returns the window with the given id.
+    pub fn get_engine_window(&self, id: WindowId) -> Option<&EngineWindow> {
+        self.windows
             .iter()
-            .map(|image| {
-                let view = ImageView::new_default(image.clone()).unwrap();
-                Framebuffer::new(
-                    render_pass.clone(),
-                    FramebufferCreateInfo {
-                        attachments: vec![view],
-                        ..Default::default()
-                    },
-                )
-                .unwrap()
-            })
-            .collect::<Vec<Arc<Framebuffer>>>();
-
-        buffers
+            .find(|window| window.get_id() == Some(id))
+    }
+
+    /// Returns the window with the given id.
+    pub fn get_engine_window_mut(&mut self, id: WindowId) -> Option<&mut EngineWindow> {
+        self.windows
+            .iter_mut()
+            .find(|window| window.get_id() == Some(id))
+    }
+
+    /// Returns all windows, the primary window first.
+    pub fn get_engine_windows(&self) -> &[EngineWindow] {
+        &self.windows
+    }
+
+    /// Returns all windows, the primary window first.
+    pub fn get_engine_windows_mut(&mut self) -> &mut [EngineWindow] {
+        &mut self.windows
+    }
+
+    /// Returns the window the engine was created with.
+    pub fn get_primary_window(&self) -> &EngineWindow {
+        &self.windows[0]
+    }
+
+    /// Returns the window the engine was created with.
+    pub fn get_primary_window_mut(&mut self) -> &mut EngineWindow {
+        &mut self.windows[0]
+    }
+
+    /// Forwards a `WindowEvent` to the window it belongs to, see `EngineWindow::on_window_event`.
+    pub fn on_window_event(&mut self, id: WindowId, event: &WindowEvent) {
+        if let Some(window) = self.get_engine_window_mut(id) {
+            window.on_window_event(event);
+        }
+    }
+
+    /// Whether the primary window's `Swapchain` has to be recreated.
+    pub fn is_swap_chain_outdated(&self) -> bool {
+        self.get_primary_window().is_swap_chain_outdated()
+    }
+
+    /// Switches the primary window between windowed and fullscreen.
+    pub fn set_fullscreen(&mut self, fullscreen: WindowFullscreen) {
+        self.get_primary_window_mut().set_fullscreen(fullscreen);
+    }
+
+    /// Toggles the primary window between windowed and fullscreen.
+    pub fn toggle_fullscreen(&mut self) {
+        self.get_primary_window_mut().toggle_fullscreen();
+    }
+
+    /// Returns the fullscreen mode of the primary window.
+    pub fn get_fullscreen(&self) -> WindowFullscreen {
+        self.get_primary_window().get_fullscreen()
+    }
+
+    /// Changes vsync of the primary window, taking effect once the `Swapchain` is recreated.
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.get_primary_window_mut().set_vsync(vsync);
+    }
+
+    /// Whether vsync is requested for the primary window.
+    pub fn get_vsync(&self) -> bool {
+        self.get_primary_window().get_vsync()
+    }
+
+    /// Grabs and shows or hides the cursor in the primary window.
+    pub fn set_cursor(&self, grab: CursorGrabMode, visible: bool) {
+        self.get_primary_window().set_cursor(grab, visible);
+    }
+
+    /// Returns the DPI scale factor of the primary window.
+    pub fn get_scale_factor(&self) -> f64 {
+        self.get_primary_window().get_scale_factor()
     }
 
     /// Returns the `EngineWindow`
     pub fn get_window(&self) -> Arc<Surface> {
-        self.window.clone()
+        self.get_primary_window().get_surface()
+    }
+
+    /// Returns the winit `Window` of the primary window.
+    pub fn get_winit_window(&self) -> Arc<Window> {
+        self.get_primary_window().get_winit_window()
     }
 
     /// Returns the `Swapchain`
     pub fn get_swap_chain(&self) -> Arc<Swapchain> {
-        self.swap_chain.clone()
+        self.get_primary_window().get_swap_chain()
     }
 
     /// Returns the `SwapchainImage`s
     pub fn get_swap_chain_images(&self) -> Vec<Arc<SwapchainImage>> {
-        self.swap_chain_images.clone()
+        self.get_primary_window().get_swap_chain_images()
     }
 }
 
 impl AbstractEngine<StandardCommandBufferAllocator> for GraphicalEngine {
-    /// Runs compute operations on the `GraphicalEngine`.
-    fn compute(&self, operation: &dyn (Fn(&Self) -> PrimaryAutoCommandBuffer)) {
-        let command_buffer = operation(self);
-
-        #[cfg(debug_assertions)]
-        let start_fence = Instant::now();
-
-        command_buffer
-            .execute(self.get_logical_device().get_first_queue())
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap()
-            .wait(None)
-            .unwrap();
-
-        #[cfg(debug_assertions)]
-        {
-            let end_fence = Instant::now();
-            log::debug!(
-                "Compute operation took: {}ms",
-                end_fence.duration_since(start_fence).as_millis()
-            );
-        }
-    }
-
     /// Returns the `Instance` Arc.
     fn get_instance(&self) -> Arc<Instance> {
         self.instance.clone()
@@ -0,0 +1,612 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs, io,
+    path::Path,
+};
+
+use winit::event::{
+    DeviceEvent, ElementState, Event, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
+
+/// Pixels treated as one line when converting pixel based scroll deltas (touchpads) into lines.
+pub const INPUT_PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+macro_rules! key_codes {
+    ($($key:ident),* $(,)?) => {
+        fn parse_key_code(name: &str) -> Option<VirtualKeyCode> {
+            match name {
+                $(stringify!($key) => Some(VirtualKeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+#[rustfmt::skip]
+key_codes! {
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, A, B, C, D, E, F, G, H, I, J, K, L,
+    M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11,
+    F12, F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24, Snapshot, Scroll, Pause,
+    Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down, Back, Return, Space,
+    Compose, Caret, Numlock, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7,
+    Numpad8, Numpad9, NumpadAdd, NumpadDivide, NumpadDecimal, NumpadComma, NumpadEnter,
+    NumpadEquals, NumpadMultiply, NumpadSubtract, AbntC1, AbntC2, Apostrophe, Apps, Asterisk, At,
+    Ax, Backslash, Calculator, Capital, Colon, Comma, Convert, Equals, Grave, Kana, Kanji, LAlt,
+    LBracket, LControl, LShift, LWin, Mail, MediaSelect, MediaStop, Minus, Mute, MyComputer,
+    NavigateForward, NavigateBackward, NextTrack, NoConvert, OEM102, Period, PlayPause, Plus, Power,
+    PrevTrack, RAlt, RBracket, RControl, RShift, RWin, Semicolon, Slash, Sleep, Stop, Sysrq, Tab,
+    Underline, Unlabeled, VolumeDown, VolumeUp, Wake, WebBack, WebFavorites, WebForward, WebHome,
+    WebRefresh, WebSearch, WebStop, Yen, Copy, Paste, Cut,
+}
+
+/// A digital input an action or axis can be bound to.
+///
+/// In action map files keys use their `VirtualKeyCode` name (e.g. `W`, `Space`, `LShift`),
+/// mouse buttons are written as `MouseLeft`, `MouseRight`, `MouseMiddle` or `Mouse<n>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputBinding {
+    Key(VirtualKeyCode),
+    MouseButton(MouseButton),
+}
+
+impl InputBinding {
+    /// Parses a binding from its action map file name.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "MouseLeft" => Some(Self::MouseButton(MouseButton::Left)),
+            "MouseRight" => Some(Self::MouseButton(MouseButton::Right)),
+            "MouseMiddle" => Some(Self::MouseButton(MouseButton::Middle)),
+            _ => match name.strip_prefix("Mouse").map(str::parse::<u16>) {
+                Some(Ok(button)) => Some(Self::MouseButton(MouseButton::Other(button))),
+                _ => parse_key_code(name).map(Self::Key),
+            },
+        }
+    }
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{:?}", key),
+            Self::MouseButton(MouseButton::Left) => write!(f, "MouseLeft"),
+            Self::MouseButton(MouseButton::Right) => write!(f, "MouseRight"),
+            Self::MouseButton(MouseButton::Middle) => write!(f, "MouseMiddle"),
+            Self::MouseButton(MouseButton::Other(button)) => write!(f, "Mouse{}", button),
+        }
+    }
+}
+
+/// Where the value of an `InputAxis` comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputAxisSource {
+    /// `-1` while a negative binding is held, `1` while a positive one is held, `0` for both or none.
+    Buttons {
+        negative: Vec<InputBinding>,
+        positive: Vec<InputBinding>,
+    },
+    /// Mouse motion of this frame, in device units.
+    MouseX,
+    MouseY,
+    /// Scroll of this frame, in lines.
+    ScrollX,
+    ScrollY,
+}
+
+/// An analog value read once per frame, e.g. movement or camera look.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputAxis {
+    pub source: InputAxisSource,
+    pub scale: f32,
+}
+
+impl InputAxis {
+    /// Creates an axis from negative and positive bindings, with a scale of `1`.
+    pub fn buttons(negative: Vec<InputBinding>, positive: Vec<InputBinding>) -> Self {
+        Self {
+            source: InputAxisSource::Buttons { negative, positive },
+            scale: 1.0,
+        }
+    }
+
+    /// Creates an axis with a scale its value is multiplied with.
+    pub fn new(source: InputAxisSource, scale: f32) -> Self {
+        Self { source, scale }
+    }
+}
+
+/// Error while loading or parsing an `ActionMap`.
+#[derive(Debug)]
+pub enum ActionMapError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read action map: {}", error),
+            Self::Parse { line, message } => {
+                write!(f, "invalid action map on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionMapError {}
+
+impl From<io::Error> for ActionMapError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Maps named actions and axes to concrete inputs.
+///
+/// The file format has one mapping per line, `#` starts a comment:
+///
+/// ```text
+/// # Actions: any of the bindings triggers the action
+/// move_forward = W, Up
+/// fire = MouseLeft
+///
+/// # Axes: negative bindings | positive bindings, or an analog source, optionally scaled
+/// # Either side of a buttons axis may be empty
+/// move_x = A, Left | D, Right
+/// jump_axis = | Space
+/// look_x = MouseX * 0.002
+/// zoom = ScrollY
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActionMap {
+    actions: HashMap<String, Vec<InputBinding>>,
+    axes: HashMap<String, InputAxis>,
+}
+
+impl ActionMap {
+    /// Creates an empty action map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads an action map from a file, see `ActionMap` for the format.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ActionMapError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Saves the action map in the format read by `ActionMap::load`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Parses an action map, see `ActionMap` for the format.
+    pub fn parse(source: &str) -> Result<Self, ActionMapError> {
+        let mut action_map = Self::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let error = |message: String| ActionMapError::Parse {
+                line: index + 1,
+                message,
+            };
+            let parse_bindings = |list: &str| {
+                if list.trim().is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                list.split(',')
+                    .map(str::trim)
+                    .map(|name| {
+                        InputBinding::parse(name)
+                            .ok_or_else(|| error(format!("unknown input '{}'", name)))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            };
+
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected 'name = inputs'".to_string()))?;
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(error("missing name".to_string()));
+            }
+
+            let (value, scale) = match value.split_once('*') {
+                Some((value, scale)) => (
+                    value,
+                    Some(
+                        scale
+                            .trim()
+                            .parse::<f32>()
+                            .map_err(|_| error(format!("invalid scale '{}'", scale.trim())))?,
+                    ),
+                ),
+                None => (value, None),
+            };
+            let value = value.trim();
+
+            let source = match value {
+                "MouseX" => Some(InputAxisSource::MouseX),
+                "MouseY" => Some(InputAxisSource::MouseY),
+                "ScrollX" => Some(InputAxisSource::ScrollX),
+                "ScrollY" => Some(InputAxisSource::ScrollY),
+                _ => match value.split_once('|') {
+                    Some((negative, positive)) => Some(InputAxisSource::Buttons {
+                        negative: parse_bindings(negative)?,
+                        positive: parse_bindings(positive)?,
+                    }),
+                    None => None,
+                },
+            };
+
+            match source {
+                Some(source) => {
+                    action_map.bind_axis(name, InputAxis::new(source, scale.unwrap_or(1.0)))
+                }
+                None if scale.is_some() => {
+                    return Err(error(format!("action '{}' can't be scaled", name)))
+                }
+                None if value.is_empty() => {
+                    return Err(error(format!("action '{}' has no inputs", name)))
+                }
+                None => {
+                    for binding in parse_bindings(value)? {
+                        action_map.bind_action(name, binding);
+                    }
+                }
+            }
+        }
+
+        Ok(action_map)
+    }
+
+    /// Adds a binding to an action, creating the action if needed.
+    pub fn bind_action(&mut self, name: &str, binding: InputBinding) {
+        let bindings = self.actions.entry(name.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Sets an axis, replacing any previous axis with the same name.
+    pub fn bind_axis(&mut self, name: &str, axis: InputAxis) {
+        self.axes.insert(name.to_string(), axis);
+    }
+
+    /// Removes the action and the axis with the given name.
+    pub fn unbind(&mut self, name: &str) {
+        self.actions.remove(name);
+        self.axes.remove(name);
+    }
+
+    /// Returns the bindings of an action, `None` for unknown actions.
+    pub fn get_action(&self, name: &str) -> Option<&[InputBinding]> {
+        self.actions.get(name).map(Vec::as_slice)
+    }
+
+    /// Returns an axis, `None` for unknown axes.
+    pub fn get_axis(&self, name: &str) -> Option<&InputAxis> {
+        self.axes.get(name)
+    }
+}
+
+impl fmt::Display for ActionMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |bindings: &[InputBinding]| {
+            bindings
+                .iter()
+                .map(InputBinding::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut actions = self.actions.iter().collect::<Vec<_>>();
+        actions.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, bindings) in actions {
+            writeln!(f, "{} = {}", name, join(bindings))?;
+        }
+
+        let mut axes = self.axes.iter().collect::<Vec<_>>();
+        axes.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, axis) in axes {
+            let source = match &axis.source {
+                InputAxisSource::Buttons { negative, positive } => {
+                    format!("{} | {}", join(negative), join(positive))
+                        .trim()
+                        .to_string()
+                }
+                source => format!("{:?}", source),
+            };
+            if axis.scale == 1.0 {
+                writeln!(f, "{} = {}", name, source)?;
+            } else {
+                writeln!(f, "{} = {} * {}", name, source, axis.scale)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Per frame input state of keyboard, mouse and the `ActionMap` on top of it.
+///
+/// Feed every winit event into `Input::on_event` and call `Input::end_frame` once the
+/// frame is done, so pressed/released states and deltas only last for a single frame.
+#[derive(Debug, Default)]
+pub struct Input {
+    action_map: ActionMap,
+    held: HashSet<InputBinding>,
+    pressed: HashSet<InputBinding>,
+    released: HashSet<InputBinding>,
+    cursor_position: Option<[f32; 2]>,
+    mouse_delta: [f32; 2],
+    scroll_delta: [f32; 2],
+}
+
+impl Input {
+    /// Creates an `Input` with nothing held, resolving actions and axes with the given `ActionMap`.
+    pub fn new(action_map: ActionMap) -> Self {
+        log::debug!("Input::new");
+
+        Self {
+            action_map,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the `ActionMap` actions and axes are resolved with.
+    pub fn get_action_map(&self) -> &ActionMap {
+        &self.action_map
+    }
+
+    /// Replaces the `ActionMap`, e.g. after the user rebound inputs.
+    pub fn set_action_map(&mut self, action_map: ActionMap) {
+        self.action_map = action_map;
+    }
+
+    /// Updates the state from a winit event, other events than window and device events are ignored.
+    pub fn on_event<T>(&mut self, event: &Event<T>) {
+        match event {
+            Event::WindowEvent { event, .. } => self.on_window_event(event),
+            Event::DeviceEvent { event, .. } => self.on_device_event(event),
+            _ => (),
+        }
+    }
+
+    /// Updates the state of keys, mouse buttons, the cursor and scrolling from a winit window event.
+    pub fn on_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => {
+                if let Some(key) = input.virtual_keycode {
+                    self.set_state(InputBinding::Key(key), input.state);
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.set_state(InputBinding::MouseButton(*button), *state);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = Some([position.x as f32, position.y as f32]);
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor_position = None;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => [*x, *y],
+                    MouseScrollDelta::PixelDelta(position) => [
+                        position.x as f32 / INPUT_PIXELS_PER_SCROLL_LINE,
+                        position.y as f32 / INPUT_PIXELS_PER_SCROLL_LINE,
+                    ],
+                };
+                self.scroll_delta[0] += delta[0];
+                self.scroll_delta[1] += delta[1];
+            }
+            // Releases would never arrive while unfocused, don't keep keys stuck
+            WindowEvent::Focused(false) => {
+                self.released.extend(self.held.drain());
+            }
+            _ => (),
+        }
+    }
+
+    /// Raw mouse motion, which keeps arriving while the cursor is grabbed.
+    pub fn on_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            self.mouse_delta[0] += delta.0 as f32;
+            self.mouse_delta[1] += delta.1 as f32;
+        }
+    }
+
+    /// Clears per frame state, call after the frame consumed the input.
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.mouse_delta = [0.0, 0.0];
+        self.scroll_delta = [0.0, 0.0];
+    }
+
+    fn set_state(&mut self, binding: InputBinding, state: ElementState) {
+        match state {
+            ElementState::Pressed => {
+                // Key repeats are not new presses
+                if self.held.insert(binding) {
+                    self.pressed.insert(binding);
+                }
+            }
+            ElementState::Released => {
+                if self.held.remove(&binding) {
+                    self.released.insert(binding);
+                }
+            }
+        }
+    }
+
+    /// Whether the binding went down this frame.
+    pub fn is_pressed(&self, binding: InputBinding) -> bool {
+        self.pressed.contains(&binding)
+    }
+
+    /// Whether the binding is currently down.
+    pub fn is_held(&self, binding: InputBinding) -> bool {
+        self.held.contains(&binding)
+    }
+
+    /// Whether the binding went up this frame.
+    pub fn is_released(&self, binding: InputBinding) -> bool {
+        self.released.contains(&binding)
+    }
+
+    /// Whether the key went down this frame.
+    pub fn is_key_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.is_pressed(InputBinding::Key(key))
+    }
+
+    /// Whether the key is currently down.
+    pub fn is_key_held(&self, key: VirtualKeyCode) -> bool {
+        self.is_held(InputBinding::Key(key))
+    }
+
+    /// Whether the key went up this frame.
+    pub fn is_key_released(&self, key: VirtualKeyCode) -> bool {
+        self.is_released(InputBinding::Key(key))
+    }
+
+    /// Whether the mouse button went down this frame.
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.is_pressed(InputBinding::MouseButton(button))
+    }
+
+    /// Whether the mouse button is currently down.
+    pub fn is_mouse_button_held(&self, button: MouseButton) -> bool {
+        self.is_held(InputBinding::MouseButton(button))
+    }
+
+    /// Whether the mouse button went up this frame.
+    pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.is_released(InputBinding::MouseButton(button))
+    }
+
+    /// Cursor position in physical pixels, `None` while outside the window.
+    pub fn get_cursor_position(&self) -> Option<[f32; 2]> {
+        self.cursor_position
+    }
+
+    /// Raw mouse motion of this frame, in device units.
+    pub fn get_mouse_delta(&self) -> [f32; 2] {
+        self.mouse_delta
+    }
+
+    /// Scroll of this frame, in lines.
+    pub fn get_scroll_delta(&self) -> [f32; 2] {
+        self.scroll_delta
+    }
+
+    fn any_binding(&self, action: &str, set: &HashSet<InputBinding>) -> bool {
+        self.action_map
+            .get_action(action)
+            .is_some_and(|bindings| bindings.iter().any(|binding| set.contains(binding)))
+    }
+
+    /// Whether any binding of the action went down this frame. Unknown actions are never pressed.
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        self.any_binding(action, &self.pressed)
+    }
+
+    /// Whether any binding of the action is currently down. Unknown actions are never held.
+    pub fn is_action_held(&self, action: &str) -> bool {
+        self.any_binding(action, &self.held)
+    }
+
+    /// Whether any binding of the action went up this frame. Unknown actions are never released.
+    pub fn is_action_released(&self, action: &str) -> bool {
+        self.any_binding(action, &self.released)
+    }
+
+    /// Current value of an axis, `0` for unknown axes.
+    pub fn get_axis(&self, axis: &str) -> f32 {
+        let Some(axis) = self.action_map.get_axis(axis) else {
+            return 0.0;
+        };
+
+        let value = match &axis.source {
+            InputAxisSource::Buttons { negative, positive } => {
+                let held = |bindings: &[InputBinding]| bindings.iter().any(|b| self.is_held(*b));
+                (held(positive) as i32 - held(negative) as i32) as f32
+            }
+            InputAxisSource::MouseX => self.mouse_delta[0],
+            InputAxisSource::MouseY => self.mouse_delta[1],
+            InputAxisSource::ScrollX => self.scroll_delta[0],
+            InputAxisSource::ScrollY => self.scroll_delta[1],
+        };
+
+        value * axis.scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_map_round_trips() {
+        let source = "
+            # Comment
+            fire = MouseLeft, Mouse4
+            move_forward = W, Up # trailing comment
+            jump_axis = | Space
+            move_x = A, Left | D, Right
+            look_x = MouseX * 0.002
+            brake_axis = S |
+            zoom = ScrollY
+        ";
+
+        let action_map = ActionMap::parse(source).unwrap();
+        assert_eq!(
+            action_map.get_action("fire"),
+            Some(
+                &[
+                    InputBinding::MouseButton(MouseButton::Left),
+                    InputBinding::MouseButton(MouseButton::Other(4))
+                ][..]
+            )
+        );
+        assert_eq!(
+            action_map.get_axis("jump_axis"),
+            Some(&InputAxis::buttons(
+                Vec::new(),
+                vec![InputBinding::Key(VirtualKeyCode::Space)]
+            ))
+        );
+        assert_eq!(
+            action_map.get_axis("look_x"),
+            Some(&InputAxis::new(InputAxisSource::MouseX, 0.002))
+        );
+
+        let serialized = action_map.to_string();
+        assert_eq!(ActionMap::parse(&serialized).unwrap(), action_map);
+    }
+
+    #[test]
+    fn action_map_reports_invalid_lines() {
+        for (source, line) in [
+            ("fire = MouseLeft\nmove_x = A | Foo", 2),
+            ("fire", 1),
+            ("fire = ", 1),
+            ("\n = W", 2),
+            ("fire = W * 2", 1),
+            ("look_x = MouseX * fast", 1),
+        ] {
+            match ActionMap::parse(source) {
+                Err(ActionMapError::Parse {
+                    line: error_line, ..
+                }) => {
+                    assert_eq!(error_line, line, "{:?}", source)
+                }
+                result => panic!("{:?} parsed as {:?}", source, result),
+            }
+        }
+    }
+}
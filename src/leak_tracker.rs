@@ -0,0 +1,229 @@
+use std::{
+    any::Any,
+    panic::Location,
+    sync::{Arc, Mutex, Weak},
+};
+
+use vulkano::{
+    buffer::Buffer,
+    device::{Device, DeviceOwned},
+    image::sys::Image,
+};
+
+/// The kind of a resource registered with `track_resource`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    Buffer,
+    Image,
+    Pipeline,
+    DescriptorSet,
+}
+
+/// A tracked resource which is still alive.
+#[derive(Clone, Debug)]
+pub struct LiveResource {
+    pub kind: ResourceKind,
+    /// The debug name given with `LogicalDevice::set_debug_name`, if any.
+    pub name: Option<String>,
+    /// Where the resource was created.
+    pub location: &'static Location<'static>,
+}
+
+impl std::fmt::Display for LiveResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.kind)?;
+        if let Some(name) = &self.name {
+            write!(f, " '{}'", name)?;
+        }
+        write!(f, " created at {}", self.location)
+    }
+}
+
+struct TrackedResource {
+    kind: ResourceKind,
+    name: Option<String>,
+    location: &'static Location<'static>,
+    /// Address of the device, only compared while the resource (which keeps the device alive) is alive.
+    device: usize,
+    resource: Weak<dyn Any + Send + Sync>,
+}
+
+struct LeakTracker {
+    resources: Vec<TrackedResource>,
+    /// Amount of resources after the last cleanup, dead ones are only removed once it doubled.
+    live_count: usize,
+}
+
+static LEAK_TRACKER: Mutex<LeakTracker> = Mutex::new(LeakTracker {
+    resources: Vec::new(),
+    live_count: 0,
+});
+
+impl LeakTracker {
+    fn remove_dead(&mut self) {
+        self.resources
+            .retain(|tracked| tracked.resource.strong_count() > 0);
+        self.live_count = self.resources.len();
+    }
+}
+
+/// Registers a resource created through the engine, together with the location of the caller.
+/// The resource is only weakly referenced.
+/// Buffers (`Arc<Buffer>`) and images (`Arc<Image>`) are also counted by `LogicalDevice::get_memory_report`.
+#[track_caller]
+pub fn track_resource<T>(kind: ResourceKind, resource: &Arc<T>)
+where
+    T: DeviceOwned + Send + Sync + 'static,
+{
+    track(kind, resource, false);
+}
+
+/// Registers a resource like `track_resource`, unless it's already tracked.
+/// Used for buffers shared by many allocations, e.g. the arenas of a `SubbufferAllocator`.
+#[track_caller]
+pub(crate) fn track_resource_once<T>(kind: ResourceKind, resource: &Arc<T>)
+where
+    T: DeviceOwned + Send + Sync + 'static,
+{
+    track(kind, resource, true);
+}
+
+#[track_caller]
+fn track<T>(kind: ResourceKind, resource: &Arc<T>, once: bool)
+where
+    T: DeviceOwned + Send + Sync + 'static,
+{
+    let device = Arc::as_ptr(resource.device()) as usize;
+    let address = Arc::as_ptr(resource) as *const ();
+    let resource: Arc<dyn Any + Send + Sync> = resource.clone();
+
+    let mut tracker = LEAK_TRACKER.lock().unwrap();
+    if once
+        && tracker.resources.iter().any(|tracked| {
+            tracked.resource.strong_count() > 0 && tracked.resource.as_ptr() as *const () == address
+        })
+    {
+        return;
+    }
+    if tracker.resources.len() >= (tracker.live_count * 2).max(64) {
+        tracker.remove_dead();
+    }
+    tracker.resources.push(TrackedResource {
+        kind,
+        name: None,
+        location: Location::caller(),
+        device,
+        resource: Arc::downgrade(&resource),
+    });
+}
+
+/// Names the tracked resource at the given address, called by `set_debug_name`.
+pub(crate) fn name_tracked_resource(address: *const (), name: &str) {
+    let mut tracker = LEAK_TRACKER.lock().unwrap();
+    if let Some(tracked) = tracker.resources.iter_mut().find(|tracked| {
+        tracked.resource.strong_count() > 0 && tracked.resource.as_ptr() as *const () == address
+    }) {
+        tracked.name = Some(name.to_string());
+    }
+}
+
+/// Returns the tracked buffers and images of a device which are still alive, for the memory report.
+pub(crate) fn get_tracked_memory_resources(
+    device: &Arc<Device>,
+) -> (Vec<Arc<Buffer>>, Vec<Arc<Image>>) {
+    let tracker = LEAK_TRACKER.lock().unwrap();
+    let mut buffers = Vec::new();
+    let mut images = Vec::new();
+
+    for tracked in &tracker.resources {
+        // Upgraded first, as the device address is only valid while the resource is alive
+        let Some(resource) = tracked.resource.upgrade() else {
+            continue;
+        };
+        if tracked.device != Arc::as_ptr(device) as usize {
+            continue;
+        }
+
+        match tracked.kind {
+            ResourceKind::Buffer => buffers.extend(resource.downcast::<Buffer>().ok()),
+            ResourceKind::Image => images.extend(resource.downcast::<Image>().ok()),
+            _ => (),
+        }
+    }
+
+    // A resource can be registered more than once, e.g. by the engine and by the application
+    buffers.sort_by_key(Arc::as_ptr);
+    buffers.dedup_by_key(|buffer| Arc::as_ptr(buffer));
+    images.sort_by_key(Arc::as_ptr);
+    images.dedup_by_key(|image| Arc::as_ptr(image));
+
+    (buffers, images)
+}
+
+/// Returns all tracked resources which are still alive, oldest first.
+pub fn get_live_resources() -> Vec<LiveResource> {
+    get_live_resources_of(None)
+}
+
+fn get_live_resources_of(device: Option<&Arc<Device>>) -> Vec<LiveResource> {
+    let mut tracker = LEAK_TRACKER.lock().unwrap();
+    tracker.remove_dead();
+
+    tracker
+        .resources
+        .iter()
+        .filter(|tracked| {
+            device.is_none_or(|device| tracked.device == Arc::as_ptr(device) as usize)
+        })
+        .map(|tracked| LiveResource {
+            kind: tracked.kind,
+            name: tracked.name.clone(),
+            location: tracked.location,
+        })
+        .collect()
+}
+
+/// Prints out all tracked resources which are still alive.
+pub fn print_live_resources(log_level: log::Level) {
+    print_resources(get_live_resources(), "are still alive", log_level);
+}
+
+fn print_resources(resources: Vec<LiveResource>, state: &str, log_level: log::Level) {
+    if resources.is_empty() {
+        return;
+    }
+
+    log::log!(
+        log_level,
+        "{} tracked resources {}:",
+        resources.len(),
+        state
+    );
+    for resource in resources {
+        log::log!(log_level, "- {}", resource);
+    }
+}
+
+/// Held as the last field of an engine, so it's dropped after all resources the engine owns itself.
+/// Reports the tracked resources of the device which are still alive then, as they outlived the engine.
+pub(crate) struct LeakCheck {
+    device: Arc<Device>,
+}
+
+impl LeakCheck {
+    pub(crate) fn new(device: Arc<Device>) -> Self {
+        Self { device }
+    }
+}
+
+impl Drop for LeakCheck {
+    fn drop(&mut self) {
+        if cfg!(debug_assertions) {
+            print_resources(
+                get_live_resources_of(Some(&self.device)),
+                "outlived their engine",
+                log::Level::Warn,
+            );
+        }
+    }
+}
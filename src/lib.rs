@@ -1,14 +1,90 @@
+mod debug_utils;
+pub use debug_utils::*;
+
+mod leak_tracker;
+pub use leak_tracker::*;
+
+mod memory_report;
+pub use memory_report::*;
+
+mod device_report;
+pub use device_report::*;
+
+mod format_support;
+pub use format_support::*;
+
+mod device_selection;
+pub use device_selection::*;
+
 mod logical_device;
 pub use logical_device::*;
 
+mod engine_error;
+pub use engine_error::*;
+
+mod submission_batch;
+pub use submission_batch::*;
+
 mod abstract_engine;
 pub use abstract_engine::*;
 
 mod compute_engine;
 pub use compute_engine::*;
 
+mod compute_cluster;
+pub use compute_cluster::*;
+
+mod window_config;
+pub use window_config::*;
+
+mod engine_window;
+pub use engine_window::*;
+
 mod graphical_engine;
 pub use graphical_engine::*;
 
 mod s_vertex;
 pub use s_vertex::*;
+
+mod render_graph;
+pub use render_graph::*;
+
+mod post_processing;
+pub use post_processing::*;
+
+mod frame_capture;
+pub use frame_capture::*;
+
+mod frame_recorder;
+pub use frame_recorder::*;
+
+mod golden_image;
+pub use golden_image::*;
+
+mod sprite_batch;
+pub use sprite_batch::*;
+
+mod text;
+pub use text::*;
+
+mod debug_draw;
+pub use debug_draw::*;
+
+mod input;
+pub use input::*;
+
+mod game_loop;
+pub use game_loop::*;
+
+mod gpu_profiler;
+pub use gpu_profiler::*;
+
+mod gpu_queries;
+pub use gpu_queries::*;
+
+#[cfg(feature = "egui")]
+mod egui_renderer;
+#[cfg(feature = "egui")]
+pub use egui_renderer::*;
+#[cfg(feature = "egui")]
+pub use {egui, egui_winit};
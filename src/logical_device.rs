@@ -1,6 +1,31 @@
 use std::sync::Arc;
 
-use vulkano::device::{physical::PhysicalDevice, Device, Properties, Queue};
+use vulkano::{
+    buffer::Subbuffer,
+    device::{
+        physical::PhysicalDevice, Device, DeviceExtensions, DeviceOwned, Features, Properties,
+        Queue, QueueCreateInfo, QueueFlags,
+    },
+    format::{Format, FormatFeatures},
+    image::ImageAccess,
+    shader::ShaderModule,
+    VulkanObject,
+};
+
+use crate::{
+    collect_memory_types, find_supported_format, format_device_uuid, get_format_features,
+    get_tracked_memory_resources, query_memory_heaps, set_debug_name, supports_memory_properties2,
+    track_resource, FormatUsage, MemoryReport, ResourceCounts, ResourceKind,
+    DEPTH_FORMAT_PREFERENCES,
+};
+
+/// The stage a GLSL shader is compiled for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlslShaderKind {
+    Vertex,
+    Fragment,
+    Compute,
+}
 
 /// A LogicalDevice is a wrapper around a Vulkano Device and it's queues.
 #[derive(Clone, Debug)]
@@ -11,6 +36,8 @@ pub struct LogicalDevice {
     queue_family_index: u32,
     /// A Vector of all Queues in this family
     queues: Vec<Arc<Queue>>,
+    /// An additional compute queue, see `get_compute_queue`
+    compute_queue: Option<Arc<Queue>>,
 }
 
 impl LogicalDevice {
@@ -20,6 +47,76 @@ impl LogicalDevice {
             device,
             queue_family_index,
             queues,
+            compute_queue: None,
+        }
+    }
+
+    /// Sets the additional compute queue, which must not be one of the queues of the LogicalDevice.
+    pub fn with_compute_queue(mut self, compute_queue: Option<Arc<Queue>>) -> Self {
+        self.compute_queue = compute_queue;
+        self
+    }
+
+    /// Returns the queues the engines create: one of `queue_family_index` followed by an additional compute queue if possible.
+    /// The compute queue is taken from a dedicated compute family if the device has one,
+    /// otherwise from `queue_family_index` if that family is compute capable and has a second queue.
+    pub(crate) fn get_queue_create_infos(
+        physical_device: &PhysicalDevice,
+        queue_family_index: u32,
+    ) -> Vec<QueueCreateInfo> {
+        let families = physical_device.queue_family_properties();
+        let dedicated_compute_family = families.iter().position(|family| {
+            family.queue_flags.contains(QueueFlags::COMPUTE)
+                && !family.queue_flags.intersects(QueueFlags::GRAPHICS)
+        });
+        let family = &families[queue_family_index as usize];
+
+        match dedicated_compute_family {
+            Some(index) if index as u32 != queue_family_index => vec![
+                QueueCreateInfo {
+                    queue_family_index,
+                    ..Default::default()
+                },
+                QueueCreateInfo {
+                    queue_family_index: index as u32,
+                    ..Default::default()
+                },
+            ],
+            _ if family.queue_flags.contains(QueueFlags::COMPUTE) && family.queue_count > 1 => {
+                vec![QueueCreateInfo {
+                    queue_family_index,
+                    queues: vec![0.5; 2],
+                    ..Default::default()
+                }]
+            }
+            _ => vec![QueueCreateInfo {
+                queue_family_index,
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// Returns the optional extensions the engines enable whenever the PhysicalDevice supports them.
+    pub(crate) fn retrieve_optional_extensions(
+        physical_device: &PhysicalDevice,
+    ) -> DeviceExtensions {
+        let supported = physical_device.supported_extensions();
+
+        DeviceExtensions {
+            ext_memory_budget: supported.ext_memory_budget
+                && supports_memory_properties2(physical_device),
+            ..DeviceExtensions::empty()
+        }
+    }
+
+    /// Returns the optional features the engines enable whenever the PhysicalDevice supports them.
+    pub(crate) fn retrieve_optional_features(physical_device: &PhysicalDevice) -> Features {
+        let supported = physical_device.supported_features();
+
+        Features {
+            pipeline_statistics_query: supported.pipeline_statistics_query,
+            occlusion_query_precise: supported.occlusion_query_precise,
+            ..Features::empty()
         }
     }
 
@@ -43,6 +140,169 @@ impl LogicalDevice {
         self.queues[0].clone()
     }
 
+    /// Returns the additional compute queue, e.g. for async compute next to the first queue.
+    /// It's of a dedicated compute queue family if the device has one, check its `queue_family_index`.
+    /// `None` if the device has no queue to spare.
+    pub fn get_compute_queue(&self) -> Option<Arc<Queue>> {
+        self.compute_queue.clone()
+    }
+
+    /// Names a Vulkan object (buffer, image, pipeline, command buffer, ...) for debugging tools and validation messages.
+    /// Does nothing if `VK_EXT_debug_utils` isn't enabled.
+    pub fn set_debug_name<T>(&self, object: &T, name: &str)
+    where
+        T: VulkanObject + DeviceOwned,
+    {
+        set_debug_name(&self.device, object, name);
+    }
+
+    /// Registers a buffer with `track_resource` for the memory report and the leak tracker.
+    #[track_caller]
+    pub fn track_buffer<T: ?Sized>(&self, buffer: &Subbuffer<T>) {
+        track_resource(ResourceKind::Buffer, buffer.buffer());
+    }
+
+    /// Registers an image with `track_resource` for the memory report and the leak tracker.
+    #[track_caller]
+    pub fn track_image<I: ImageAccess + ?Sized>(&self, image: &I) {
+        track_resource(ResourceKind::Image, image.inner().image);
+    }
+
+    /// Collects heap budgets (with `VK_EXT_memory_budget`), the memory of tracked resources per memory type and live resource counts.
+    /// Tracked resources are the buffers and images registered with `track_resource`, including the ones the engine creates.
+    pub fn get_memory_report(&self) -> MemoryReport {
+        let physical_device = self.get_physical_device();
+        let (buffers, images) = get_tracked_memory_resources(&self.device);
+        let memory_types = collect_memory_types(&physical_device, &buffers, &images);
+        let live_resources = ResourceCounts {
+            buffers: buffers.len(),
+            images: images.len(),
+        };
+
+        MemoryReport {
+            heaps: query_memory_heaps(
+                &physical_device,
+                self.device.enabled_extensions().ext_memory_budget,
+            ),
+            memory_types,
+            device_allocation_count: self.device.allocation_count(),
+            max_device_allocation_count: self.get_device_max_memory_allocation_count(),
+            live_resources,
+        }
+    }
+
+    /// Prints out the memory report, warning about heaps nearing their budget.
+    pub fn print_memory_report(&self, log_level: log::Level) {
+        self.get_memory_report().print(log_level);
+    }
+
+    /// Returns the features of a format for images with optimal tiling, empty if the format isn't supported.
+    pub fn get_format_features(&self, format: Format) -> FormatFeatures {
+        get_format_features(self.device.physical_device(), format)
+    }
+
+    /// Whether images with optimal tiling of a format can be used for all usages.
+    pub fn is_format_supported(&self, format: Format, usages: &[FormatUsage]) -> bool {
+        self.find_supported_format(&[format], usages).is_some()
+    }
+
+    /// Returns the first format of the preference list supporting all usages.
+    pub fn find_supported_format(
+        &self,
+        preferences: &[Format],
+        usages: &[FormatUsage],
+    ) -> Option<Format> {
+        find_supported_format(self.device.physical_device(), preferences, usages)
+    }
+
+    /// Returns the first format of the preference list supporting all usages.
+    /// Panics if none of them does.
+    pub fn select_format(&self, preferences: &[Format], usages: &[FormatUsage]) -> Format {
+        let format = self
+            .find_supported_format(preferences, usages)
+            .unwrap_or_else(|| {
+                panic!(
+                    "none of the formats {:?} supports {:?} on {}",
+                    preferences,
+                    usages,
+                    self.get_device_name()
+                )
+            });
+        if format != preferences[0] {
+            log::debug!(
+                "Format {:?} doesn't support {:?}, falling back to {:?}",
+                preferences[0],
+                usages,
+                format
+            );
+        }
+
+        format
+    }
+
+    /// Returns the best supported depth attachment format of `DEPTH_FORMAT_PREFERENCES`.
+    pub fn select_depth_format(&self) -> Format {
+        self.select_format(
+            &DEPTH_FORMAT_PREFERENCES,
+            &[FormatUsage::DepthStencilAttachment],
+        )
+    }
+
+    /// Compiles GLSL source code to SPIR-V at runtime and creates a `ShaderModule` from it.
+    /// This is used by the engine's built-in shaders, applications should prefer `vulkano_shaders::shader!`.
+    pub fn create_glsl_shader_module(
+        &self,
+        source: &str,
+        shader_kind: GlslShaderKind,
+    ) -> Arc<ShaderModule> {
+        self.create_glsl_shader_module_with_defines(source, shader_kind, &[])
+    }
+
+    /// Compiles GLSL source code like `create_glsl_shader_module`, with preprocessor macros defined as `(name, value)`.
+    pub fn create_glsl_shader_module_with_defines(
+        &self,
+        source: &str,
+        shader_kind: GlslShaderKind,
+        defines: &[(&str, &str)],
+    ) -> Arc<ShaderModule> {
+        let stage = match shader_kind {
+            GlslShaderKind::Vertex => naga::ShaderStage::Vertex,
+            GlslShaderKind::Fragment => naga::ShaderStage::Fragment,
+            GlslShaderKind::Compute => naga::ShaderStage::Compute,
+        };
+
+        let mut glsl_options = naga::front::glsl::Options::from(stage);
+        glsl_options.defines.extend(
+            defines
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        );
+
+        let module = naga::front::glsl::Frontend::default()
+            .parse(&glsl_options, source)
+            .unwrap_or_else(|e| panic!("failed to parse GLSL shader: {:?}", e));
+        let module_info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .unwrap_or_else(|e| panic!("failed to validate GLSL shader: {:?}", e));
+
+        // The default flags adjust the coordinate space for WebGPU, which must not happen for Vulkan.
+        // Vulkano matches vertex attributes by name, hence the names of inputs and outputs are kept.
+        let options = naga::back::spv::Options {
+            flags: naga::back::spv::WriterFlags::DEBUG
+                | naga::back::spv::WriterFlags::LABEL_VARYINGS,
+            ..Default::default()
+        };
+        let words = naga::back::spv::write_vec(&module, &module_info, &options, None)
+            .expect("failed to write SPIR-V");
+
+        // Safety: naga validated the module and only emits valid SPIR-V
+        unsafe { ShaderModule::from_words(self.get_device(), &words) }
+            .expect("failed to create shader module")
+    }
+
     /// Prints out all enabled extensions of the LogicalDevice.
     pub fn print_enabled_extensions(&self, log_level: log::Level) {
         let extensions = self.device.enabled_extensions();
@@ -64,7 +324,14 @@ impl LogicalDevice {
         log::log!(log_level, "Device ID: {}", properties.device_id);
         log::log!(log_level, "API Version: {}", properties.api_version);
         log::log!(log_level, "Driver ID: {:?}", properties.driver_id);
-        log::log!(log_level, "Device UUID: {:?}", properties.device_uuid);
+        log::log!(
+            log_level,
+            "Device UUID: {}",
+            properties
+                .device_uuid
+                .as_ref()
+                .map_or("-".to_string(), format_device_uuid)
+        );
         log::log!(
             log_level,
             "Pipeline Cache UUID: {:?}",
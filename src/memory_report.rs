@@ -0,0 +1,276 @@
+use std::{collections::HashSet, sync::Arc};
+
+use vulkano::{
+    buffer::{Buffer, BufferMemory},
+    device::physical::PhysicalDevice,
+    image::sys::{Image, ImageMemory},
+    memory::{allocator::MemoryAlloc, MemoryHeapFlags, MemoryPropertyFlags},
+    Version, VulkanObject,
+};
+
+/// Heaps with a usage above this fraction of their budget are reported with a warning.
+pub const MEMORY_BUDGET_WARNING_THRESHOLD: f64 = 0.9;
+
+/// Budget and usage of a single memory heap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryHeapStatistics {
+    pub heap_index: u32,
+    pub size: u64,
+    pub device_local: bool,
+    /// Memory the process can use before allocations may fail or degrade, `None` without `VK_EXT_memory_budget`.
+    pub budget: Option<u64>,
+    /// Memory currently used by the process, `None` without `VK_EXT_memory_budget`.
+    pub usage: Option<u64>,
+}
+
+impl MemoryHeapStatistics {
+    /// Fraction of the budget in use, `None` without `VK_EXT_memory_budget`.
+    pub fn get_usage_ratio(&self) -> Option<f64> {
+        match (self.budget, self.usage) {
+            (Some(budget), Some(usage)) if budget > 0 => Some(usage as f64 / budget as f64),
+            _ => None,
+        }
+    }
+
+    /// Whether the usage is above `MEMORY_BUDGET_WARNING_THRESHOLD` of the budget.
+    pub fn is_near_budget(&self) -> bool {
+        self.get_usage_ratio()
+            .is_some_and(|ratio| ratio > MEMORY_BUDGET_WARNING_THRESHOLD)
+    }
+}
+
+/// Memory blocks of a single memory type backing tracked resources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryTypeStatistics {
+    pub memory_type_index: u32,
+    pub heap_index: u32,
+    pub property_flags: MemoryPropertyFlags,
+    /// `DeviceMemory` blocks holding at least one tracked resource.
+    pub block_count: u32,
+    /// Total size of these blocks.
+    pub block_bytes: u64,
+    /// Bytes of these blocks used by tracked resources.
+    pub used_bytes: u64,
+}
+
+impl MemoryTypeStatistics {
+    /// Bytes of the blocks not used by tracked resources, i.e. free or used by untracked ones.
+    pub fn get_free_bytes(&self) -> u64 {
+        self.block_bytes.saturating_sub(self.used_bytes)
+    }
+}
+
+/// Amount of live tracked resources by kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResourceCounts {
+    pub buffers: usize,
+    pub images: usize,
+}
+
+/// Memory telemetry of a `LogicalDevice`, see `LogicalDevice::get_memory_report`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryReport {
+    pub heaps: Vec<MemoryHeapStatistics>,
+    /// Only memory types holding tracked resources.
+    pub memory_types: Vec<MemoryTypeStatistics>,
+    /// `DeviceMemory` allocations of the whole device, tracked or not.
+    pub device_allocation_count: u32,
+    pub max_device_allocation_count: u32,
+    pub live_resources: ResourceCounts,
+}
+
+impl MemoryReport {
+    /// Returns the heaps above `MEMORY_BUDGET_WARNING_THRESHOLD` of their budget.
+    pub fn get_heaps_near_budget(&self) -> impl Iterator<Item = &MemoryHeapStatistics> {
+        self.heaps.iter().filter(|heap| heap.is_near_budget())
+    }
+
+    /// Prints out the report and warns about heaps nearing their budget.
+    pub fn print(&self, log_level: log::Level) {
+        for heap in &self.heaps {
+            let budget = match (heap.usage, heap.budget) {
+                (Some(usage), Some(budget)) => format!(
+                    "; Usage: {} of {} budget ({:.1}%)",
+                    format_bytes(usage),
+                    format_bytes(budget),
+                    heap.get_usage_ratio().unwrap_or(0.0) * 100.0
+                ),
+                _ => String::new(),
+            };
+            log::log!(
+                log_level,
+                "Memory Heap {}{}: Size: {}{}",
+                heap.heap_index,
+                if heap.device_local {
+                    " (Device Local)"
+                } else {
+                    ""
+                },
+                format_bytes(heap.size),
+                budget
+            );
+        }
+
+        for memory_type in &self.memory_types {
+            log::log!(
+                log_level,
+                "Memory Type {} (Heap {}, {:?}): Blocks: {}; Used: {}; Free: {}",
+                memory_type.memory_type_index,
+                memory_type.heap_index,
+                memory_type.property_flags,
+                memory_type.block_count,
+                format_bytes(memory_type.used_bytes),
+                format_bytes(memory_type.get_free_bytes())
+            );
+        }
+
+        log::log!(
+            log_level,
+            "Device Memory Allocations: {} of {}",
+            self.device_allocation_count,
+            self.max_device_allocation_count
+        );
+        log::log!(
+            log_level,
+            "Live Resources: {} Buffers; {} Images",
+            self.live_resources.buffers,
+            self.live_resources.images
+        );
+
+        for heap in self.get_heaps_near_budget() {
+            log::warn!(
+                "Memory Heap {} is at {:.1}% of its budget",
+                heap.heap_index,
+                heap.get_usage_ratio().unwrap_or(0.0) * 100.0
+            );
+        }
+    }
+}
+
+/// Collects the memory backing the given resources per memory type.
+pub(crate) fn collect_memory_types(
+    physical_device: &PhysicalDevice,
+    buffers: &[Arc<Buffer>],
+    images: &[Arc<Image>],
+) -> Vec<MemoryTypeStatistics> {
+    // Sparse and swapchain memory isn't owned by the resource itself
+    let allocations = buffers
+        .iter()
+        .filter_map(|buffer| match buffer.memory() {
+            BufferMemory::Normal(allocation) => Some(allocation),
+            _ => None,
+        })
+        .chain(images.iter().flat_map(|image| match image.memory() {
+            ImageMemory::Normal(allocations) => allocations.iter().collect(),
+            _ => Vec::new(),
+        }));
+
+    let memory_properties = physical_device.memory_properties();
+    let mut memory_types = Vec::<MemoryTypeStatistics>::new();
+    let mut blocks = HashSet::new();
+    for allocation in allocations {
+        let device_memory = allocation.device_memory();
+        let memory_type_index = device_memory.memory_type_index();
+
+        let index = match memory_types
+            .iter()
+            .position(|statistics| statistics.memory_type_index == memory_type_index)
+        {
+            Some(index) => index,
+            None => {
+                let memory_type = &memory_properties.memory_types[memory_type_index as usize];
+                memory_types.push(MemoryTypeStatistics {
+                    memory_type_index,
+                    heap_index: memory_type.heap_index,
+                    property_flags: memory_type.property_flags,
+                    block_count: 0,
+                    block_bytes: 0,
+                    used_bytes: 0,
+                });
+                memory_types.len() - 1
+            }
+        };
+
+        let statistics = &mut memory_types[index];
+        if blocks.insert(device_memory.handle()) {
+            statistics.block_count += 1;
+            statistics.block_bytes += device_memory.allocation_size();
+        }
+        statistics.used_bytes += MemoryAlloc::size(allocation);
+    }
+    memory_types.sort_by_key(|statistics| statistics.memory_type_index);
+
+    memory_types
+}
+
+/// Collects the size of every heap and, if `VK_EXT_memory_budget` is enabled, its budget and usage.
+pub(crate) fn query_memory_heaps(
+    physical_device: &PhysicalDevice,
+    memory_budget: bool,
+) -> Vec<MemoryHeapStatistics> {
+    let budget = memory_budget
+        .then(|| query_memory_budget(physical_device))
+        .flatten();
+
+    physical_device
+        .memory_properties()
+        .memory_heaps
+        .iter()
+        .enumerate()
+        .map(|(index, heap)| MemoryHeapStatistics {
+            heap_index: index as u32,
+            size: heap.size,
+            device_local: heap.flags.intersects(MemoryHeapFlags::DEVICE_LOCAL),
+            budget: budget.map(|budget| budget.heap_budget[index]),
+            usage: budget.map(|budget| budget.heap_usage[index]),
+        })
+        .collect()
+}
+
+/// Whether `vkGetPhysicalDeviceMemoryProperties2` is available, which `VK_EXT_memory_budget` needs.
+/// Gated on the device's API version, which vulkano already caps by the instance's maximum version.
+pub(crate) fn supports_memory_properties2(physical_device: &PhysicalDevice) -> bool {
+    physical_device.api_version() >= Version::V1_1
+        || physical_device
+            .instance()
+            .enabled_extensions()
+            .khr_get_physical_device_properties2
+}
+
+/// Vulkano doesn't expose `VK_EXT_memory_budget`, hence the properties are queried directly.
+fn query_memory_budget(
+    physical_device: &PhysicalDevice,
+) -> Option<ash::vk::PhysicalDeviceMemoryBudgetPropertiesEXT> {
+    let instance = physical_device.instance();
+    let fns = instance.fns();
+
+    let mut budget = ash::vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+    let mut properties = ash::vk::PhysicalDeviceMemoryProperties2::builder().push_next(&mut budget);
+
+    // Safety: the budget properties are only chained if the device enabled the extension
+    unsafe {
+        if physical_device.api_version() >= Version::V1_1 {
+            (fns.v1_1.get_physical_device_memory_properties2)(
+                physical_device.handle(),
+                &mut *properties,
+            );
+        } else if instance
+            .enabled_extensions()
+            .khr_get_physical_device_properties2
+        {
+            (fns.khr_get_physical_device_properties2
+                .get_physical_device_memory_properties2_khr)(
+                physical_device.handle(),
+                &mut *properties,
+            );
+        } else {
+            return None;
+        }
+    }
+
+    Some(budget)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
@@ -0,0 +1,993 @@
+use std::sync::Arc;
+
+use vulkano::{
+    buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage},
+    command_buffer::{
+        allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, CommandBufferUsage,
+        CopyBufferToImageInfo, RenderPassBeginInfo, SubpassContents,
+    },
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    device::DeviceOwned,
+    format::Format,
+    image::{
+        view::ImageView, ImageAccess, ImageCreateFlags, ImageDimensions, ImageUsage,
+        ImageViewAbstract, StorageImage,
+    },
+    memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
+    pipeline::{
+        graphics::{
+            input_assembly::InputAssemblyState,
+            vertex_input::VertexInputState,
+            viewport::{Viewport, ViewportState},
+        },
+        ComputePipeline, GraphicsPipeline, Pipeline, PipelineBindPoint,
+    },
+    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass},
+};
+
+use crate::{
+    get_glsl_format_qualifier, set_debug_name, track_resource, AbstractEngine, CommandBufferLabels,
+    EngineCommandBufferBuilder, FormatUsage, GlslShaderKind, GraphicalEngine, LogicalDevice,
+    ResourceKind, HDR_FORMAT_PREFERENCES, LUT_FORMAT_PREFERENCES,
+};
+
+/// A single full-screen effect of a `PostProcessingChain`.
+pub trait PostProcessingStage {
+    /// Returns the name of the stage.
+    fn get_name(&self) -> &str;
+
+    /// Records the stage into a command buffer.
+    /// `input` and `output` are both storage images of the same size with the format of `PostProcessingChain::select_hdr_format`.
+    #[track_caller]
+    fn record(
+        &self,
+        builder: &mut EngineCommandBufferBuilder,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        input: Arc<dyn ImageViewAbstract>,
+        output: Arc<dyn ImageViewAbstract>,
+    );
+
+    /// Called whenever the `PostProcessingChain` is resized.
+    /// Stages owning intermediate images must recreate them here.
+    #[track_caller]
+    fn resize(&mut self, _memory_allocator: &StandardMemoryAllocator, _dimensions: [u32; 2]) {}
+}
+
+/// A compute pipeline running once per pixel of its output image.
+/// The images are bound to set 0 in the given order, starting with binding 0.
+/// The shader gets the image format qualifier of the selected HDR format as `HDR_FORMAT` and the given `defines`.
+struct FullScreenComputePass {
+    pipeline: Arc<ComputePipeline>,
+}
+
+impl FullScreenComputePass {
+    #[track_caller]
+    fn new<E, Pc>(engine: &E, name: &str, source: &str, defines: &[(&str, &str)]) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+        Pc: BufferContents,
+    {
+        let device = engine.get_logical_device();
+        let defines = [&[get_hdr_format_define(&device)], defines].concat();
+        let shader = device.create_glsl_shader_module_with_defines(
+            source,
+            GlslShaderKind::Compute,
+            &defines,
+        );
+        let pipeline = engine.create_compute_pipeline::<Pc, _>(shader, &());
+        device.set_debug_name(&*pipeline, &format!("{} Pipeline", name));
+
+        Self { pipeline }
+    }
+
+    #[track_caller]
+    fn record<Pc>(
+        &self,
+        builder: &mut EngineCommandBufferBuilder,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        images: Vec<Arc<dyn ImageViewAbstract>>,
+        output: Arc<dyn ImageViewAbstract>,
+        push_constants: Pc,
+    ) where
+        Pc: BufferContents,
+    {
+        let [width, height] = output.image().dimensions().width_height();
+        let layout = self.pipeline.layout();
+
+        let set = PersistentDescriptorSet::new(
+            descriptor_set_allocator,
+            layout.set_layouts()[0].clone(),
+            images
+                .into_iter()
+                .enumerate()
+                .map(|(binding, image)| WriteDescriptorSet::image_view(binding as u32, image)),
+        )
+        .expect("failed to create post-processing descriptor set");
+        track_resource(ResourceKind::DescriptorSet, &set);
+
+        builder
+            .bind_pipeline_compute(self.pipeline.clone())
+            .bind_descriptor_sets(PipelineBindPoint::Compute, layout.clone(), 0, set)
+            .push_constants(layout.clone(), 0, push_constants)
+            .dispatch([width.div_ceil(8), height.div_ceil(8), 1])
+            .expect("failed to dispatch post-processing stage");
+    }
+}
+
+/// Returns the `HDR_FORMAT` define of the built-in shaders, the image format qualifier of the selected HDR format.
+fn get_hdr_format_define(device: &LogicalDevice) -> (&'static str, &'static str) {
+    (
+        "HDR_FORMAT",
+        get_glsl_format_qualifier(PostProcessingChain::select_hdr_format(device)),
+    )
+}
+
+/// Creates an image usable as input and output of `PostProcessingStage`s.
+#[track_caller]
+fn create_stage_image(
+    memory_allocator: &StandardMemoryAllocator,
+    dimensions: [u32; 2],
+    format: Format,
+    usage: ImageUsage,
+) -> Arc<ImageView<StorageImage>> {
+    let image = StorageImage::with_usage(
+        memory_allocator,
+        ImageDimensions::Dim2d {
+            width: dimensions[0],
+            height: dimensions[1],
+            array_layers: 1,
+        },
+        format,
+        usage,
+        ImageCreateFlags::empty(),
+        None,
+    )
+    .expect("failed to create post-processing image");
+    track_resource(ResourceKind::Image, image.inner().image);
+    set_debug_name(
+        memory_allocator.device(),
+        &**image.inner().image,
+        "PostProcessing Image",
+    );
+
+    ImageView::new_default(image).expect("failed to create post-processing image view")
+}
+
+/// The tone mapping operator of a `ToneMappingStage`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMappingOperator {
+    Reinhard,
+    Aces,
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct ToneMappingPushConstants {
+    operator: u32,
+    exposure: f32,
+}
+
+/// Maps HDR colors into the displayable [0, 1] range.
+pub struct ToneMappingStage {
+    pass: FullScreenComputePass,
+    operator: ToneMappingOperator,
+    exposure: f32,
+}
+
+impl ToneMappingStage {
+    /// Creates a `ToneMappingStage` with a given operator and exposure multiplier.
+    #[track_caller]
+    pub fn new<E>(engine: &E, operator: ToneMappingOperator, exposure: f32) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        Self {
+            pass: FullScreenComputePass::new::<E, ToneMappingPushConstants>(
+                engine,
+                "Tone Mapping",
+                include_str!("../shaders/post_processing/tone_mapping.comp"),
+                &[],
+            ),
+            operator,
+            exposure,
+        }
+    }
+
+    /// Sets the tone mapping operator.
+    pub fn set_operator(&mut self, operator: ToneMappingOperator) {
+        self.operator = operator;
+    }
+
+    /// Sets the exposure multiplier applied before tone mapping.
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure;
+    }
+}
+
+impl PostProcessingStage for ToneMappingStage {
+    fn get_name(&self) -> &str {
+        "Tone Mapping"
+    }
+
+    fn record(
+        &self,
+        builder: &mut EngineCommandBufferBuilder,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        input: Arc<dyn ImageViewAbstract>,
+        output: Arc<dyn ImageViewAbstract>,
+    ) {
+        self.pass.record(
+            builder,
+            descriptor_set_allocator,
+            vec![input, output.clone()],
+            output,
+            ToneMappingPushConstants {
+                operator: match self.operator {
+                    ToneMappingOperator::Reinhard => 0,
+                    ToneMappingOperator::Aces => 1,
+                },
+                exposure: self.exposure,
+            },
+        );
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct GammaCorrectionPushConstants {
+    gamma: f32,
+}
+
+/// Applies gamma correction.
+/// Not needed if the `Swapchain` uses an sRGB format, as the hardware encodes the colors already.
+pub struct GammaCorrectionStage {
+    pass: FullScreenComputePass,
+    gamma: f32,
+}
+
+impl GammaCorrectionStage {
+    /// Creates a `GammaCorrectionStage` with a given gamma, usually `2.2`.
+    #[track_caller]
+    pub fn new<E>(engine: &E, gamma: f32) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        Self {
+            pass: FullScreenComputePass::new::<E, GammaCorrectionPushConstants>(
+                engine,
+                "Gamma Correction",
+                include_str!("../shaders/post_processing/gamma_correction.comp"),
+                &[],
+            ),
+            gamma,
+        }
+    }
+
+    /// Sets the gamma.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.gamma = gamma;
+    }
+}
+
+impl PostProcessingStage for GammaCorrectionStage {
+    fn get_name(&self) -> &str {
+        "Gamma Correction"
+    }
+
+    fn record(
+        &self,
+        builder: &mut EngineCommandBufferBuilder,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        input: Arc<dyn ImageViewAbstract>,
+        output: Arc<dyn ImageViewAbstract>,
+    ) {
+        self.pass.record(
+            builder,
+            descriptor_set_allocator,
+            vec![input, output.clone()],
+            output,
+            GammaCorrectionPushConstants { gamma: self.gamma },
+        );
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct FxaaPushConstants {
+    edge_threshold: f32,
+    edge_threshold_min: f32,
+    span_max: f32,
+}
+
+/// Fast approximate anti-aliasing.
+/// Should run after tone mapping, as edges are detected on the luma of the colors.
+pub struct FxaaStage {
+    pass: FullScreenComputePass,
+    edge_threshold: f32,
+    edge_threshold_min: f32,
+    span_max: f32,
+}
+
+impl FxaaStage {
+    /// Creates a `FxaaStage` with the default FXAA quality settings.
+    #[track_caller]
+    pub fn new<E>(engine: &E) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        Self {
+            pass: FullScreenComputePass::new::<E, FxaaPushConstants>(
+                engine,
+                "FXAA",
+                include_str!("../shaders/post_processing/fxaa.comp"),
+                &[],
+            ),
+            edge_threshold: 0.125,
+            edge_threshold_min: 0.0312,
+            span_max: 8.0,
+        }
+    }
+
+    /// Sets the relative and absolute minimal luma contrast a pixel needs to be considered an edge.
+    pub fn set_edge_thresholds(&mut self, edge_threshold: f32, edge_threshold_min: f32) {
+        self.edge_threshold = edge_threshold;
+        self.edge_threshold_min = edge_threshold_min;
+    }
+
+    /// Sets the maximal length (in pixels) of the blur along an edge.
+    pub fn set_span_max(&mut self, span_max: f32) {
+        self.span_max = span_max;
+    }
+}
+
+impl PostProcessingStage for FxaaStage {
+    fn get_name(&self) -> &str {
+        "FXAA"
+    }
+
+    fn record(
+        &self,
+        builder: &mut EngineCommandBufferBuilder,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        input: Arc<dyn ImageViewAbstract>,
+        output: Arc<dyn ImageViewAbstract>,
+    ) {
+        self.pass.record(
+            builder,
+            descriptor_set_allocator,
+            vec![input, output.clone()],
+            output,
+            FxaaPushConstants {
+                edge_threshold: self.edge_threshold,
+                edge_threshold_min: self.edge_threshold_min,
+                span_max: self.span_max,
+            },
+        );
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct BloomThresholdPushConstants {
+    threshold: f32,
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct BloomBlurPushConstants {
+    direction: [i32; 2],
+    radius: i32,
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct BloomCompositePushConstants {
+    intensity: f32,
+}
+
+/// Makes bright areas bleed into their surroundings.
+/// Should run before tone mapping, as only HDR colors above the threshold contribute.
+pub struct BloomStage {
+    threshold_pass: FullScreenComputePass,
+    blur_pass: FullScreenComputePass,
+    composite_pass: FullScreenComputePass,
+    bloom_images: [Arc<ImageView<StorageImage>>; 2],
+    hdr_format: Format,
+    threshold: f32,
+    intensity: f32,
+    radius: u32,
+}
+
+impl BloomStage {
+    /// Creates a `BloomStage`.
+    /// Colors brighter than `threshold` are blurred with a given `radius` (in pixels) and added `intensity` times to the image.
+    #[track_caller]
+    pub fn new<E>(
+        engine: &E,
+        memory_allocator: &StandardMemoryAllocator,
+        dimensions: [u32; 2],
+        threshold: f32,
+        intensity: f32,
+        radius: u32,
+    ) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        let hdr_format = PostProcessingChain::select_hdr_format(&engine.get_logical_device());
+
+        Self {
+            threshold_pass: FullScreenComputePass::new::<E, BloomThresholdPushConstants>(
+                engine,
+                "Bloom Threshold",
+                include_str!("../shaders/post_processing/bloom_threshold.comp"),
+                &[],
+            ),
+            blur_pass: FullScreenComputePass::new::<E, BloomBlurPushConstants>(
+                engine,
+                "Bloom Blur",
+                include_str!("../shaders/post_processing/bloom_blur.comp"),
+                &[],
+            ),
+            composite_pass: FullScreenComputePass::new::<E, BloomCompositePushConstants>(
+                engine,
+                "Bloom Composite",
+                include_str!("../shaders/post_processing/bloom_composite.comp"),
+                &[],
+            ),
+            bloom_images: Self::create_bloom_images(memory_allocator, dimensions, hdr_format),
+            hdr_format,
+            threshold,
+            intensity,
+            radius,
+        }
+    }
+
+    #[track_caller]
+    fn create_bloom_images(
+        memory_allocator: &StandardMemoryAllocator,
+        dimensions: [u32; 2],
+        format: Format,
+    ) -> [Arc<ImageView<StorageImage>>; 2] {
+        // Not created by `map`, as closures would hide the caller from the leak tracker
+        [
+            create_stage_image(memory_allocator, dimensions, format, ImageUsage::STORAGE),
+            create_stage_image(memory_allocator, dimensions, format, ImageUsage::STORAGE),
+        ]
+    }
+
+    /// Sets the brightness above which colors contribute to the bloom.
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold;
+    }
+
+    /// Sets how strong the bloom is added to the image.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity;
+    }
+
+    /// Sets the blur radius in pixels.
+    pub fn set_radius(&mut self, radius: u32) {
+        self.radius = radius;
+    }
+}
+
+impl PostProcessingStage for BloomStage {
+    fn get_name(&self) -> &str {
+        "Bloom"
+    }
+
+    fn record(
+        &self,
+        builder: &mut EngineCommandBufferBuilder,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        input: Arc<dyn ImageViewAbstract>,
+        output: Arc<dyn ImageViewAbstract>,
+    ) {
+        let [bloom_a, bloom_b] = self.bloom_images.clone();
+
+        self.threshold_pass.record(
+            builder,
+            descriptor_set_allocator,
+            vec![input.clone(), bloom_a.clone()],
+            bloom_a.clone(),
+            BloomThresholdPushConstants {
+                threshold: self.threshold,
+            },
+        );
+
+        // Horizontal blur from A into B, vertical blur from B back into A
+        for (direction, source, target) in [
+            ([1, 0], bloom_a.clone(), bloom_b.clone()),
+            ([0, 1], bloom_b, bloom_a.clone()),
+        ] {
+            self.blur_pass.record(
+                builder,
+                descriptor_set_allocator,
+                vec![source, target.clone()],
+                target,
+                BloomBlurPushConstants {
+                    direction,
+                    radius: self.radius as i32,
+                },
+            );
+        }
+
+        self.composite_pass.record(
+            builder,
+            descriptor_set_allocator,
+            vec![input, output.clone(), bloom_a],
+            output,
+            BloomCompositePushConstants {
+                intensity: self.intensity,
+            },
+        );
+    }
+
+    fn resize(&mut self, memory_allocator: &StandardMemoryAllocator, dimensions: [u32; 2]) {
+        self.bloom_images =
+            Self::create_bloom_images(memory_allocator, dimensions, self.hdr_format);
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct VignettePushConstants {
+    intensity: f32,
+    radius: f32,
+    smoothness: f32,
+}
+
+/// Darkens the corners of the image.
+pub struct VignetteStage {
+    pass: FullScreenComputePass,
+    intensity: f32,
+    radius: f32,
+    smoothness: f32,
+}
+
+impl VignetteStage {
+    /// Creates a `VignetteStage`.
+    /// `radius` is the distance from the center (`1.0` being the corners) where the darkening ends,
+    /// `smoothness` the distance over which it fades in and `intensity` how dark it gets.
+    #[track_caller]
+    pub fn new<E>(engine: &E, intensity: f32, radius: f32, smoothness: f32) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        Self {
+            pass: FullScreenComputePass::new::<E, VignettePushConstants>(
+                engine,
+                "Vignette",
+                include_str!("../shaders/post_processing/vignette.comp"),
+                &[],
+            ),
+            intensity,
+            radius,
+            smoothness,
+        }
+    }
+
+    /// Sets how dark the vignette gets.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity;
+    }
+
+    /// Sets where the vignette ends and over which distance it fades in.
+    pub fn set_radius(&mut self, radius: f32, smoothness: f32) {
+        self.radius = radius;
+        self.smoothness = smoothness;
+    }
+}
+
+impl PostProcessingStage for VignetteStage {
+    fn get_name(&self) -> &str {
+        "Vignette"
+    }
+
+    fn record(
+        &self,
+        builder: &mut EngineCommandBufferBuilder,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        input: Arc<dyn ImageViewAbstract>,
+        output: Arc<dyn ImageViewAbstract>,
+    ) {
+        self.pass.record(
+            builder,
+            descriptor_set_allocator,
+            vec![input, output.clone()],
+            output,
+            VignettePushConstants {
+                intensity: self.intensity,
+                radius: self.radius,
+                smoothness: self.smoothness,
+            },
+        );
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct ColorGradingPushConstants {
+    strength: f32,
+}
+
+/// Remaps colors through a 3D look-up table (LUT).
+/// Should run after tone mapping, as the LUT only covers colors in the [0, 1] range.
+pub struct ColorGradingStage {
+    pass: FullScreenComputePass,
+    lut: Arc<dyn ImageViewAbstract>,
+    strength: f32,
+}
+
+impl ColorGradingStage {
+    /// Creates a `ColorGradingStage` from a 3D storage image LUT of a `LUT_FORMAT_PREFERENCES` format, e.g. created by `create_lut`.
+    /// `strength` blends between the original (`0.0`) and the graded (`1.0`) colors.
+    #[track_caller]
+    pub fn new<E>(engine: &E, lut: Arc<dyn ImageViewAbstract>, strength: f32) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        let lut_format = lut.format().expect("LUT has no format");
+        assert!(
+            LUT_FORMAT_PREFERENCES.contains(&lut_format),
+            "LUT format {:?} is not supported",
+            lut_format
+        );
+
+        Self {
+            pass: FullScreenComputePass::new::<E, ColorGradingPushConstants>(
+                engine,
+                "Color Grading",
+                include_str!("../shaders/post_processing/color_grading.comp"),
+                &[("LUT_FORMAT", get_glsl_format_qualifier(lut_format))],
+            ),
+            lut,
+            strength,
+        }
+    }
+
+    /// Creates and uploads a LUT with `size` texels per axis, in the first `LUT_FORMAT_PREFERENCES` format usable as storage image.
+    /// `texels` must contain `size * size * size` colors, with red changing fastest and blue slowest.
+    #[track_caller]
+    pub fn create_lut<E>(
+        engine: &E,
+        memory_allocator: &StandardMemoryAllocator,
+        size: u32,
+        texels: Vec<[u8; 4]>,
+    ) -> Arc<ImageView<StorageImage>>
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        assert_eq!(
+            texels.len(),
+            (size * size * size) as usize,
+            "LUT must contain size^3 texels"
+        );
+
+        let format = engine
+            .get_logical_device()
+            .select_format(&LUT_FORMAT_PREFERENCES, &[FormatUsage::Storage]);
+        let image = StorageImage::with_usage(
+            memory_allocator,
+            ImageDimensions::Dim3d {
+                width: size,
+                height: size,
+                depth: size,
+            },
+            format,
+            ImageUsage::STORAGE | ImageUsage::TRANSFER_DST,
+            ImageCreateFlags::empty(),
+            None,
+        )
+        .expect("failed to create LUT image");
+        track_resource(ResourceKind::Image, image.inner().image);
+
+        // The 16-bit fallback gets every channel scaled from [0, 255] to [0, 65535]
+        let bytes = match format {
+            Format::R16G16B16A16_UNORM => texels
+                .into_iter()
+                .flatten()
+                .flat_map(|channel| (channel as u16 * 257).to_le_bytes())
+                .collect::<Vec<_>>(),
+            _ => texels.into_iter().flatten().collect(),
+        };
+        let upload_buffer = Buffer::from_iter(
+            memory_allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Upload,
+                ..Default::default()
+            },
+            bytes,
+        )
+        .expect("failed to create LUT upload buffer");
+        track_resource(ResourceKind::Buffer, upload_buffer.buffer());
+
+        engine.compute(&|engine: &E| {
+            let mut builder = AutoCommandBufferBuilder::primary(
+                &engine.get_command_buffer_allocator(),
+                engine.get_logical_device().get_queue_family_index(),
+                CommandBufferUsage::OneTimeSubmit,
+            )
+            .unwrap();
+
+            builder
+                .copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
+                    upload_buffer.clone(),
+                    image.clone(),
+                ))
+                .unwrap();
+
+            builder.build().unwrap()
+        });
+
+        ImageView::new_default(image).expect("failed to create LUT image view")
+    }
+
+    /// Replaces the LUT, which must have the format of the current one.
+    pub fn set_lut(&mut self, lut: Arc<dyn ImageViewAbstract>) {
+        assert_eq!(
+            lut.format(),
+            self.lut.format(),
+            "LUT format differs from the one the stage was created with"
+        );
+
+        self.lut = lut;
+    }
+
+    /// Sets how strong the graded colors are blended in.
+    pub fn set_strength(&mut self, strength: f32) {
+        self.strength = strength;
+    }
+}
+
+impl PostProcessingStage for ColorGradingStage {
+    fn get_name(&self) -> &str {
+        "Color Grading"
+    }
+
+    fn record(
+        &self,
+        builder: &mut EngineCommandBufferBuilder,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        input: Arc<dyn ImageViewAbstract>,
+        output: Arc<dyn ImageViewAbstract>,
+    ) {
+        self.pass.record(
+            builder,
+            descriptor_set_allocator,
+            vec![input, output.clone(), self.lut.clone()],
+            output,
+            ColorGradingPushConstants {
+                strength: self.strength,
+            },
+        );
+    }
+}
+
+/// A `PostProcessingChain` renders the scene into an offscreen HDR target,
+/// runs a configurable list of `PostProcessingStage`s on it and finally presents the result onto the `Swapchain`.
+///
+/// The scene is rendered with the `RenderPass` and `Framebuffer` from `create_scene_render_pass` and `create_scene_frame_buffer`.
+/// Presenting uses the `RenderPass` and `Framebuffer`s of the `GraphicalEngine`.
+pub struct PostProcessingChain {
+    dimensions: [u32; 2],
+    hdr_format: Format,
+    hdr_target: Arc<ImageView<StorageImage>>,
+    stage_images: [Arc<ImageView<StorageImage>>; 2],
+    stages: Vec<Box<dyn PostProcessingStage>>,
+    descriptor_set_allocator: StandardDescriptorSetAllocator,
+    present_render_pass: Arc<RenderPass>,
+    present_pipeline: Arc<GraphicsPipeline>,
+    present_frame_buffers: Vec<Arc<Framebuffer>>,
+}
+
+impl PostProcessingChain {
+    /// Creates a `PostProcessingChain` without any stages, sized like the `Swapchain` of the `GraphicalEngine`.
+    #[track_caller]
+    pub fn new(engine: &GraphicalEngine, memory_allocator: &StandardMemoryAllocator) -> Self {
+        log::debug!("PostProcessingChain::new");
+
+        let device = engine.get_logical_device();
+        let dimensions = engine.get_swap_chain().image_extent();
+
+        let hdr_format = Self::select_hdr_format(&device);
+        let present_render_pass = engine.create_render_pass();
+        let vertex_shader = device.create_glsl_shader_module(
+            include_str!("../shaders/post_processing/present.vert"),
+            GlslShaderKind::Vertex,
+        );
+        let fragment_shader = device.create_glsl_shader_module_with_defines(
+            include_str!("../shaders/post_processing/present.frag"),
+            GlslShaderKind::Fragment,
+            &[get_hdr_format_define(&device)],
+        );
+        let present_pipeline = GraphicsPipeline::start()
+            .vertex_input_state(VertexInputState::new())
+            .vertex_shader(vertex_shader.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .fragment_shader(fragment_shader.entry_point("main").unwrap(), ())
+            .render_pass(Subpass::from(present_render_pass.clone(), 0).unwrap())
+            .build(device.get_device())
+            .expect("failed to create present pipeline");
+        track_resource(ResourceKind::Pipeline, &present_pipeline);
+        device.set_debug_name(&*present_pipeline, "PostProcessingChain Present Pipeline");
+
+        let (hdr_target, stage_images) =
+            Self::create_images(memory_allocator, dimensions, hdr_format);
+
+        Self {
+            dimensions,
+            hdr_format,
+            hdr_target,
+            stage_images,
+            stages: Vec::new(),
+            descriptor_set_allocator: StandardDescriptorSetAllocator::new(device.get_device()),
+            present_frame_buffers: engine.create_frame_buffers(present_render_pass.clone()),
+            present_render_pass,
+            present_pipeline,
+        }
+    }
+
+    /// Returns the first `HDR_FORMAT_PREFERENCES` format usable as storage image, color attachment and sampled image,
+    /// which is the format of the HDR target and all intermediate images.
+    pub fn select_hdr_format(device: &LogicalDevice) -> Format {
+        device.select_format(
+            &HDR_FORMAT_PREFERENCES,
+            &[
+                FormatUsage::Storage,
+                FormatUsage::ColorAttachment,
+                FormatUsage::Sampled,
+            ],
+        )
+    }
+
+    /// Returns the `Format` of the HDR target and all intermediate images.
+    pub fn get_hdr_format(&self) -> Format {
+        self.hdr_format
+    }
+
+    #[track_caller]
+    fn create_images(
+        memory_allocator: &StandardMemoryAllocator,
+        dimensions: [u32; 2],
+        format: Format,
+    ) -> (
+        Arc<ImageView<StorageImage>>,
+        [Arc<ImageView<StorageImage>>; 2],
+    ) {
+        let usage = ImageUsage::STORAGE | ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST;
+
+        (
+            create_stage_image(
+                memory_allocator,
+                dimensions,
+                format,
+                usage | ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+            ),
+            [
+                create_stage_image(memory_allocator, dimensions, format, usage),
+                create_stage_image(memory_allocator, dimensions, format, usage),
+            ],
+        )
+    }
+
+    /// Appends a stage to the end of the chain.
+    pub fn add_stage<S>(&mut self, stage: S)
+    where
+        S: PostProcessingStage + 'static,
+    {
+        log::debug!("Post-processing stage added: {}", stage.get_name());
+
+        self.stages.push(Box::new(stage));
+    }
+
+    /// Returns the stages of the chain, e.g. to change their parameters.
+    pub fn get_stages_mut(&mut self) -> &mut Vec<Box<dyn PostProcessingStage>> {
+        &mut self.stages
+    }
+
+    /// Returns the HDR target the scene has to be rendered into.
+    pub fn get_hdr_target(&self) -> Arc<ImageView<StorageImage>> {
+        self.hdr_target.clone()
+    }
+
+    /// Creates a `RenderPass` rendering into the HDR target.
+    pub fn create_scene_render_pass(&self, engine: &GraphicalEngine) -> Arc<RenderPass> {
+        engine.create_render_pass_with_format(self.hdr_format)
+    }
+
+    /// Creates a `Framebuffer` wrapping the HDR target for a given scene `RenderPass`.
+    /// Has to be recreated after `resize`.
+    pub fn create_scene_frame_buffer(&self, render_pass: Arc<RenderPass>) -> Arc<Framebuffer> {
+        Framebuffer::new(
+            render_pass,
+            FramebufferCreateInfo {
+                attachments: vec![self.hdr_target.clone()],
+                ..Default::default()
+            },
+        )
+        .expect("failed to create scene framebuffer")
+    }
+
+    /// Recreates all images and `Framebuffer`s to match the `Swapchain` of the `GraphicalEngine`.
+    /// Must be called after `GraphicalEngine::recreate_swap_chain_and_images`.
+    #[track_caller]
+    pub fn resize(&mut self, engine: &GraphicalEngine, memory_allocator: &StandardMemoryAllocator) {
+        log::debug!("PostProcessingChain::resize");
+
+        self.dimensions = engine.get_swap_chain().image_extent();
+        (self.hdr_target, self.stage_images) =
+            Self::create_images(memory_allocator, self.dimensions, self.hdr_format);
+        self.present_frame_buffers = engine.create_frame_buffers(self.present_render_pass.clone());
+
+        for stage in &mut self.stages {
+            stage.resize(memory_allocator, self.dimensions);
+        }
+    }
+
+    /// Records all stages and the presentation onto the `SwapchainImage` with the given index.
+    /// Must be recorded after the scene has been rendered into the HDR target.
+    #[track_caller]
+    pub fn record(&self, builder: &mut EngineCommandBufferBuilder, image_index: u32) {
+        let mut input: Arc<dyn ImageViewAbstract> = self.hdr_target.clone();
+        for (i, stage) in self.stages.iter().enumerate() {
+            let output: Arc<dyn ImageViewAbstract> = self.stage_images[i % 2].clone();
+
+            log::trace!("Recording post-processing stage: {}", stage.get_name());
+            builder.label(stage.get_name(), [0.0; 4], |builder| {
+                stage.record(
+                    builder,
+                    &self.descriptor_set_allocator,
+                    input,
+                    output.clone(),
+                )
+            });
+
+            input = output;
+        }
+
+        let layout = self.present_pipeline.layout();
+        let set = PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            layout.set_layouts()[0].clone(),
+            [WriteDescriptorSet::image_view(0, input)],
+        )
+        .expect("failed to create present descriptor set");
+        track_resource(ResourceKind::DescriptorSet, &set);
+
+        builder.label("Present", [0.0; 4], |builder| {
+            builder
+                .begin_render_pass(
+                    RenderPassBeginInfo {
+                        clear_values: vec![Some([0.0, 0.0, 0.0, 1.0].into())],
+                        ..RenderPassBeginInfo::framebuffer(
+                            self.present_frame_buffers[image_index as usize].clone(),
+                        )
+                    },
+                    SubpassContents::Inline,
+                )
+                .unwrap()
+                .set_viewport(
+                    0,
+                    [Viewport {
+                        origin: [0.0, 0.0],
+                        dimensions: [self.dimensions[0] as f32, self.dimensions[1] as f32],
+                        depth_range: 0.0..1.0,
+                    }],
+                )
+                .bind_pipeline_graphics(self.present_pipeline.clone())
+                .bind_descriptor_sets(PipelineBindPoint::Graphics, layout.clone(), 0, set)
+                .draw(3, 1, 0, 0)
+                .unwrap()
+                .end_render_pass()
+                .unwrap();
+        });
+    }
+}
@@ -0,0 +1,620 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use vulkano::{
+    buffer::Subbuffer,
+    command_buffer::{
+        allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, CommandBufferUsage,
+        PrimaryAutoCommandBuffer,
+    },
+    device::DeviceOwned,
+    format::Format,
+    image::{
+        view::ImageView, ImageAccess, ImageCreateFlags, ImageDimensions, ImageUsage,
+        ImageViewAbstract, StorageImage,
+    },
+    memory::allocator::StandardMemoryAllocator,
+};
+
+use crate::{
+    find_supported_format, set_debug_name, track_resource, AbstractEngine, CommandBufferLabels,
+    EngineCommandBufferBuilder, FormatUsage, ResourceKind,
+};
+
+/// The closure recording the commands of a single pass of a `RenderGraph`.
+pub type RenderGraphPassRecorder =
+    Box<dyn Fn(&mut EngineCommandBufferBuilder, &RenderGraphResources) + Send + Sync>;
+
+/// Identifies a resource (image or buffer) of a `RenderGraph`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RenderGraphResourceId(usize);
+
+/// The kind of work a `RenderGraphPass` submits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderGraphPassKind {
+    Graphics,
+    Compute,
+}
+
+/// Describes a transient image which is allocated (and possibly reused for other transient images) by the `RenderGraph` itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderGraphImageDescription {
+    pub dimensions: ImageDimensions,
+    pub format: Format,
+    pub usage: ImageUsage,
+}
+
+/// A resource known to the `RenderGraph`.
+#[derive(Clone, Debug)]
+enum RenderGraphResource {
+    /// A transient image with the formats to fall back to if its own format doesn't support its usage.
+    TransientImage(RenderGraphImageDescription, Vec<Format>),
+    AllocatedImage(Arc<dyn ImageViewAbstract>),
+    ImportedImage(Arc<dyn ImageViewAbstract>),
+    ImportedBuffer(Subbuffer<[u8]>),
+}
+
+/// A single pass of a `RenderGraph`, declaring which resources it reads and writes.
+pub struct RenderGraphPass {
+    name: String,
+    kind: RenderGraphPassKind,
+    reads: Vec<RenderGraphResourceId>,
+    writes: Vec<RenderGraphResourceId>,
+    recorder: RenderGraphPassRecorder,
+}
+
+impl RenderGraphPass {
+    /// Returns the name of the pass.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the kind of the pass.
+    pub fn get_kind(&self) -> RenderGraphPassKind {
+        self.kind
+    }
+
+    /// Returns the resources read by the pass.
+    pub fn get_reads(&self) -> &[RenderGraphResourceId] {
+        &self.reads
+    }
+
+    /// Returns the resources written by the pass.
+    pub fn get_writes(&self) -> &[RenderGraphResourceId] {
+        &self.writes
+    }
+}
+
+/// A `RenderGraph` is a collection of graphics and compute passes with their read/write resources.
+/// Once compiled, the passes are ordered by their dependencies, transient images are allocated
+/// (reusing whole images for transient images with equal descriptions whose lifetimes don't overlap)
+/// and everything is recorded into a single command buffer.
+/// Memory isn't aliased between images of different descriptions.
+///
+/// Synchronization between passes is inserted while recording: Vulkano's `AutoCommandBufferBuilder`
+/// tracks every resource used by a command and emits the required pipeline barriers and layout transitions.
+#[derive(Default)]
+pub struct RenderGraph {
+    resource_names: Vec<String>,
+    resources: Vec<RenderGraphResource>,
+    passes: Vec<RenderGraphPass>,
+}
+
+impl RenderGraph {
+    /// Creates an empty `RenderGraph`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a transient image which is allocated by the `RenderGraph` on compilation.
+    pub fn add_transient_image(
+        &mut self,
+        name: &str,
+        description: RenderGraphImageDescription,
+    ) -> RenderGraphResourceId {
+        self.add_transient_image_with_fallbacks(name, description, &[])
+    }
+
+    /// Declares a transient image like `add_transient_image`, which is created with the first of `fallback_formats`
+    /// supporting its usage if `description.format` doesn't.
+    pub fn add_transient_image_with_fallbacks(
+        &mut self,
+        name: &str,
+        description: RenderGraphImageDescription,
+        fallback_formats: &[Format],
+    ) -> RenderGraphResourceId {
+        self.add_resource(
+            name,
+            RenderGraphResource::TransientImage(description, fallback_formats.to_vec()),
+        )
+    }
+
+    /// Imports an image which is owned outside of the `RenderGraph`, e.g. a `SwapchainImage`.
+    pub fn import_image(
+        &mut self,
+        name: &str,
+        image_view: Arc<dyn ImageViewAbstract>,
+    ) -> RenderGraphResourceId {
+        self.add_resource(name, RenderGraphResource::ImportedImage(image_view))
+    }
+
+    /// Imports a buffer which is owned outside of the `RenderGraph`.
+    pub fn import_buffer(&mut self, name: &str, buffer: Subbuffer<[u8]>) -> RenderGraphResourceId {
+        self.add_resource(name, RenderGraphResource::ImportedBuffer(buffer))
+    }
+
+    fn add_resource(&mut self, name: &str, resource: RenderGraphResource) -> RenderGraphResourceId {
+        self.resource_names.push(name.to_string());
+        self.resources.push(resource);
+
+        RenderGraphResourceId(self.resources.len() - 1)
+    }
+
+    /// Adds a pass to the `RenderGraph`.
+    /// `reads` and `writes` must list every resource the `recorder` accesses, otherwise the pass may be ordered wrongly.
+    pub fn add_pass<F>(
+        &mut self,
+        name: &str,
+        kind: RenderGraphPassKind,
+        reads: &[RenderGraphResourceId],
+        writes: &[RenderGraphResourceId],
+        recorder: F,
+    ) where
+        F: Fn(&mut EngineCommandBufferBuilder, &RenderGraphResources) + Send + Sync + 'static,
+    {
+        for resource in reads.iter().chain(writes) {
+            assert!(
+                resource.0 < self.resources.len(),
+                "pass '{}' uses a resource of another render graph",
+                name
+            );
+        }
+
+        self.passes.push(RenderGraphPass {
+            name: name.to_string(),
+            kind,
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            recorder: Box::new(recorder),
+        });
+    }
+
+    /// Returns the name of a resource.
+    pub fn get_resource_name(&self, resource: RenderGraphResourceId) -> &str {
+        &self.resource_names[resource.0]
+    }
+
+    /// Returns the passes in the order they were added.
+    pub fn get_passes(&self) -> &[RenderGraphPass] {
+        &self.passes
+    }
+
+    /// Orders the passes by their dependencies and returns their indices in execution order.
+    /// A pass reading a resource runs after every pass writing it, no matter in which order they were added.
+    /// Passes writing the same resource, as well as independent passes, keep the order they were added in.
+    ///
+    /// Panics if the passes depend on each other in a cycle.
+    pub fn get_execution_order(&self) -> Vec<usize> {
+        let pass_count = self.passes.len();
+        let mut dependencies: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); pass_count];
+
+        for resource in 0..self.resources.len() {
+            let resource = RenderGraphResourceId(resource);
+            let writers: Vec<usize> = (0..pass_count)
+                .filter(|pass| self.passes[*pass].writes.contains(&resource))
+                .collect();
+            let readers: Vec<usize> = (0..pass_count)
+                .filter(|pass| self.passes[*pass].reads.contains(&resource))
+                .collect();
+
+            for pair in writers.windows(2) {
+                dependencies[pair[1]].insert(pair[0]);
+            }
+
+            // Passes reading and writing a resource are already ordered as writers
+            for reader in readers.iter().filter(|reader| !writers.contains(reader)) {
+                dependencies[*reader].extend(writers.iter().copied());
+            }
+        }
+
+        let mut order = Vec::with_capacity(pass_count);
+        let mut scheduled = vec![false; pass_count];
+        while order.len() < pass_count {
+            let next = (0..pass_count)
+                .find(|pass| {
+                    !scheduled[*pass]
+                        && dependencies[*pass]
+                            .iter()
+                            .all(|dependency| scheduled[*dependency])
+                })
+                .expect("render graph contains a dependency cycle");
+
+            scheduled[next] = true;
+            order.push(next);
+        }
+
+        order
+    }
+
+    /// Returns the lifetime of every resource as first and last position in the execution `order`, `None` if it's unused.
+    fn get_lifetimes(&self, order: &[usize]) -> Vec<Option<(usize, usize)>> {
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.resources.len()];
+        for (position, pass) in order.iter().enumerate() {
+            let pass = &self.passes[*pass];
+            for resource in pass.reads.iter().chain(&pass.writes) {
+                let lifetime = lifetimes[resource.0].get_or_insert((position, position));
+                lifetime.1 = position;
+            }
+        }
+
+        lifetimes
+    }
+
+    /// Compiles the `RenderGraph`: orders the passes and allocates all transient images.
+    /// Transient images with equal descriptions reuse the same physical image if their lifetimes don't overlap.
+    #[track_caller]
+    pub fn compile(
+        self,
+        memory_allocator: &StandardMemoryAllocator,
+        queue_family_index: u32,
+    ) -> CompiledRenderGraph {
+        log::debug!("RenderGraph::compile");
+
+        let order = self.get_execution_order();
+        let lifetimes = self.get_lifetimes(&order);
+
+        // Physical images as (description, last use) with their image views
+        let mut physical_images: Vec<(RenderGraphImageDescription, usize)> = Vec::new();
+        let mut physical_image_views: Vec<Arc<ImageView<StorageImage>>> = Vec::new();
+        // Names of the resources sharing each physical image
+        let mut physical_image_names: Vec<Vec<String>> = Vec::new();
+        let mut resources = Vec::with_capacity(self.resources.len());
+        for (index, resource) in self.resources.into_iter().enumerate() {
+            let resource = match resource {
+                RenderGraphResource::TransientImage(description, fallback_formats) => {
+                    let preferences = [vec![description.format], fallback_formats].concat();
+                    let format = find_supported_format(
+                        memory_allocator.device().physical_device(),
+                        &preferences,
+                        &FormatUsage::from_image_usage(description.usage),
+                    )
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "none of the formats {:?} of transient image '{}' supports {:?}",
+                            preferences, self.resource_names[index], description.usage
+                        )
+                    });
+                    if format != description.format {
+                        log::debug!(
+                            "Transient image '{}' falls back to {:?}",
+                            self.resource_names[index],
+                            format
+                        );
+                    }
+                    let description = RenderGraphImageDescription {
+                        format,
+                        ..description
+                    };
+
+                    let Some((first_use, last_use)) = lifetimes[index] else {
+                        log::warn!(
+                            "Transient image '{}' isn't used by any pass",
+                            self.resource_names[index]
+                        );
+                        resources.push(None);
+                        continue;
+                    };
+
+                    let reused = find_reusable_image(&physical_images, &description, first_use);
+
+                    let image_view = match reused {
+                        Some(physical) => {
+                            log::debug!(
+                                "Transient image '{}' reuses a previously used image",
+                                self.resource_names[index]
+                            );
+
+                            physical_images[physical].1 = last_use;
+                            physical_image_names[physical].push(self.resource_names[index].clone());
+                            physical_image_views[physical].clone()
+                        }
+                        None => {
+                            let image = StorageImage::with_usage(
+                                memory_allocator,
+                                description.dimensions,
+                                description.format,
+                                description.usage,
+                                ImageCreateFlags::empty(),
+                                Some(queue_family_index),
+                            )
+                            .expect("failed to create transient image");
+                            track_resource(ResourceKind::Image, image.inner().image);
+                            let image_view = ImageView::new_default(image)
+                                .expect("failed to create transient image view");
+
+                            physical_images.push((description, last_use));
+                            physical_image_views.push(image_view.clone());
+                            physical_image_names.push(vec![self.resource_names[index].clone()]);
+                            image_view
+                        }
+                    };
+
+                    Some(RenderGraphResource::AllocatedImage(image_view))
+                }
+                resource => Some(resource),
+            };
+
+            resources.push(resource);
+        }
+
+        // Reused images are named after all resources sharing them
+        for (image_view, names) in physical_image_views.iter().zip(&physical_image_names) {
+            set_debug_name(
+                memory_allocator.device(),
+                &**image_view.image().inner().image,
+                &names.join(" / "),
+            );
+        }
+
+        log::debug!(
+            "Render graph compiled: {} passes, {} physical transient images",
+            order.len(),
+            physical_images.len()
+        );
+
+        CompiledRenderGraph {
+            passes: self.passes,
+            order,
+            resources: RenderGraphResources {
+                resource_names: self.resource_names,
+                resources,
+            },
+        }
+    }
+}
+
+/// Returns the index of a physical image, given as description and last use, which a transient image first used at `first_use` can reuse:
+/// the first one with an equal description which isn't used anymore at that point.
+fn find_reusable_image(
+    physical_images: &[(RenderGraphImageDescription, usize)],
+    description: &RenderGraphImageDescription,
+    first_use: usize,
+) -> Option<usize> {
+    physical_images
+        .iter()
+        .position(|(other, other_last_use)| other == description && *other_last_use < first_use)
+}
+
+/// The resources of a compiled `RenderGraph`, handed to every pass while recording.
+pub struct RenderGraphResources {
+    resource_names: Vec<String>,
+    resources: Vec<Option<RenderGraphResource>>,
+}
+
+impl RenderGraphResources {
+    /// Returns the `ImageView` of an image resource.
+    pub fn get_image_view(&self, resource: RenderGraphResourceId) -> Arc<dyn ImageViewAbstract> {
+        match &self.resources[resource.0] {
+            Some(RenderGraphResource::AllocatedImage(image_view))
+            | Some(RenderGraphResource::ImportedImage(image_view)) => image_view.clone(),
+            _ => panic!(
+                "resource '{}' is not an allocated image",
+                self.resource_names[resource.0]
+            ),
+        }
+    }
+
+    /// Returns the `Subbuffer` of a buffer resource.
+    pub fn get_buffer(&self, resource: RenderGraphResourceId) -> Subbuffer<[u8]> {
+        match &self.resources[resource.0] {
+            Some(RenderGraphResource::ImportedBuffer(buffer)) => buffer.clone(),
+            _ => panic!(
+                "resource '{}' is not a buffer",
+                self.resource_names[resource.0]
+            ),
+        }
+    }
+}
+
+/// A `RenderGraph` with ordered passes and allocated transient images.
+/// It can be recorded and executed any number of times.
+pub struct CompiledRenderGraph {
+    passes: Vec<RenderGraphPass>,
+    order: Vec<usize>,
+    resources: RenderGraphResources,
+}
+
+impl CompiledRenderGraph {
+    /// Replaces an imported image, e.g. with the `SwapchainImage` acquired for the current frame.
+    pub fn set_imported_image(
+        &mut self,
+        resource: RenderGraphResourceId,
+        image_view: Arc<dyn ImageViewAbstract>,
+    ) {
+        assert!(
+            matches!(
+                self.resources.resources[resource.0],
+                Some(RenderGraphResource::ImportedImage(_))
+            ),
+            "resource '{}' is not an imported image",
+            self.resources.resource_names[resource.0]
+        );
+
+        self.resources.resources[resource.0] = Some(RenderGraphResource::ImportedImage(image_view));
+    }
+
+    /// Replaces an imported buffer.
+    pub fn set_imported_buffer(
+        &mut self,
+        resource: RenderGraphResourceId,
+        buffer: Subbuffer<[u8]>,
+    ) {
+        assert!(
+            matches!(
+                self.resources.resources[resource.0],
+                Some(RenderGraphResource::ImportedBuffer(_))
+            ),
+            "resource '{}' is not an imported buffer",
+            self.resources.resource_names[resource.0]
+        );
+
+        self.resources.resources[resource.0] = Some(RenderGraphResource::ImportedBuffer(buffer));
+    }
+
+    /// Returns the resources of the `RenderGraph`.
+    pub fn get_resources(&self) -> &RenderGraphResources {
+        &self.resources
+    }
+
+    /// Returns the passes in execution order.
+    pub fn get_ordered_passes(&self) -> Vec<&RenderGraphPass> {
+        self.order.iter().map(|pass| &self.passes[*pass]).collect()
+    }
+
+    /// Records all passes in execution order into a single `PrimaryAutoCommandBuffer`.
+    /// Use this to submit the graph yourself, e.g. joined with a `Swapchain` acquire future.
+    pub fn record<E>(&self, engine: &E) -> PrimaryAutoCommandBuffer
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &engine.get_command_buffer_allocator(),
+            engine.get_logical_device().get_queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+
+        for pass in self.get_ordered_passes() {
+            log::trace!("Recording {:?} pass '{}'", pass.kind, pass.name);
+            builder.label(&pass.name, [0.0; 4], |builder| {
+                (pass.recorder)(builder, &self.resources)
+            });
+        }
+
+        let command_buffer = builder.build().unwrap();
+        engine
+            .get_logical_device()
+            .set_debug_name(&command_buffer, "RenderGraph");
+
+        command_buffer
+    }
+
+    /// Records and executes all passes on a given engine and waits for them to finish.
+    pub fn execute<E>(&self, engine: &E)
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        engine.compute(&|engine: &E| self.record(engine));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn description(width: u32) -> RenderGraphImageDescription {
+        RenderGraphImageDescription {
+            dimensions: ImageDimensions::Dim2d {
+                width,
+                height: 16,
+                array_layers: 1,
+            },
+            format: Format::R16G16B16A16_SFLOAT,
+            usage: ImageUsage::STORAGE,
+        }
+    }
+
+    fn add_pass(
+        graph: &mut RenderGraph,
+        name: &str,
+        reads: &[RenderGraphResourceId],
+        writes: &[RenderGraphResourceId],
+    ) {
+        graph.add_pass(name, RenderGraphPassKind::Compute, reads, writes, |_, _| {});
+    }
+
+    #[test]
+    fn readers_run_after_writers() {
+        let mut graph = RenderGraph::new();
+        let gbuffer = graph.add_transient_image("GBuffer", description(16));
+        let lighting = graph.add_transient_image("Lighting", description(16));
+
+        add_pass(&mut graph, "Post", &[lighting], &[]);
+        add_pass(&mut graph, "Lighting", &[gbuffer], &[lighting]);
+        add_pass(&mut graph, "Independent", &[], &[]);
+        add_pass(&mut graph, "Geometry", &[], &[gbuffer]);
+
+        assert_eq!(graph.get_execution_order(), vec![2, 3, 1, 0]);
+    }
+
+    #[test]
+    fn writers_keep_their_order() {
+        let mut graph = RenderGraph::new();
+        let image = graph.add_transient_image("Image", description(16));
+
+        add_pass(&mut graph, "Read", &[image], &[]);
+        add_pass(&mut graph, "First Write", &[], &[image]);
+        add_pass(&mut graph, "Read Write", &[image], &[image]);
+        add_pass(&mut graph, "Last Write", &[], &[image]);
+
+        assert_eq!(graph.get_execution_order(), vec![1, 2, 3, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "dependency cycle")]
+    fn cycles_are_detected() {
+        let mut graph = RenderGraph::new();
+        let a = graph.add_transient_image("A", description(16));
+        let b = graph.add_transient_image("B", description(16));
+
+        add_pass(&mut graph, "A to B", &[a], &[b]);
+        add_pass(&mut graph, "B to A", &[b], &[a]);
+
+        graph.get_execution_order();
+    }
+
+    #[test]
+    fn lifetimes_follow_the_execution_order() {
+        let mut graph = RenderGraph::new();
+        let a = graph.add_transient_image("A", description(16));
+        let b = graph.add_transient_image("B", description(16));
+        graph.add_transient_image("Unused", description(16));
+
+        add_pass(&mut graph, "Read B", &[b], &[]);
+        add_pass(&mut graph, "Write A", &[], &[a]);
+        add_pass(&mut graph, "A to B", &[a], &[b]);
+
+        let order = graph.get_execution_order();
+        assert_eq!(order, vec![1, 2, 0]);
+        assert_eq!(
+            graph.get_lifetimes(&order),
+            vec![Some((0, 1)), Some((1, 2)), None]
+        );
+    }
+
+    #[test]
+    fn images_are_reused_after_their_last_use() {
+        let physical_images = [(description(16), 1), (description(32), 0)];
+
+        // Equal description, first used after the last use
+        assert_eq!(
+            find_reusable_image(&physical_images, &description(16), 2),
+            Some(0)
+        );
+        assert_eq!(
+            find_reusable_image(&physical_images, &description(32), 1),
+            Some(1)
+        );
+
+        // Overlapping lifetimes
+        assert_eq!(
+            find_reusable_image(&physical_images, &description(16), 1),
+            None
+        );
+
+        // Different descriptions never share an image
+        assert_eq!(
+            find_reusable_image(&physical_images, &description(64), 5),
+            None
+        );
+    }
+}
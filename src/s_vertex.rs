@@ -7,3 +7,27 @@ pub struct SVertex {
     #[format(R32G32_SFLOAT)]
     pub position: [f32; 2],
 }
+
+/// The vertex of a `Sprite` drawn by a `SpriteBatch`.
+/// `position` is in pixels, with the origin in the top left corner of the viewport.
+#[derive(Vertex, BufferContents, Zeroable, Copy, Clone, PartialEq, PartialOrd)]
+#[repr(C)]
+pub struct SSpriteVertex {
+    #[format(R32G32_SFLOAT)]
+    pub position: [f32; 2],
+    #[format(R32G32_SFLOAT)]
+    pub uv: [f32; 2],
+    #[format(R32G32B32A32_SFLOAT)]
+    pub color: [f32; 4],
+}
+
+/// The vertex of a line drawn by `DebugDraw`.
+/// `position` is in world space.
+#[derive(Vertex, BufferContents, Zeroable, Copy, Clone, PartialEq, PartialOrd)]
+#[repr(C)]
+pub struct SDebugVertex {
+    #[format(R32G32B32_SFLOAT)]
+    pub position: [f32; 3],
+    #[format(R32G32B32A32_SFLOAT)]
+    pub color: [f32; 4],
+}
@@ -0,0 +1,517 @@
+use std::{collections::HashMap, sync::Arc};
+
+use image::RgbaImage;
+use vulkano::{
+    buffer::{
+        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo},
+        Buffer, BufferContents, BufferCreateInfo, BufferUsage,
+    },
+    command_buffer::{
+        allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, CommandBufferUsage,
+        CopyBufferToImageInfo,
+    },
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    image::{
+        view::ImageView, ImageAccess, ImageCreateFlags, ImageDimensions, ImageUsage, StorageImage,
+    },
+    memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
+    pipeline::{
+        graphics::{
+            color_blend::ColorBlendState,
+            input_assembly::InputAssemblyState,
+            vertex_input::Vertex,
+            viewport::{Viewport, ViewportState},
+        },
+        GraphicsPipeline, Pipeline, PipelineBindPoint,
+    },
+    render_pass::{RenderPass, Subpass},
+    sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo},
+};
+
+use crate::{
+    convert_rgba8_pixels, track_resource, track_resource_once, AbstractEngine,
+    EngineCommandBufferBuilder, FormatUsage, GlslShaderKind, ResourceKind, SSpriteVertex,
+    SRGB_FORMAT_PREFERENCES,
+};
+
+/// A texture sprites can be drawn with, e.g. a single image or a `TextureAtlas`.
+pub struct SpriteTexture {
+    image_view: Arc<ImageView<StorageImage>>,
+    sampler: Arc<Sampler>,
+    dimensions: [u32; 2],
+}
+
+impl SpriteTexture {
+    /// Uploads an image into a new `SpriteTexture`.
+    /// The image is expected to be sRGB encoded, `filter` is used for magnification and minification.
+    /// The texture gets the first `SRGB_FORMAT_PREFERENCES` format which can be sampled.
+    #[track_caller]
+    pub fn from_image<E>(
+        engine: &E,
+        memory_allocator: &StandardMemoryAllocator,
+        image: &RgbaImage,
+        filter: Filter,
+    ) -> Arc<Self>
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        let dimensions = [image.width(), image.height()];
+        let format = engine
+            .get_logical_device()
+            .select_format(&SRGB_FORMAT_PREFERENCES, &[FormatUsage::Sampled]);
+
+        let texture_image = StorageImage::with_usage(
+            memory_allocator,
+            ImageDimensions::Dim2d {
+                width: dimensions[0],
+                height: dimensions[1],
+                array_layers: 1,
+            },
+            format,
+            ImageUsage::SAMPLED | ImageUsage::TRANSFER_DST,
+            ImageCreateFlags::empty(),
+            None,
+        )
+        .expect("failed to create sprite texture");
+        track_resource(ResourceKind::Image, texture_image.inner().image);
+
+        let mut pixels = image.as_raw().clone();
+        convert_rgba8_pixels(format, &mut pixels);
+
+        let upload_buffer = Buffer::from_iter(
+            memory_allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Upload,
+                ..Default::default()
+            },
+            pixels,
+        )
+        .expect("failed to create sprite texture upload buffer");
+        track_resource(ResourceKind::Buffer, upload_buffer.buffer());
+
+        engine.compute(&|engine: &E| {
+            let mut builder = AutoCommandBufferBuilder::primary(
+                &engine.get_command_buffer_allocator(),
+                engine.get_logical_device().get_queue_family_index(),
+                CommandBufferUsage::OneTimeSubmit,
+            )
+            .unwrap();
+
+            builder
+                .copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
+                    upload_buffer.clone(),
+                    texture_image.clone(),
+                ))
+                .unwrap();
+
+            builder.build().unwrap()
+        });
+
+        let sampler = Sampler::new(
+            engine.get_logical_device().get_device(),
+            SamplerCreateInfo {
+                mag_filter: filter,
+                min_filter: filter,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )
+        .expect("failed to create sprite sampler");
+
+        Arc::new(Self {
+            image_view: ImageView::new_default(texture_image)
+                .expect("failed to create sprite texture view"),
+            sampler,
+            dimensions,
+        })
+    }
+
+    /// Returns the dimensions of the texture in pixels.
+    pub fn get_dimensions(&self) -> [u32; 2] {
+        self.dimensions
+    }
+
+    /// Returns the `ImageView` of the texture.
+    pub fn get_image_view(&self) -> Arc<ImageView<StorageImage>> {
+        self.image_view.clone()
+    }
+
+    /// Returns the `Sampler` of the texture.
+    pub fn get_sampler(&self) -> Arc<Sampler> {
+        self.sampler.clone()
+    }
+
+    /// Returns a `SpriteRegion` covering the whole texture.
+    pub fn get_full_region(&self) -> SpriteRegion {
+        SpriteRegion {
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            size: [self.dimensions[0] as f32, self.dimensions[1] as f32],
+        }
+    }
+}
+
+/// A rectangular part of a `SpriteTexture`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteRegion {
+    /// The texture coordinates as `[u_min, v_min, u_max, v_max]`.
+    pub uv_rect: [f32; 4],
+    /// The size in pixels.
+    pub size: [f32; 2],
+}
+
+/// A `SpriteTexture` containing many named sprites, so they can be drawn without switching textures.
+pub struct TextureAtlas {
+    texture: Arc<SpriteTexture>,
+    regions: HashMap<String, SpriteRegion>,
+}
+
+impl TextureAtlas {
+    /// Creates a `TextureAtlas` without any regions.
+    pub fn new(texture: Arc<SpriteTexture>) -> Self {
+        Self {
+            texture,
+            regions: HashMap::new(),
+        }
+    }
+
+    /// Adds a named region given in pixels as `[x, y, width, height]`.
+    pub fn add_region(&mut self, name: &str, rect: [u32; 4]) {
+        let [width, height] = self.texture.get_dimensions().map(|value| value as f32);
+        let [x, y, region_width, region_height] = rect.map(|value| value as f32);
+
+        self.regions.insert(
+            name.to_string(),
+            SpriteRegion {
+                uv_rect: [
+                    x / width,
+                    y / height,
+                    (x + region_width) / width,
+                    (y + region_height) / height,
+                ],
+                size: [region_width, region_height],
+            },
+        );
+    }
+
+    /// Splits the whole texture into tiles of a given size and adds them row by row as `<prefix><index>`.
+    pub fn add_grid(&mut self, prefix: &str, tile_size: [u32; 2]) {
+        let [width, height] = self.texture.get_dimensions();
+        let columns = width / tile_size[0];
+        let rows = height / tile_size[1];
+
+        for row in 0..rows {
+            for column in 0..columns {
+                self.add_region(
+                    &format!("{}{}", prefix, row * columns + column),
+                    [
+                        column * tile_size[0],
+                        row * tile_size[1],
+                        tile_size[0],
+                        tile_size[1],
+                    ],
+                );
+            }
+        }
+    }
+
+    /// Returns a named region.
+    pub fn get_region(&self, name: &str) -> Option<SpriteRegion> {
+        self.regions.get(name).copied()
+    }
+
+    /// Returns the texture of the atlas.
+    pub fn get_texture(&self) -> &Arc<SpriteTexture> {
+        &self.texture
+    }
+}
+
+/// A textured quad drawn by a `SpriteBatch`.
+/// All positions and sizes are in pixels, with the origin in the top left corner of the viewport.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+    /// The position of the sprite's origin.
+    pub position: [f32; 2],
+    /// The unscaled size.
+    pub size: [f32; 2],
+    /// The point the sprite is positioned, rotated and scaled around, relative to its size.
+    /// `[0.0, 0.0]` is the top left, `[0.5, 0.5]` the center.
+    pub origin: [f32; 2],
+    /// The clockwise rotation in radians.
+    pub rotation: f32,
+    pub scale: [f32; 2],
+    /// The texture coordinates as `[u_min, v_min, u_max, v_max]`.
+    pub uv_rect: [f32; 4],
+    /// The color the texture is multiplied with.
+    pub tint: [f32; 4],
+    /// Sprites on higher layers are drawn on top of sprites on lower layers.
+    pub layer: i32,
+}
+
+impl Sprite {
+    /// Creates an untinted, unrotated `Sprite` showing the whole texture, with its origin in the center.
+    pub fn new(position: [f32; 2], size: [f32; 2]) -> Self {
+        Self {
+            position,
+            size,
+            origin: [0.5, 0.5],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            tint: [1.0, 1.0, 1.0, 1.0],
+            layer: 0,
+        }
+    }
+
+    /// Creates a `Sprite` showing a region of a texture in its original size.
+    pub fn from_region(region: SpriteRegion, position: [f32; 2]) -> Self {
+        Self {
+            uv_rect: region.uv_rect,
+            ..Self::new(position, region.size)
+        }
+    }
+
+    fn vertices(&self) -> [SSpriteVertex; 4] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let [u_min, v_min, u_max, v_max] = self.uv_rect;
+
+        [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]].map(|corner: [f32; 2]| {
+            let x = (corner[0] - self.origin[0]) * self.size[0] * self.scale[0];
+            let y = (corner[1] - self.origin[1]) * self.size[1] * self.scale[1];
+
+            SSpriteVertex {
+                position: [
+                    self.position[0] + x * cos - y * sin,
+                    self.position[1] + x * sin + y * cos,
+                ],
+                uv: [
+                    u_min + (u_max - u_min) * corner[0],
+                    v_min + (v_max - v_min) * corner[1],
+                ],
+                color: self.tint,
+            }
+        })
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct SpritePushConstants {
+    screen_size: [f32; 2],
+}
+
+/// Accumulates `Sprite`s over a frame and draws them with as few draw calls as possible.
+///
+/// On `flush`, sprites are sorted by layer and, inside a layer, by texture.
+/// Every run of sprites sharing a texture is drawn with a single draw call,
+/// so using a `TextureAtlas` for sprites on the same layer keeps the amount of draw calls low.
+/// Inside a layer, the draw order is only kept for sprites sharing a texture.
+pub struct SpriteBatch {
+    pipeline: Arc<GraphicsPipeline>,
+    buffer_allocator: SubbufferAllocator,
+    descriptor_set_allocator: StandardDescriptorSetAllocator,
+    textures: Vec<Arc<SpriteTexture>>,
+    sprites: Vec<(usize, Sprite)>,
+    draw_calls: u32,
+}
+
+impl SpriteBatch {
+    /// Creates a `SpriteBatch` drawing into the first subpass of a given `RenderPass`, e.g. from `GraphicalEngine::create_render_pass`.
+    #[track_caller]
+    pub fn new<E>(
+        engine: &E,
+        memory_allocator: Arc<StandardMemoryAllocator>,
+        render_pass: Arc<RenderPass>,
+    ) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        log::debug!("SpriteBatch::new");
+
+        let device = engine.get_logical_device();
+        let vertex_shader = device.create_glsl_shader_module(
+            include_str!("../shaders/sprite_batch/sprite.vert"),
+            GlslShaderKind::Vertex,
+        );
+        let fragment_shader = device.create_glsl_shader_module(
+            include_str!("../shaders/sprite_batch/sprite.frag"),
+            GlslShaderKind::Fragment,
+        );
+
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input_state(SSpriteVertex::per_vertex())
+            .vertex_shader(vertex_shader.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .fragment_shader(fragment_shader.entry_point("main").unwrap(), ())
+            .color_blend_state(ColorBlendState::new(1).blend_alpha())
+            .render_pass(Subpass::from(render_pass, 0).expect("render pass has no subpass"))
+            .build(device.get_device())
+            .expect("failed to create sprite pipeline");
+        track_resource(ResourceKind::Pipeline, &pipeline);
+        device.set_debug_name(&*pipeline, "SpriteBatch Pipeline");
+        E::validate_push_constants::<SpritePushConstants>(pipeline.layout());
+
+        Self {
+            pipeline,
+            buffer_allocator: SubbufferAllocator::new(
+                memory_allocator,
+                SubbufferAllocatorCreateInfo {
+                    buffer_usage: BufferUsage::VERTEX_BUFFER | BufferUsage::INDEX_BUFFER,
+                    memory_usage: MemoryUsage::Upload,
+                    ..Default::default()
+                },
+            ),
+            descriptor_set_allocator: StandardDescriptorSetAllocator::new(device.get_device()),
+            textures: Vec::new(),
+            sprites: Vec::new(),
+            draw_calls: 0,
+        }
+    }
+
+    /// Queues a `Sprite` to be drawn with a given texture on the next `flush`.
+    pub fn draw(&mut self, texture: &Arc<SpriteTexture>, sprite: Sprite) {
+        let texture_index = match self
+            .textures
+            .iter()
+            .position(|known| Arc::ptr_eq(known, texture))
+        {
+            Some(index) => index,
+            None => {
+                self.textures.push(texture.clone());
+                self.textures.len() - 1
+            }
+        };
+
+        self.sprites.push((texture_index, sprite));
+    }
+
+    /// Returns how many sprites are queued.
+    pub fn get_sprite_count(&self) -> usize {
+        self.sprites.len()
+    }
+
+    /// Returns how many draw calls the last `flush` recorded.
+    pub fn get_draw_calls(&self) -> u32 {
+        self.draw_calls
+    }
+
+    /// Discards all queued sprites.
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+        self.textures.clear();
+    }
+
+    /// Records all queued sprites and clears the batch.
+    /// Must be recorded inside the `RenderPass` the `SpriteBatch` was created with,
+    /// `dimensions` are the dimensions of the `Framebuffer` in pixels.
+    #[track_caller]
+    pub fn flush(&mut self, builder: &mut EngineCommandBufferBuilder, dimensions: [u32; 2]) {
+        self.draw_calls = 0;
+        if self.sprites.is_empty() {
+            self.clear();
+            return;
+        }
+
+        // Stable, so sprites sharing layer and texture keep their order
+        self.sprites
+            .sort_by_key(|(texture_index, sprite)| (sprite.layer, *texture_index));
+
+        let vertex_buffer = self
+            .buffer_allocator
+            .allocate_slice::<SSpriteVertex>(self.sprites.len() as u64 * 4)
+            .expect("failed to allocate sprite vertices");
+        track_resource_once(ResourceKind::Buffer, vertex_buffer.buffer());
+        let index_buffer = self
+            .buffer_allocator
+            .allocate_slice::<u32>(self.sprites.len() as u64 * 6)
+            .expect("failed to allocate sprite indices");
+        track_resource_once(ResourceKind::Buffer, index_buffer.buffer());
+        {
+            let mut vertices = vertex_buffer.write().unwrap();
+            let mut indices = index_buffer.write().unwrap();
+
+            for (i, (_, sprite)) in self.sprites.iter().enumerate() {
+                vertices[i * 4..i * 4 + 4].copy_from_slice(&sprite.vertices());
+
+                let base = i as u32 * 4;
+                indices[i * 6..i * 6 + 6].copy_from_slice(&[
+                    base,
+                    base + 1,
+                    base + 2,
+                    base + 2,
+                    base + 3,
+                    base,
+                ]);
+            }
+        }
+
+        let layout = self.pipeline.layout().clone();
+        builder
+            .set_viewport(
+                0,
+                [Viewport {
+                    origin: [0.0, 0.0],
+                    dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                    depth_range: 0.0..1.0,
+                }],
+            )
+            .bind_pipeline_graphics(self.pipeline.clone())
+            .bind_vertex_buffers(0, vertex_buffer)
+            .bind_index_buffer(index_buffer)
+            .push_constants(
+                layout.clone(),
+                0,
+                SpritePushConstants {
+                    screen_size: [dimensions[0] as f32, dimensions[1] as f32],
+                },
+            );
+
+        let mut run_start = 0;
+        while run_start < self.sprites.len() {
+            let texture_index = self.sprites[run_start].0;
+            let run_end = self.sprites[run_start..]
+                .iter()
+                .position(|(index, _)| *index != texture_index)
+                .map_or(self.sprites.len(), |length| run_start + length);
+
+            let texture = &self.textures[texture_index];
+            let set = PersistentDescriptorSet::new(
+                &self.descriptor_set_allocator,
+                layout.set_layouts()[0].clone(),
+                [
+                    WriteDescriptorSet::image_view(0, texture.get_image_view()),
+                    WriteDescriptorSet::sampler(1, texture.get_sampler()),
+                ],
+            )
+            .expect("failed to create sprite descriptor set");
+            track_resource(ResourceKind::DescriptorSet, &set);
+
+            builder
+                .bind_descriptor_sets(PipelineBindPoint::Graphics, layout.clone(), 0, set)
+                .draw_indexed(
+                    (run_end - run_start) as u32 * 6,
+                    1,
+                    run_start as u32 * 6,
+                    0,
+                    0,
+                )
+                .expect("failed to draw sprites");
+
+            self.draw_calls += 1;
+            run_start = run_end;
+        }
+
+        log::trace!(
+            "Flushed {} sprites in {} draw calls",
+            self.sprites.len(),
+            self.draw_calls
+        );
+        self.clear();
+    }
+}
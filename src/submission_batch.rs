@@ -0,0 +1,206 @@
+use std::{sync::Arc, time::Duration};
+
+use vulkano::{
+    command_buffer::{
+        allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, CommandBufferUsage,
+        PrimaryAutoCommandBuffer, PrimaryCommandBufferAbstract, SemaphoreSubmitInfo, SubmitInfo,
+    },
+    device::{DeviceOwned, Queue},
+    sync::{
+        fence::{Fence, FenceCreateInfo},
+        semaphore::Semaphore,
+        PipelineStages,
+    },
+};
+
+use crate::{AbstractEngine, EngineCommandBufferBuilder, EngineError};
+
+/// Identifies a submission of a `SubmissionBatch`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubmissionId(usize);
+
+/// A `SubmissionBatch` collects several command buffers with the semaphores between them
+/// and submits all of them to a queue at once with a single `vkQueueSubmit`.
+///
+/// Every command buffer is its own submission. Dependencies between submissions of the batch are expressed with
+/// `add_dependency`, dependencies on batches of other queues (e.g. of `AbstractEngine::create_compute_submission_batch`)
+/// with `signal_semaphore` and `wait_semaphore`.
+/// Resources shared between queue families must be created with concurrent sharing.
+///
+/// ```ignore
+/// let mut batch = engine.create_submission_batch();
+/// let simulate = batch.record(&engine, |builder| simulation.record(builder));
+/// let shade = batch.record(&engine, |builder| shading.record(builder));
+/// batch.add_dependency(shade, simulate);
+/// batch.submit()?.wait(None)?;
+/// ```
+pub struct SubmissionBatch {
+    queue: Arc<Queue>,
+    submissions: Vec<SubmitInfo>,
+    /// Semaphores created by the batch, kept alive until it completed.
+    semaphores: Vec<Arc<Semaphore>>,
+}
+
+impl SubmissionBatch {
+    /// Creates an empty `SubmissionBatch` submitting to the given queue.
+    pub fn new(queue: Arc<Queue>) -> Self {
+        Self {
+            queue,
+            submissions: Vec::new(),
+            semaphores: Vec::new(),
+        }
+    }
+
+    /// Returns the queue the batch is submitted to.
+    pub fn get_queue(&self) -> Arc<Queue> {
+        self.queue.clone()
+    }
+
+    /// Adds a command buffer as a new submission.
+    /// It must have been recorded for the queue family of the batch's queue.
+    pub fn add(&mut self, command_buffer: PrimaryAutoCommandBuffer) -> SubmissionId {
+        // Owning the command buffer guarantees it hasn't been and won't be submitted anywhere else
+        let command_buffer: Arc<dyn PrimaryCommandBufferAbstract> = Arc::new(command_buffer);
+        self.submissions.push(SubmitInfo {
+            command_buffers: vec![command_buffer],
+            ..Default::default()
+        });
+
+        SubmissionId(self.submissions.len() - 1)
+    }
+
+    /// Records a one-time command buffer with the allocator and the queue family of the engine and adds it as a new submission.
+    pub fn record<E>(
+        &mut self,
+        engine: &E,
+        record: impl FnOnce(&mut EngineCommandBufferBuilder),
+    ) -> SubmissionId
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &engine.get_command_buffer_allocator(),
+            self.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .expect("failed to create command buffer builder");
+
+        record(&mut builder);
+
+        self.add(builder.build().expect("failed to build command buffer"))
+    }
+
+    /// Makes `submission` wait for `depends_on` to complete, including the visibility of all of its writes.
+    /// `depends_on` has to be added before `submission`.
+    pub fn add_dependency(&mut self, submission: SubmissionId, depends_on: SubmissionId) {
+        assert!(
+            depends_on < submission,
+            "submissions can only depend on submissions added before them"
+        );
+
+        let semaphore = self.signal_semaphore(depends_on);
+        self.wait_semaphore(submission, semaphore, PipelineStages::ALL_COMMANDS);
+    }
+
+    /// Returns a new semaphore signaled once `submission` completed, e.g. to be waited for by a batch of another queue.
+    /// The semaphore can only be waited for once, by a batch submitted after this one.
+    pub fn signal_semaphore(&mut self, submission: SubmissionId) -> Arc<Semaphore> {
+        let semaphore = Arc::new(
+            Semaphore::from_pool(self.queue.device().clone()).expect("failed to create semaphore"),
+        );
+        self.semaphores.push(semaphore.clone());
+        self.submissions[submission.0]
+            .signal_semaphores
+            .push(SemaphoreSubmitInfo::semaphore(semaphore.clone()));
+
+        semaphore
+    }
+
+    /// Makes `stages` of `submission` wait for a semaphore, e.g. one from `signal_semaphore` of a batch of another queue.
+    /// The semaphore's signal has to be submitted before this batch.
+    pub fn wait_semaphore(
+        &mut self,
+        submission: SubmissionId,
+        semaphore: Arc<Semaphore>,
+        stages: PipelineStages,
+    ) {
+        self.submissions[submission.0]
+            .wait_semaphores
+            .push(SemaphoreSubmitInfo {
+                stages,
+                ..SemaphoreSubmitInfo::semaphore(semaphore)
+            });
+    }
+
+    /// Returns the amount of submissions.
+    pub fn len(&self) -> usize {
+        self.submissions.len()
+    }
+
+    /// Whether no command buffer was added yet.
+    pub fn is_empty(&self) -> bool {
+        self.submissions.is_empty()
+    }
+
+    /// Submits all submissions with a single `vkQueueSubmit` and returns a fence signaled once all of them completed.
+    /// Resources used by the batch must not be written by other work which is still pending on the GPU,
+    /// as vulkano's access checks of `GpuFuture`s don't apply to batches.
+    pub fn submit(self) -> Result<SubmissionFence, EngineError> {
+        log::debug!(
+            "SubmissionBatch::submit: {} submissions",
+            self.submissions.len()
+        );
+
+        let fence = Arc::new(
+            Fence::new(self.queue.device().clone(), FenceCreateInfo::default())
+                .expect("failed to create fence"),
+        );
+
+        // Safety: the command buffers are owned by the batch and hence never submitted before,
+        // every semaphore of `add_dependency` is signaled by an earlier submission and waited for exactly once.
+        // The queue keeps the submissions and their resources alive and locked until the fence is waited for.
+        self.queue.with(|mut queue| unsafe {
+            queue.submit_unchecked(self.submissions, Some(fence.clone()))
+        })?;
+
+        Ok(SubmissionFence {
+            fence,
+            semaphores: self.semaphores,
+            completed: false,
+        })
+    }
+}
+
+/// Signaled once all submissions of a submitted `SubmissionBatch` completed.
+/// Dropping it blocks until then, like vulkano's `FenceSignalFuture`.
+pub struct SubmissionFence {
+    fence: Arc<Fence>,
+    semaphores: Vec<Arc<Semaphore>>,
+    completed: bool,
+}
+
+impl SubmissionFence {
+    /// Whether all submissions completed, without blocking.
+    pub fn is_complete(&self) -> Result<bool, EngineError> {
+        Ok(self.completed || self.fence.is_signaled()?)
+    }
+
+    /// Blocks until all submissions completed or the timeout elapsed, which is reported as `EngineError::Timeout`.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> Result<(), EngineError> {
+        if !self.completed {
+            self.fence.wait(timeout)?;
+            self.completed = true;
+            self.semaphores.clear();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for SubmissionFence {
+    fn drop(&mut self) {
+        if let Err(e) = self.wait(None) {
+            log::error!("failed to wait for submission batch: {}", e);
+        }
+    }
+}
@@ -0,0 +1,671 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use ab_glyph::{Font as _, FontVec, GlyphId, InvalidFont, PxScale, ScaleFont as _};
+use image::{Rgba, RgbaImage};
+use vulkano::{
+    buffer::{
+        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo},
+        BufferContents, BufferUsage,
+    },
+    command_buffer::allocator::StandardCommandBufferAllocator,
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    memory::allocator::{MemoryUsage, StandardMemoryAllocator},
+    pipeline::{
+        graphics::{
+            color_blend::ColorBlendState,
+            input_assembly::InputAssemblyState,
+            vertex_input::Vertex,
+            viewport::{Viewport, ViewportState},
+        },
+        GraphicsPipeline, Pipeline, PipelineBindPoint,
+    },
+    render_pass::{RenderPass, Subpass},
+    sampler::Filter,
+};
+
+use crate::{
+    track_resource, track_resource_once, AbstractEngine, EngineCommandBufferBuilder,
+    GlslShaderKind, ResourceKind, SSpriteVertex, SpriteTexture,
+};
+
+/// A TrueType or OpenType font.
+pub struct Font {
+    font: FontVec,
+}
+
+impl Font {
+    /// Parses a font from the contents of a TTF or OTF file.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, InvalidFont> {
+        Ok(Self {
+            font: FontVec::try_from_vec(data)?,
+        })
+    }
+
+    /// Loads a font from a TTF or OTF file.
+    pub fn load<P>(path: P) -> std::io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_bytes(std::fs::read(path)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// How glyphs are stored inside a `FontAtlas`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlyphRasterization {
+    /// The coverage of every pixel. Looks best when drawn at the rasterized size.
+    Bitmap,
+    /// The distance to the outline, up to `spread` pixels.
+    /// Stays sharp when scaled up, e.g. for text in world space.
+    SignedDistanceField { spread: u32 },
+}
+
+/// How the lines of a `TextLayout` are aligned to each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// Options for `FontAtlas::layout_text`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextLayoutOptions {
+    pub alignment: TextAlignment,
+    /// Lines are broken at whitespace to not exceed this width in pixels.
+    /// Lines are also aligned inside this width instead of the widest line.
+    pub max_width: Option<f32>,
+    /// Multiplier of the font's line height.
+    pub line_spacing: f32,
+    /// Multiplier of the rasterized size of the `FontAtlas`.
+    pub scale: f32,
+}
+
+impl Default for TextLayoutOptions {
+    fn default() -> Self {
+        Self {
+            alignment: TextAlignment::Left,
+            max_width: None,
+            line_spacing: 1.0,
+            scale: 1.0,
+        }
+    }
+}
+
+/// The placement of a rasterized glyph inside the atlas and relative to the pen position on the baseline.
+#[derive(Clone, Copy, Debug)]
+struct GlyphQuad {
+    offset: [f32; 2],
+    size: [f32; 2],
+    uv_rect: [f32; 4],
+}
+
+#[derive(Clone, Copy, Debug)]
+struct AtlasGlyph {
+    id: GlyphId,
+    advance: f32,
+    /// `None` for glyphs without an outline, e.g. whitespace.
+    quad: Option<GlyphQuad>,
+}
+
+/// A glyph of a `TextLayout`, positioned relative to the top left corner of the text.
+#[derive(Clone, Copy, Debug)]
+struct PositionedGlyph {
+    position: [f32; 2],
+    quad: GlyphQuad,
+}
+
+/// Positioned glyphs of a string, created by `FontAtlas::layout_text` and drawn by a `TextRenderer`.
+/// Positions are in pixels relative to the top left corner of the text.
+#[derive(Clone, Debug)]
+pub struct TextLayout {
+    glyphs: Vec<PositionedGlyph>,
+    size: [f32; 2],
+    scale: f32,
+}
+
+impl TextLayout {
+    /// Returns the size of the text in pixels.
+    pub fn get_size(&self) -> [f32; 2] {
+        self.size
+    }
+
+    /// Returns how many glyph quads the text consists of.
+    pub fn get_glyph_count(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    fn vertices(
+        &self,
+        origin: [f32; 2],
+        color: [f32; 4],
+    ) -> impl Iterator<Item = SSpriteVertex> + '_ {
+        self.glyphs.iter().flat_map(move |glyph| {
+            let x = origin[0] + glyph.position[0] + glyph.quad.offset[0] * self.scale;
+            let y = origin[1] + glyph.position[1] + glyph.quad.offset[1] * self.scale;
+            let [width, height] = glyph.quad.size.map(|value| value * self.scale);
+            let [u_min, v_min, u_max, v_max] = glyph.quad.uv_rect;
+
+            [
+                ([x, y], [u_min, v_min]),
+                ([x + width, y], [u_max, v_min]),
+                ([x + width, y + height], [u_max, v_max]),
+                ([x + width, y + height], [u_max, v_max]),
+                ([x, y + height], [u_min, v_max]),
+                ([x, y], [u_min, v_min]),
+            ]
+            .map(|(position, uv)| SSpriteVertex {
+                position,
+                uv,
+                color,
+            })
+        })
+    }
+}
+
+/// A GPU texture containing a set of glyphs of a `Font`, rasterized at a fixed pixel size.
+pub struct FontAtlas {
+    font: Arc<Font>,
+    pixel_size: f32,
+    rasterization: GlyphRasterization,
+    glyphs: HashMap<char, AtlasGlyph>,
+    texture: Arc<SpriteTexture>,
+}
+
+impl FontAtlas {
+    /// The printable ASCII characters, enough for most debug text and FPS counters.
+    pub const ASCII_CHARACTERS: &'static str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+    /// Rasterizes the given characters of a font at `pixel_size` (the height of a line) and uploads them.
+    #[track_caller]
+    pub fn new<E>(
+        engine: &E,
+        memory_allocator: &StandardMemoryAllocator,
+        font: Arc<Font>,
+        pixel_size: f32,
+        characters: &str,
+        rasterization: GlyphRasterization,
+    ) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        log::debug!("FontAtlas::new");
+
+        let scale = PxScale::from(pixel_size);
+        let scaled_font = font.font.as_scaled(scale);
+        let padding = match rasterization {
+            GlyphRasterization::Bitmap => 0,
+            GlyphRasterization::SignedDistanceField { spread } => spread,
+        };
+
+        // Rasterize every glyph into its own bitmap first, so they can be packed afterwards
+        let mut bitmaps = Vec::new();
+        let mut glyphs = HashMap::new();
+        for character in characters.chars() {
+            if glyphs.contains_key(&character) {
+                continue;
+            }
+
+            let id = font.font.glyph_id(character);
+            let mut glyph = AtlasGlyph {
+                id,
+                advance: scaled_font.h_advance(id),
+                quad: None,
+            };
+
+            if let Some(outline) = font.font.outline_glyph(id.with_scale(scale)) {
+                let bounds = outline.px_bounds();
+                let width = bounds.width() as u32 + 2 * padding;
+                let height = bounds.height() as u32 + 2 * padding;
+
+                let mut coverage = vec![0.0; (width * height) as usize];
+                outline.draw(|x, y, value| {
+                    coverage[((y + padding) * width + x + padding) as usize] = value;
+                });
+
+                let alpha = match rasterization {
+                    GlyphRasterization::Bitmap => coverage,
+                    GlyphRasterization::SignedDistanceField { spread } => {
+                        Self::signed_distance_field(&coverage, width, height, spread)
+                    }
+                };
+
+                glyph.quad = Some(GlyphQuad {
+                    offset: [bounds.min.x - padding as f32, bounds.min.y - padding as f32],
+                    size: [width as f32, height as f32],
+                    uv_rect: [0.0; 4],
+                });
+                bitmaps.push((character, width, height, alpha));
+            }
+
+            glyphs.insert(character, glyph);
+        }
+
+        // Pack the bitmaps row by row into a roughly square atlas, keeping a pixel between glyphs against bleeding
+        let area: u32 = bitmaps
+            .iter()
+            .map(|(_, width, height, _)| (width + 1) * (height + 1))
+            .sum();
+        let widest = bitmaps
+            .iter()
+            .map(|(_, width, _, _)| width + 1)
+            .max()
+            .unwrap_or(1);
+        let atlas_width = ((area as f32).sqrt().ceil() as u32)
+            .max(widest)
+            .next_power_of_two();
+
+        let mut placements = Vec::with_capacity(bitmaps.len());
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for (_, width, height, _) in &bitmaps {
+            if x + width > atlas_width {
+                x = 0;
+                y += row_height + 1;
+                row_height = 0;
+            }
+
+            placements.push((x, y));
+            x += width + 1;
+            row_height = row_height.max(*height);
+        }
+        let atlas_height = (y + row_height).max(1);
+
+        let mut image = RgbaImage::from_pixel(atlas_width, atlas_height, Rgba([255, 255, 255, 0]));
+        for ((character, width, height, alpha), (x, y)) in bitmaps.iter().zip(placements) {
+            for row in 0..*height {
+                for column in 0..*width {
+                    let value = alpha[(row * width + column) as usize];
+                    image.get_pixel_mut(x + column, y + row).0[3] =
+                        (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+            }
+
+            if let Some(quad) = glyphs
+                .get_mut(character)
+                .and_then(|glyph| glyph.quad.as_mut())
+            {
+                quad.uv_rect = [
+                    x as f32 / atlas_width as f32,
+                    y as f32 / atlas_height as f32,
+                    (x + width) as f32 / atlas_width as f32,
+                    (y + height) as f32 / atlas_height as f32,
+                ];
+            }
+        }
+
+        log::debug!(
+            "Rasterized {} glyphs into a {}x{} font atlas",
+            glyphs.len(),
+            atlas_width,
+            atlas_height
+        );
+
+        Self {
+            font,
+            pixel_size,
+            rasterization,
+            glyphs,
+            texture: SpriteTexture::from_image(engine, memory_allocator, &image, Filter::Linear),
+        }
+    }
+
+    /// Converts a coverage bitmap into a signed distance field, mapping the outline to `0.5`.
+    fn signed_distance_field(coverage: &[f32], width: u32, height: u32, spread: u32) -> Vec<f32> {
+        let spread = spread.max(1) as i32;
+        let is_inside = |x: i32, y: i32| coverage[(y as u32 * width + x as u32) as usize] >= 0.5;
+
+        (0..height as i32)
+            .flat_map(|y| (0..width as i32).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let inside = is_inside(x, y);
+
+                let mut distance = spread as f32;
+                for search_y in (y - spread).max(0)..(y + spread + 1).min(height as i32) {
+                    for search_x in (x - spread).max(0)..(x + spread + 1).min(width as i32) {
+                        if is_inside(search_x, search_y) != inside {
+                            let dx = (search_x - x) as f32;
+                            let dy = (search_y - y) as f32;
+                            // The outline is halfway between the two pixels
+                            distance = distance.min((dx * dx + dy * dy).sqrt() - 0.5);
+                        }
+                    }
+                }
+
+                let signed_distance = if inside { distance } else { -distance };
+                0.5 + signed_distance / (2.0 * spread as f32)
+            })
+            .collect()
+    }
+
+    /// Returns the pixel size the glyphs were rasterized at.
+    pub fn get_pixel_size(&self) -> f32 {
+        self.pixel_size
+    }
+
+    /// Returns how the glyphs are stored.
+    pub fn get_rasterization(&self) -> GlyphRasterization {
+        self.rasterization
+    }
+
+    /// Returns the texture containing the glyphs.
+    pub fn get_texture(&self) -> &Arc<SpriteTexture> {
+        &self.texture
+    }
+
+    /// Returns the distance between two baselines at a given scale, without line spacing.
+    pub fn get_line_height(&self, scale: f32) -> f32 {
+        let scaled_font = self.font.font.as_scaled(PxScale::from(self.pixel_size));
+
+        (scaled_font.ascent() - scaled_font.descent() + scaled_font.line_gap()) * scale
+    }
+
+    /// Lays out a string with kerning, breaking lines at `\n` and, if a `max_width` is set, at whitespace.
+    /// Characters missing in the atlas advance the pen but aren't drawn.
+    pub fn layout_text(&self, text: &str, options: &TextLayoutOptions) -> TextLayout {
+        let scaled_font = self.font.font.as_scaled(PxScale::from(self.pixel_size));
+        let scale = options.scale;
+        let max_width = options.max_width.map(|width| width / scale);
+
+        // Lines in unscaled pixels, relative to the start of the line on the baseline
+        let mut lines: Vec<(Vec<PositionedGlyph>, f32)> = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut glyphs: Vec<PositionedGlyph> = Vec::new();
+            let mut pen = 0.0;
+            let mut width = 0.0;
+            let mut previous: Option<GlyphId> = None;
+            // The first glyph after the last whitespace, the width before it and the pen after it
+            let mut break_opportunity: Option<(usize, f32, f32)> = None;
+
+            for character in paragraph.chars() {
+                let id = self
+                    .glyphs
+                    .get(&character)
+                    .map_or_else(|| self.font.font.glyph_id(character), |glyph| glyph.id);
+                if let Some(previous) = previous {
+                    pen += scaled_font.kern(previous, id);
+                }
+                previous = Some(id);
+
+                if character.is_whitespace() {
+                    break_opportunity =
+                        Some((glyphs.len(), width, pen + scaled_font.h_advance(id)));
+                    pen += scaled_font.h_advance(id);
+                    continue;
+                }
+
+                let glyph = self.glyphs.get(&character);
+                let advance =
+                    glyph.map_or_else(|| scaled_font.h_advance(id), |glyph| glyph.advance);
+
+                if let (Some(max_width), Some((index, line_width, word_start))) =
+                    (max_width, break_opportunity)
+                {
+                    if pen + advance > max_width {
+                        let mut word = glyphs.split_off(index);
+                        for glyph in &mut word {
+                            glyph.position[0] -= word_start;
+                        }
+
+                        lines.push((std::mem::replace(&mut glyphs, word), line_width));
+                        pen -= word_start;
+                        break_opportunity = None;
+                    }
+                }
+
+                if let Some(quad) = glyph.and_then(|glyph| glyph.quad) {
+                    glyphs.push(PositionedGlyph {
+                        position: [pen, 0.0],
+                        quad,
+                    });
+                }
+
+                pen += advance;
+                width = pen;
+            }
+
+            lines.push((glyphs, width));
+        }
+
+        let text_width =
+            max_width.unwrap_or_else(|| lines.iter().map(|(_, width)| *width).fold(0.0, f32::max));
+        let line_height = self.get_line_height(1.0) * options.line_spacing;
+
+        let mut glyphs = Vec::new();
+        for (i, (line, width)) in lines.iter().enumerate() {
+            let alignment_offset = match options.alignment {
+                TextAlignment::Left => 0.0,
+                TextAlignment::Center => (text_width - width) / 2.0,
+                TextAlignment::Right => text_width - width,
+            };
+            let baseline = scaled_font.ascent() + i as f32 * line_height;
+
+            glyphs.extend(line.iter().map(|glyph| PositionedGlyph {
+                position: [
+                    (glyph.position[0] + alignment_offset) * scale,
+                    baseline * scale,
+                ],
+                quad: glyph.quad,
+            }));
+        }
+
+        TextLayout {
+            glyphs,
+            size: [
+                text_width * scale,
+                (scaled_font.ascent() - scaled_font.descent()
+                    + (lines.len() - 1) as f32 * line_height)
+                    * scale,
+            ],
+            scale,
+        }
+    }
+}
+
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct TextPushConstants {
+    transform: [[f32; 4]; 4],
+    signed_distance_field: u32,
+}
+
+/// Queued text sharing a `FontAtlas` and a transform, drawn with a single draw call.
+struct TextDraw {
+    atlas: Arc<FontAtlas>,
+    /// `None` for screen space
+    transform: Option<[[f32; 4]; 4]>,
+    vertices: Vec<SSpriteVertex>,
+}
+
+/// Draws `TextLayout`s in screen or world space.
+///
+/// Text is queued with `draw_text` or `draw_text_in_world` and recorded with `flush`.
+/// Consecutive text using the same `FontAtlas` and transform is drawn with a single draw call.
+pub struct TextRenderer {
+    pipeline: Arc<GraphicsPipeline>,
+    buffer_allocator: SubbufferAllocator,
+    descriptor_set_allocator: StandardDescriptorSetAllocator,
+    draws: Vec<TextDraw>,
+    draw_calls: u32,
+}
+
+impl TextRenderer {
+    /// Creates a `TextRenderer` drawing into the first subpass of a given `RenderPass`, e.g. from `GraphicalEngine::create_render_pass`.
+    #[track_caller]
+    pub fn new<E>(
+        engine: &E,
+        memory_allocator: Arc<StandardMemoryAllocator>,
+        render_pass: Arc<RenderPass>,
+    ) -> Self
+    where
+        E: AbstractEngine<StandardCommandBufferAllocator>,
+    {
+        log::debug!("TextRenderer::new");
+
+        let device = engine.get_logical_device();
+        let vertex_shader = device.create_glsl_shader_module(
+            include_str!("../shaders/text/text.vert"),
+            GlslShaderKind::Vertex,
+        );
+        let fragment_shader = device.create_glsl_shader_module(
+            include_str!("../shaders/text/text.frag"),
+            GlslShaderKind::Fragment,
+        );
+
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input_state(SSpriteVertex::per_vertex())
+            .vertex_shader(vertex_shader.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .fragment_shader(fragment_shader.entry_point("main").unwrap(), ())
+            .color_blend_state(ColorBlendState::new(1).blend_alpha())
+            .render_pass(Subpass::from(render_pass, 0).expect("render pass has no subpass"))
+            .build(device.get_device())
+            .expect("failed to create text pipeline");
+        track_resource(ResourceKind::Pipeline, &pipeline);
+        device.set_debug_name(&*pipeline, "TextRenderer Pipeline");
+        E::validate_push_constants::<TextPushConstants>(pipeline.layout());
+
+        Self {
+            pipeline,
+            buffer_allocator: SubbufferAllocator::new(
+                memory_allocator,
+                SubbufferAllocatorCreateInfo {
+                    buffer_usage: BufferUsage::VERTEX_BUFFER,
+                    memory_usage: MemoryUsage::Upload,
+                    ..Default::default()
+                },
+            ),
+            descriptor_set_allocator: StandardDescriptorSetAllocator::new(device.get_device()),
+            draws: Vec::new(),
+            draw_calls: 0,
+        }
+    }
+
+    /// Queues text to be drawn in screen space, with its top left corner at `position` in pixels.
+    pub fn draw_text(
+        &mut self,
+        atlas: &Arc<FontAtlas>,
+        layout: &TextLayout,
+        position: [f32; 2],
+        color: [f32; 4],
+    ) {
+        self.queue(atlas, None, layout.vertices(position, color));
+    }
+
+    /// Queues text to be drawn in world space.
+    /// `transform` is a column-major matrix mapping the pixels of the layout into clip space,
+    /// usually `projection * view * model`, where `model` also scales pixels into world units.
+    pub fn draw_text_in_world(
+        &mut self,
+        atlas: &Arc<FontAtlas>,
+        layout: &TextLayout,
+        transform: [[f32; 4]; 4],
+        color: [f32; 4],
+    ) {
+        self.queue(atlas, Some(transform), layout.vertices([0.0, 0.0], color));
+    }
+
+    fn queue<I>(&mut self, atlas: &Arc<FontAtlas>, transform: Option<[[f32; 4]; 4]>, vertices: I)
+    where
+        I: Iterator<Item = SSpriteVertex>,
+    {
+        match self.draws.last_mut() {
+            Some(draw) if Arc::ptr_eq(&draw.atlas, atlas) && draw.transform == transform => {
+                draw.vertices.extend(vertices);
+            }
+            _ => self.draws.push(TextDraw {
+                atlas: atlas.clone(),
+                transform,
+                vertices: vertices.collect(),
+            }),
+        }
+    }
+
+    /// Returns how many draw calls the last `flush` recorded.
+    pub fn get_draw_calls(&self) -> u32 {
+        self.draw_calls
+    }
+
+    /// Discards all queued text.
+    pub fn clear(&mut self) {
+        self.draws.clear();
+    }
+
+    /// Records all queued text and clears the queue.
+    /// Must be recorded inside the `RenderPass` the `TextRenderer` was created with,
+    /// `dimensions` are the dimensions of the `Framebuffer` in pixels.
+    #[track_caller]
+    pub fn flush(&mut self, builder: &mut EngineCommandBufferBuilder, dimensions: [u32; 2]) {
+        self.draw_calls = 0;
+
+        let [width, height] = dimensions.map(|value| value as f32);
+        let screen_transform = [
+            [2.0 / width, 0.0, 0.0, 0.0],
+            [0.0, 2.0 / height, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [-1.0, -1.0, 0.0, 1.0],
+        ];
+        let layout = self.pipeline.layout().clone();
+
+        builder
+            .set_viewport(
+                0,
+                [Viewport {
+                    origin: [0.0, 0.0],
+                    dimensions: [width, height],
+                    depth_range: 0.0..1.0,
+                }],
+            )
+            .bind_pipeline_graphics(self.pipeline.clone());
+
+        for draw in self.draws.drain(..) {
+            if draw.vertices.is_empty() {
+                continue;
+            }
+
+            let vertex_buffer = self
+                .buffer_allocator
+                .allocate_slice::<SSpriteVertex>(draw.vertices.len() as u64)
+                .expect("failed to allocate text vertices");
+            track_resource_once(ResourceKind::Buffer, vertex_buffer.buffer());
+            vertex_buffer
+                .write()
+                .unwrap()
+                .copy_from_slice(&draw.vertices);
+
+            let texture = draw.atlas.get_texture();
+            let set = PersistentDescriptorSet::new(
+                &self.descriptor_set_allocator,
+                layout.set_layouts()[0].clone(),
+                [
+                    WriteDescriptorSet::image_view(0, texture.get_image_view()),
+                    WriteDescriptorSet::sampler(1, texture.get_sampler()),
+                ],
+            )
+            .expect("failed to create text descriptor set");
+            track_resource(ResourceKind::DescriptorSet, &set);
+
+            builder
+                .bind_descriptor_sets(PipelineBindPoint::Graphics, layout.clone(), 0, set)
+                .bind_vertex_buffers(0, vertex_buffer)
+                .push_constants(
+                    layout.clone(),
+                    0,
+                    TextPushConstants {
+                        transform: draw.transform.unwrap_or(screen_transform),
+                        signed_distance_field: matches!(
+                            draw.atlas.get_rasterization(),
+                            GlyphRasterization::SignedDistanceField { .. }
+                        ) as u32,
+                    },
+                )
+                .draw(draw.vertices.len() as u32, 1, 0, 0)
+                .expect("failed to draw text");
+
+            self.draw_calls += 1;
+        }
+    }
+}
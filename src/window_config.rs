@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use vulkano::{instance::Instance, swapchain::Surface};
+use vulkano_win::VkSurfaceBuild;
+use winit::{
+    dpi::LogicalSize,
+    event_loop::EventLoopWindowTarget,
+    monitor::{MonitorHandle, VideoMode},
+    window::{CursorGrabMode, Fullscreen, Window, WindowBuilder},
+};
+
+/// Fullscreen mode of a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowFullscreen {
+    Windowed,
+    /// A borderless window covering the monitor, keeps the desktop video mode.
+    Borderless,
+    /// Takes over the monitor with its largest video mode.
+    Exclusive,
+}
+
+/// Monitor a window is placed on when going fullscreen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowMonitor {
+    Primary,
+    /// The monitor the window currently is on, the primary one while creating the window.
+    Current,
+    /// Index into the available monitors.
+    Index(usize),
+    /// First monitor whose name contains the given string.
+    Name(String),
+}
+
+impl WindowMonitor {
+    /// Finds the monitor, falling back to the primary monitor if it isn't available.
+    pub(crate) fn resolve(
+        &self,
+        available: Vec<MonitorHandle>,
+        primary: Option<MonitorHandle>,
+        current: Option<MonitorHandle>,
+    ) -> Option<MonitorHandle> {
+        let monitor = match self {
+            WindowMonitor::Primary => primary.clone(),
+            WindowMonitor::Current => current.or_else(|| primary.clone()),
+            WindowMonitor::Index(index) => available.get(*index).cloned(),
+            WindowMonitor::Name(name) => available.into_iter().find(|monitor| {
+                monitor
+                    .name()
+                    .is_some_and(|monitor_name| monitor_name.contains(name.as_str()))
+            }),
+        };
+
+        monitor.or_else(|| {
+            log::warn!("Monitor {:?} not found, using the primary monitor", self);
+            primary
+        })
+    }
+}
+
+impl WindowFullscreen {
+    /// Converts into winit's fullscreen mode on the given monitor.
+    pub(crate) fn to_winit(self, monitor: Option<MonitorHandle>) -> Option<Fullscreen> {
+        match self {
+            WindowFullscreen::Windowed => None,
+            WindowFullscreen::Borderless => Some(Fullscreen::Borderless(monitor)),
+            WindowFullscreen::Exclusive => match monitor.as_ref().and_then(best_video_mode) {
+                Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                None => {
+                    log::warn!("No video mode for exclusive fullscreen, using borderless");
+                    Some(Fullscreen::Borderless(monitor))
+                }
+            },
+        }
+    }
+}
+
+/// Largest resolution, then highest refresh rate and bit depth.
+fn best_video_mode(monitor: &MonitorHandle) -> Option<VideoMode> {
+    monitor.video_modes().max_by_key(|video_mode| {
+        let size = video_mode.size();
+        (
+            size.width as u64 * size.height as u64,
+            video_mode.refresh_rate_millihertz(),
+            video_mode.bit_depth(),
+        )
+    })
+}
+
+/// Grabs and shows or hides the cursor. Falls back to confining if locking isn't supported.
+pub(crate) fn apply_cursor(window: &Window, grab: CursorGrabMode, visible: bool) {
+    if let Err(error) = window.set_cursor_grab(grab) {
+        if grab == CursorGrabMode::Locked {
+            log::warn!("Cursor can't be locked ({}), confining it instead", error);
+            if let Err(error) = window.set_cursor_grab(CursorGrabMode::Confined) {
+                log::warn!("Cursor can't be confined: {}", error);
+            }
+        } else {
+            log::warn!("Cursor can't be grabbed: {}", error);
+        }
+    }
+    window.set_cursor_visible(visible);
+}
+
+/// Settings of the window created by `GraphicalEngine::with_window_config`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowConfig {
+    pub title: String,
+    /// Inner size in logical pixels, scaled by the DPI of the monitor.
+    pub size: [u32; 2],
+    pub resizable: bool,
+    /// Waits for the vertical blank when presenting, otherwise presents without tearing
+    /// if the surface supports it (mailbox) or immediately.
+    pub vsync: bool,
+    pub fullscreen: WindowFullscreen,
+    pub monitor: WindowMonitor,
+    pub cursor_grab: CursorGrabMode,
+    pub cursor_visible: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "Vulkan Engine".to_string(),
+            size: [1024, 768],
+            resizable: true,
+            vsync: true,
+            fullscreen: WindowFullscreen::Windowed,
+            monitor: WindowMonitor::Primary,
+            cursor_grab: CursorGrabMode::None,
+            cursor_visible: true,
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Creates the window and its `Surface`.
+    pub fn build_surface<T>(
+        &self,
+        instance: Arc<Instance>,
+        event_loop: &EventLoopWindowTarget<T>,
+    ) -> Arc<Surface> {
+        log::debug!("WindowConfig::build_surface");
+
+        let monitor = self.monitor.resolve(
+            event_loop.available_monitors().collect(),
+            event_loop.primary_monitor(),
+            None,
+        );
+
+        let surface = WindowBuilder::new()
+            .with_title(self.title.clone())
+            .with_inner_size(LogicalSize::new(self.size[0], self.size[1]))
+            .with_resizable(self.resizable)
+            .with_fullscreen(self.fullscreen.to_winit(monitor))
+            .build_vk_surface(event_loop, instance)
+            .expect("failed to create window surface");
+
+        let window = surface
+            .object()
+            .expect("surface has no window")
+            .downcast_ref::<Window>()
+            .expect("surface wasn't created from a winit window");
+        apply_cursor(window, self.cursor_grab, self.cursor_visible);
+
+        surface
+    }
+}
@@ -0,0 +1,59 @@
+//! Runs workloads through a `ComputeCluster` of a single engine, which works on any Vulkan implementation, e.g. lavapipe in CI.
+
+use vulkan_engine::{AbstractEngine, ComputeCluster, ComputeEngine};
+use vulkano::{
+    buffer::{Buffer, BufferCreateInfo, BufferUsage},
+    command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage},
+    memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
+};
+
+#[test]
+fn run_jobs_keeps_job_order() {
+    let cluster = ComputeCluster::from_engines(vec![ComputeEngine::new()]);
+    let jobs = (0..16).collect::<Vec<u32>>();
+
+    // Every job fills a buffer with a value derived from the job on the GPU and reads it back
+    let report = cluster.run_jobs(jobs, |engine, job| {
+        let memory_allocator =
+            StandardMemoryAllocator::new_default(engine.get_logical_device().get_device());
+        let buffer = Buffer::new_slice::<u32>(
+            &memory_allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Download,
+                ..Default::default()
+            },
+            4,
+        )
+        .expect("failed to create buffer");
+
+        engine.compute(&|engine: &ComputeEngine| {
+            let mut builder = AutoCommandBufferBuilder::primary(
+                &engine.get_command_buffer_allocator(),
+                engine.get_logical_device().get_queue_family_index(),
+                CommandBufferUsage::OneTimeSubmit,
+            )
+            .unwrap();
+
+            builder.fill_buffer(buffer.clone(), job * 10).unwrap();
+
+            builder.build().unwrap()
+        });
+
+        let content = buffer.read().unwrap();
+        assert!(content.iter().all(|value| *value == job * 10));
+
+        content[0]
+    });
+
+    assert_eq!(
+        report.results,
+        (0..16).map(|job| job * 10).collect::<Vec<_>>()
+    );
+    assert_eq!(report.timings.len(), 1);
+    assert_eq!(report.timings[0].engine_index, 0);
+    assert_eq!(report.timings[0].work, 0..16);
+}
@@ -0,0 +1,293 @@
+//! Renders the scenes of the image producing examples headlessly and compares them against the checked in golden images.
+//! Runs on any Vulkan implementation, e.g. lavapipe in CI.
+//!
+//! Set `VULKAN_ENGINE_BLESS=1` to update the golden images after an intended change.
+
+use std::{path::PathBuf, sync::Arc};
+
+use vulkan_engine::{
+    AbstractEngine, ComputeEngine, FormatUsage, FrameCapture, GoldenImage, SVertex,
+    COLOR_FORMAT_PREFERENCES,
+};
+use vulkano::{
+    buffer::{Buffer, BufferCreateInfo, BufferUsage},
+    command_buffer::{
+        AutoCommandBufferBuilder, ClearColorImageInfo, CommandBufferUsage, RenderPassBeginInfo,
+        SubpassContents,
+    },
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    format::{ClearColorValue, Format},
+    image::{view::ImageView, ImageAccess, ImageDimensions, StorageImage},
+    memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
+    pipeline::{
+        graphics::{
+            input_assembly::InputAssemblyState,
+            vertex_input::Vertex,
+            viewport::{Viewport, ViewportState},
+        },
+        GraphicsPipeline, Pipeline, PipelineBindPoint,
+    },
+    render_pass::{Framebuffer, FramebufferCreateInfo, Subpass},
+    single_pass_renderpass,
+};
+
+mod shader_graphical_pipeline_vertex {
+    vulkano_shaders::shader! {ty: "vertex", path: "shaders/005_graphical_pipeline.vert"}
+}
+
+mod shader_graphical_pipeline_fragment {
+    vulkano_shaders::shader! {ty: "fragment", path: "shaders/005_graphical_pipeline.frag"}
+}
+
+mod shader_mandelbrot {
+    vulkano_shaders::shader! {ty: "compute", path: "shaders/006_mandelbrot_image.comp"}
+}
+
+const IMAGE_SIZE: u32 = 1024;
+
+fn golden_image(name: &str) -> GoldenImage {
+    GoldenImage::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(name))
+}
+
+/// Creates the image a scene is rendered into, in the first of the preferred formats supporting it.
+/// `FrameCapture` converts BGRA fallbacks back to RGBA.
+fn create_target_image(
+    compute_engine: &ComputeEngine,
+    memory_allocator: &StandardMemoryAllocator,
+    preferences: &[Format],
+) -> Arc<StorageImage> {
+    // StorageImage::new makes the image usable as storage, color attachment and sampled image
+    let format = compute_engine.get_logical_device().select_format(
+        preferences,
+        &[
+            FormatUsage::Storage,
+            FormatUsage::ColorAttachment,
+            FormatUsage::Sampled,
+        ],
+    );
+
+    StorageImage::new(
+        memory_allocator,
+        ImageDimensions::Dim2d {
+            width: IMAGE_SIZE,
+            height: IMAGE_SIZE,
+            array_layers: 1,
+        },
+        format,
+        Some(compute_engine.get_logical_device().get_queue_family_index()),
+    )
+    .expect("failed to create image")
+}
+
+#[test]
+fn create_image() {
+    let compute_engine = ComputeEngine::new();
+    let memory_allocator =
+        StandardMemoryAllocator::new_default(compute_engine.get_logical_device().get_device());
+    let image = create_target_image(
+        &compute_engine,
+        &memory_allocator,
+        &COLOR_FORMAT_PREFERENCES,
+    );
+
+    compute_engine.compute(&|engine: &ComputeEngine| {
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &engine.get_command_buffer_allocator(),
+            engine.get_logical_device().get_queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+
+        builder
+            .clear_color_image(ClearColorImageInfo {
+                clear_value: ClearColorValue::Float([0.0, 0.0, 1.0, 1.0]),
+                ..ClearColorImageInfo::image(image.clone())
+            })
+            .unwrap();
+
+        builder.build().unwrap()
+    });
+
+    let capture = FrameCapture::capture(&compute_engine, &memory_allocator, image);
+    golden_image("004_create_image.png").assert_matches(&capture.to_rgba_image());
+}
+
+#[test]
+fn graphical_pipeline() {
+    let compute_engine = ComputeEngine::new();
+    let device = compute_engine.get_logical_device().get_device();
+    let memory_allocator = StandardMemoryAllocator::new_default(device.clone());
+    let image = create_target_image(
+        &compute_engine,
+        &memory_allocator,
+        &COLOR_FORMAT_PREFERENCES,
+    );
+
+    let vertex_buffer = Buffer::from_iter(
+        &memory_allocator,
+        BufferCreateInfo {
+            usage: BufferUsage::VERTEX_BUFFER,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            usage: MemoryUsage::Upload,
+            ..Default::default()
+        },
+        [
+            SVertex {
+                position: [-0.5, -0.5],
+            },
+            SVertex {
+                position: [0.0, 0.5],
+            },
+            SVertex {
+                position: [0.5, -0.25],
+            },
+        ],
+    )
+    .unwrap();
+
+    let render_pass = single_pass_renderpass!(
+        device.clone(),
+        attachments: {
+            color: {
+                load: Clear,
+                store: Store,
+                format: image.format(),
+                samples: 1,
+            }
+        },
+        pass: {
+            color: [color],
+            depth_stencil: {}
+        }
+    )
+    .unwrap();
+
+    let framebuffer = Framebuffer::new(
+        render_pass.clone(),
+        FramebufferCreateInfo {
+            attachments: vec![ImageView::new_default(image.clone()).unwrap()],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let vertex_shader = shader_graphical_pipeline_vertex::load(device.clone())
+        .expect("failed to create vertex shader module");
+    let fragment_shader = shader_graphical_pipeline_fragment::load(device.clone())
+        .expect("failed to create fragment shader module");
+    let pipeline = GraphicsPipeline::start()
+        .vertex_input_state(SVertex::per_vertex())
+        .vertex_shader(vertex_shader.entry_point("main").unwrap(), ())
+        .input_assembly_state(InputAssemblyState::new())
+        .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([
+            Viewport {
+                origin: [0.0, 0.0],
+                dimensions: [IMAGE_SIZE as f32, IMAGE_SIZE as f32],
+                depth_range: 0.0..1.0,
+            },
+        ]))
+        .fragment_shader(fragment_shader.entry_point("main").unwrap(), ())
+        .render_pass(Subpass::from(render_pass, 0).unwrap())
+        .build(device)
+        .unwrap();
+
+    compute_engine.compute(&|engine: &ComputeEngine| {
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &engine.get_command_buffer_allocator(),
+            engine.get_logical_device().get_queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+
+        builder
+            .begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![Some([0.0, 0.0, 1.0, 1.0].into())],
+                    ..RenderPassBeginInfo::framebuffer(framebuffer.clone())
+                },
+                SubpassContents::Inline,
+            )
+            .unwrap()
+            .bind_pipeline_graphics(pipeline.clone())
+            .bind_vertex_buffers(0, vertex_buffer.clone())
+            .draw(3, 1, 0, 0)
+            .unwrap()
+            .end_render_pass()
+            .unwrap();
+
+        builder.build().unwrap()
+    });
+
+    let capture = FrameCapture::capture(&compute_engine, &memory_allocator, image);
+    golden_image("005_graphical_pipeline.png").assert_matches(&capture.to_rgba_image());
+}
+
+#[test]
+fn mandelbrot_image() {
+    let compute_engine = ComputeEngine::new();
+    let device = compute_engine.get_logical_device().get_device();
+    let memory_allocator = StandardMemoryAllocator::new_default(device.clone());
+    let descriptor_set_allocator = StandardDescriptorSetAllocator::new(device.clone());
+    // The shader declares the image as `rgba8`, so R8G8B8A8_UNORM is the only usable storage format
+    let image = create_target_image(
+        &compute_engine,
+        &memory_allocator,
+        &[Format::R8G8B8A8_UNORM],
+    );
+
+    let shader = shader_mandelbrot::load(device).expect("failed to create shader module");
+    let compute_pipeline = compute_engine
+        .create_compute_pipeline::<shader_mandelbrot::PushConstants, _>(
+            shader,
+            &shader_mandelbrot::SpecializationConstants {
+                max_iterations: 200,
+            },
+        );
+
+    let set = PersistentDescriptorSet::new(
+        &descriptor_set_allocator,
+        compute_pipeline.layout().set_layouts()[0].clone(),
+        [WriteDescriptorSet::image_view(
+            0,
+            ImageView::new_default(image.clone()).unwrap(),
+        )],
+    )
+    .expect("failed to create descriptor set");
+
+    compute_engine.compute(&|engine: &ComputeEngine| {
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &engine.get_command_buffer_allocator(),
+            engine.get_logical_device().get_queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+
+        builder
+            .bind_pipeline_compute(compute_pipeline.clone())
+            .bind_descriptor_sets(
+                PipelineBindPoint::Compute,
+                compute_pipeline.layout().clone(),
+                0,
+                set.clone(),
+            )
+            .push_constants(
+                compute_pipeline.layout().clone(),
+                0,
+                shader_mandelbrot::PushConstants {
+                    center: [-1.0, 0.0],
+                    zoom: 1.0,
+                },
+            )
+            .dispatch([IMAGE_SIZE / 8, IMAGE_SIZE / 8, 1])
+            .unwrap();
+
+        builder.build().unwrap()
+    });
+
+    let capture = FrameCapture::capture(&compute_engine, &memory_allocator, image);
+    golden_image("006_mandelbrot_image.png").assert_matches(&capture.to_rgba_image());
+}
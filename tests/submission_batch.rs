@@ -0,0 +1,215 @@
+//! Chains dependent compute stages with `SubmissionBatch`es and checks their result.
+//! Runs on any Vulkan implementation, e.g. lavapipe in CI.
+
+use std::sync::Arc;
+
+use vulkan_engine::{AbstractEngine, ComputeEngine, EngineCommandBufferBuilder, GlslShaderKind};
+use vulkano::{
+    buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer},
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator},
+    pipeline::{ComputePipeline, Pipeline, PipelineBindPoint},
+    sync::{PipelineStages, Sharing},
+};
+
+const COUNT: u32 = 256;
+
+/// The first stage writes the index of every element.
+const FILL_SHADER: &str = "
+#version 450
+
+layout(local_size_x = 64) in;
+
+layout(set = 0, binding = 0) buffer Data {
+    uint values[];
+};
+
+void main() {
+    uint index = gl_GlobalInvocationID.x;
+    values[index] = index;
+}
+";
+
+/// The second stage transforms the values of the first one.
+const TRANSFORM_SHADER: &str = "
+#version 450
+
+layout(local_size_x = 64) in;
+
+layout(set = 0, binding = 0) buffer Data {
+    uint values[];
+};
+
+void main() {
+    uint index = gl_GlobalInvocationID.x;
+    values[index] = values[index] * 2 + 1;
+}
+";
+
+struct Stage {
+    pipeline: Arc<ComputePipeline>,
+    set: Arc<PersistentDescriptorSet>,
+}
+
+impl Stage {
+    fn new(
+        engine: &ComputeEngine,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        source: &str,
+        buffer: Subbuffer<[u32]>,
+    ) -> Self {
+        let shader = engine
+            .get_logical_device()
+            .create_glsl_shader_module(source, GlslShaderKind::Compute);
+        let pipeline = engine.create_compute_pipeline::<(), _>(shader, &());
+        let set = PersistentDescriptorSet::new(
+            descriptor_set_allocator,
+            pipeline.layout().set_layouts()[0].clone(),
+            [WriteDescriptorSet::buffer(0, buffer)],
+        )
+        .expect("failed to create descriptor set");
+
+        Self { pipeline, set }
+    }
+
+    fn record(&self, builder: &mut EngineCommandBufferBuilder) {
+        builder
+            .bind_pipeline_compute(self.pipeline.clone())
+            .bind_descriptor_sets(
+                PipelineBindPoint::Compute,
+                self.pipeline.layout().clone(),
+                0,
+                self.set.clone(),
+            )
+            .dispatch([COUNT / 64, 1, 1])
+            .expect("failed to dispatch stage");
+    }
+}
+
+/// Creates the buffer both stages work on, shared between the given queue families.
+fn create_buffer(
+    memory_allocator: &StandardMemoryAllocator,
+    mut queue_family_indices: Vec<u32>,
+) -> Subbuffer<[u32]> {
+    queue_family_indices.dedup();
+    let sharing = if queue_family_indices.len() > 1 {
+        Sharing::Concurrent(queue_family_indices.into())
+    } else {
+        Sharing::Exclusive
+    };
+
+    Buffer::new_slice::<u32>(
+        memory_allocator,
+        BufferCreateInfo {
+            sharing,
+            usage: BufferUsage::STORAGE_BUFFER,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            usage: MemoryUsage::Download,
+            ..Default::default()
+        },
+        COUNT as u64,
+    )
+    .expect("failed to create buffer")
+}
+
+fn assert_transformed(buffer: &Subbuffer<[u32]>) {
+    let content = buffer.read().expect("buffer is still in use by the GPU");
+
+    assert_eq!(
+        &content[..],
+        (0..COUNT).map(|index| index * 2 + 1).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn dependent_stages_in_one_batch() {
+    let engine = ComputeEngine::new();
+    let device = engine.get_logical_device().get_device();
+    let memory_allocator = StandardMemoryAllocator::new_default(device.clone());
+    let descriptor_set_allocator = StandardDescriptorSetAllocator::new(device);
+
+    let buffer = create_buffer(
+        &memory_allocator,
+        vec![engine.get_logical_device().get_queue_family_index()],
+    );
+    let fill = Stage::new(
+        &engine,
+        &descriptor_set_allocator,
+        FILL_SHADER,
+        buffer.clone(),
+    );
+    let transform = Stage::new(
+        &engine,
+        &descriptor_set_allocator,
+        TRANSFORM_SHADER,
+        buffer.clone(),
+    );
+
+    let mut batch = engine.create_submission_batch();
+    let filled = batch.record(&engine, |builder| fill.record(builder));
+    let transformed = batch.record(&engine, |builder| transform.record(builder));
+    batch.add_dependency(transformed, filled);
+    batch
+        .submit()
+        .expect("failed to submit batch")
+        .wait(None)
+        .expect("failed to wait for batch");
+
+    assert_transformed(&buffer);
+}
+
+#[test]
+fn dependent_stages_across_queues() {
+    let engine = ComputeEngine::new();
+    let mut compute_batch = match engine.create_compute_submission_batch() {
+        Some(compute_batch) => compute_batch,
+        None => {
+            log::warn!("Skipped, the device has no additional compute queue");
+            return;
+        }
+    };
+    let device = engine.get_logical_device().get_device();
+    let memory_allocator = StandardMemoryAllocator::new_default(device.clone());
+    let descriptor_set_allocator = StandardDescriptorSetAllocator::new(device);
+
+    let buffer = create_buffer(
+        &memory_allocator,
+        vec![
+            engine.get_logical_device().get_queue_family_index(),
+            compute_batch.get_queue().queue_family_index(),
+        ],
+    );
+    let fill = Stage::new(
+        &engine,
+        &descriptor_set_allocator,
+        FILL_SHADER,
+        buffer.clone(),
+    );
+    let transform = Stage::new(
+        &engine,
+        &descriptor_set_allocator,
+        TRANSFORM_SHADER,
+        buffer.clone(),
+    );
+
+    // The fill runs on the first queue, the transform on the compute queue once it's signaled
+    let mut batch = engine.create_submission_batch();
+    let filled = batch.record(&engine, |builder| fill.record(builder));
+    let semaphore = batch.signal_semaphore(filled);
+    let mut fill_fence = batch.submit().expect("failed to submit batch");
+
+    let transformed = compute_batch.record(&engine, |builder| transform.record(builder));
+    compute_batch.wait_semaphore(transformed, semaphore, PipelineStages::COMPUTE_SHADER);
+    compute_batch
+        .submit()
+        .expect("failed to submit compute batch")
+        .wait(None)
+        .expect("failed to wait for compute batch");
+    fill_fence.wait(None).expect("failed to wait for batch");
+
+    assert_transformed(&buffer);
+}